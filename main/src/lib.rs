@@ -10,18 +10,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#![forbid(unsafe_code)]
 
 pub mod preprocessing;
 
-pub use preprocessing::vocab::{base_vocab::BaseVocab, bert_vocab::BertVocab, openai_gpt_vocab::OpenAiGptVocab, gpt2_vocab::Gpt2Vocab, roberta_vocab::RobertaVocab};
+pub use preprocessing::vocab::{base_vocab::BaseVocab, bert_vocab::BertVocab, openai_gpt_vocab::OpenAiGptVocab, gpt2_vocab::Gpt2Vocab, roberta_vocab::RobertaVocab,
+                              albert_vocab::AlbertVocab, xlnet_vocab::XLNetVocab, t5_vocab::T5Vocab, xlm_roberta_vocab::XLMRobertaVocab};
+pub use preprocessing::vocab::sentence_piece_model::{SentencePieceModel, SentencePiece, SentencePieceType, SentencePieceModelType};
 pub use preprocessing::tokenizer::bert_tokenizer;
 pub use preprocessing::tokenizer::tokenization_utils;
 pub use crate::preprocessing::tokenizer::bert_tokenizer::BertTokenizer;
-pub use crate::preprocessing::tokenizer::base_tokenizer::{MultiThreadedTokenizer, TruncationStrategy, TokenizedInput, Tokenizer};
-pub use crate::preprocessing::vocab::base_vocab::Vocab;
+pub use crate::preprocessing::tokenizer::base_tokenizer::{MultiThreadedTokenizer, TruncationStrategy, PaddingStrategy, PaddingSide, TokenizedInput, Tokenizer, Offset, Token};
+pub use crate::preprocessing::vocab::base_vocab::{Vocab, SpecialTokenMap};
+pub use crate::preprocessing::error::TokenizerError;
 pub use crate::preprocessing::tokenizer::ctrl_tokenizer::CtrlTokenizer;
 pub use crate::preprocessing::tokenizer::gpt2_tokenizer::Gpt2Tokenizer;
 pub use crate::preprocessing::tokenizer::roberta_tokenizer::RobertaTokenizer;
 pub use crate::preprocessing::tokenizer::openai_gpt_tokenizer::OpenAiGptTokenizer;
+pub use crate::preprocessing::tokenizer::albert_tokenizer::AlbertTokenizer;
+pub use crate::preprocessing::tokenizer::xlnet_tokenizer::XLNetTokenizer;
+pub use crate::preprocessing::tokenizer::t5_tokenizer::T5Tokenizer;
+pub use crate::preprocessing::tokenizer::xlm_roberta_tokenizer::XLMRobertaTokenizer;
+pub use crate::preprocessing::tokenizer::bpe_cache::{BpeCache, BpeCacheStats};
+pub use crate::preprocessing::tokenizer::special_token_matcher::SpecialTokenMatcher;
+pub use crate::preprocessing::tokenizer::template_processor::{TemplateProcessor, TemplatePiece, TemplateTokenizer};
+pub use crate::preprocessing::tokenizer::tokenizer_json::PretrainedTokenizer;
+pub use crate::preprocessing::trainer::wordpiece_trainer::WordPieceTrainer;
+pub use crate::preprocessing::trainer::bpe_trainer::BpeTrainer;
 
 #[macro_use] extern crate lazy_static;
@@ -9,27 +9,480 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use rust_tokenizers;
-use rust_tokenizers::preprocessing::vocab::base_vocab::Vocab;
-use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, Tokenizer};
+use rust_tokenizers::{Vocab, Tokenizer, MultiThreadedTokenizer, TruncationStrategy, PaddingStrategy, PaddingSide, TokenizedInput,
+                      TokenizerError, BertTokenizer, CtrlTokenizer, Gpt2Tokenizer, RobertaTokenizer, OpenAiGptTokenizer};
+use serde_json::{json, Value};
 use std::env;
-use rust_tokenizers::preprocessing::adapters::Example;
-use std::sync::Arc;
-use rust_tokenizers::BertTokenizer;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: rust_tokenizers_bin <COMMAND> --tokenizer <NAME> --vocab <PATH> [--merges <PATH>] [OPTIONS] [FILE]...
+
+Reads one example per line from the files, or from the standard input if no file is given,
+and writes one JSON object per line to the standard output.
+
+Commands:
+  tokenize                   Splits each line into tokens
+  encode                     Encodes each line, or each pair of tab-separated texts with --pairs
+  decode                     Decodes each line of token ids, given as a JSON array, as the output of
+                             `encode` or separated by whitespaces
+  inspect-vocab              Prints the size and the special tokens of the vocabulary
+
+Options:
+  --tokenizer <NAME>         One of `bert`, `ctrl`, `gpt2`, `roberta` or `openai-gpt`
+  --vocab <PATH>             Vocabulary file
+  --merges <PATH>            Merges file, required by all tokenizers but `bert`
+  --lower-case               Lower cases the inputs
+  --max-len <N>              Maximum length of the encodings [default: 512]
+  --truncation <STRATEGY>    One of `longest_first`, `only_first`, `only_second`, `longest_first_left`,
+                             `only_first_left`, `only_second_left`, `head_tail` or `do_not_truncate` [default: longest_first]
+  --head <N>                 Number of tokens kept from the beginning of the sequence by `head_tail`, the remaining
+                             tokens being taken from its end [default: 0]
+  --stride <N>               Number of overflowing tokens overlapping the encoding [default: 0]
+  --pairs                    Reads pairs of tab-separated texts when encoding
+  --skip-special-tokens      Removes the special tokens when decoding
+  --no-clean-up              Keeps the spaces before punctuation when decoding
+  -h, --help                 Prints this message";
+
+/// Number of lines processed in parallel at once
+const BATCH_SIZE: usize = 1024;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Tokenize,
+    Encode,
+    Decode,
+    InspectVocab,
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenizerType {
+    Bert,
+    Ctrl,
+    Gpt2,
+    Roberta,
+    OpenAiGpt,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    tokenizer_type: TokenizerType,
+    vocab_path: String,
+    merges_path: Option<String>,
+    lower_case: bool,
+    max_len: usize,
+    truncation_strategy: TruncationStrategy,
+    stride: usize,
+    pairs: bool,
+    skip_special_tokens: bool,
+    clean_up_tokenization_spaces: bool,
+    input_paths: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let command = match args.next().map(String::as_str) {
+        Some("tokenize") => Command::Tokenize,
+        Some("encode") => Command::Encode,
+        Some("decode") => Command::Decode,
+        Some("inspect-vocab") => Command::InspectVocab,
+        Some(command) => return Err(format!("unknown command `{}`", command)),
+        None => return Err("missing command".to_owned())
+    };
+
+    let mut tokenizer_type = None;
+    let mut vocab_path = None;
+    let mut merges_path = None;
+    let mut lower_case = false;
+    let mut max_len = 512;
+    let mut truncation_strategy = "longest_first";
+    let mut head = 0;
+    let mut stride = 0;
+    let mut pairs = false;
+    let mut skip_special_tokens = false;
+    let mut clean_up_tokenization_spaces = true;
+    let mut input_paths = vec!();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tokenizer" => tokenizer_type = Some(parse_tokenizer_type(flag_value(&mut args, arg)?)?),
+            "--vocab" => vocab_path = Some(flag_value(&mut args, arg)?.to_owned()),
+            "--merges" => merges_path = Some(flag_value(&mut args, arg)?.to_owned()),
+            "--lower-case" => lower_case = true,
+            "--max-len" => max_len = parse_number(flag_value(&mut args, arg)?, arg)?,
+            "--truncation" => truncation_strategy = flag_value(&mut args, arg)?,
+            "--head" => head = parse_number(flag_value(&mut args, arg)?, arg)?,
+            "--stride" => stride = parse_number(flag_value(&mut args, arg)?, arg)?,
+            "--pairs" => pairs = true,
+            "--skip-special-tokens" => skip_special_tokens = true,
+            "--no-clean-up" => clean_up_tokenization_spaces = false,
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            path => input_paths.push(path.to_owned())
+        }
+    }
+
+    let tokenizer_type = tokenizer_type.ok_or("missing --tokenizer")?;
+    let vocab_path = vocab_path.ok_or("missing --vocab")?;
+    let truncation_strategy = parse_truncation_strategy(truncation_strategy, head)?;
+    if tokenizer_type != TokenizerType::Bert && merges_path.is_none() {
+        return Err("missing --merges".to_owned());
+    }
+    Ok(Options {
+        command,
+        tokenizer_type,
+        vocab_path,
+        merges_path,
+        lower_case,
+        max_len,
+        truncation_strategy,
+        stride,
+        pairs,
+        skip_special_tokens,
+        clean_up_tokenization_spaces,
+        input_paths,
+    })
+}
+
+fn flag_value<'a>(args: &mut impl Iterator<Item=&'a String>, flag: &str) -> Result<&'a str, String> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| format!("missing value for `{}`", flag))
+}
+
+fn parse_number(value: &str, flag: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("invalid value `{}` for `{}`", value, flag))
+}
+
+fn parse_tokenizer_type(value: &str) -> Result<TokenizerType, String> {
+    match value {
+        "bert" => Ok(TokenizerType::Bert),
+        "ctrl" => Ok(TokenizerType::Ctrl),
+        "gpt2" => Ok(TokenizerType::Gpt2),
+        "roberta" => Ok(TokenizerType::Roberta),
+        "openai-gpt" => Ok(TokenizerType::OpenAiGpt),
+        _ => Err(format!("unknown tokenizer `{}`", value))
+    }
+}
+
+fn parse_truncation_strategy(value: &str, head: usize) -> Result<TruncationStrategy, String> {
+    match value {
+        "longest_first" => Ok(TruncationStrategy::LongestFirst),
+        "only_first" => Ok(TruncationStrategy::OnlyFirst),
+        "only_second" => Ok(TruncationStrategy::OnlySecond),
+        "longest_first_left" => Ok(TruncationStrategy::LongestFirstLeft),
+        "only_first_left" => Ok(TruncationStrategy::OnlyFirstLeft),
+        "only_second_left" => Ok(TruncationStrategy::OnlySecondLeft),
+        "head_tail" => Ok(TruncationStrategy::HeadTail { head }),
+        "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
+        _ => Err(format!("unknown truncation strategy `{}`", value))
+    }
+}
+
+fn merges_path(options: &Options) -> &str {
+    options.merges_path.as_deref().unwrap_or_default()
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let vocab_path = options.vocab_path.as_str();
+    match options.tokenizer_type {
+        TokenizerType::Bert => process(&BertTokenizer::from_file(vocab_path, options.lower_case).map_err(|e| e.to_string())?, options),
+        TokenizerType::Ctrl => process(&CtrlTokenizer::from_file(vocab_path, merges_path(options), options.lower_case).map_err(|e| e.to_string())?, options),
+        TokenizerType::Gpt2 => process(&Gpt2Tokenizer::from_file(vocab_path, merges_path(options), options.lower_case).map_err(|e| e.to_string())?, options),
+        TokenizerType::Roberta => process(&RobertaTokenizer::from_file(vocab_path, merges_path(options), options.lower_case).map_err(|e| e.to_string())?, options),
+        TokenizerType::OpenAiGpt => process(&OpenAiGptTokenizer::from_file(vocab_path, merges_path(options), options.lower_case).map_err(|e| e.to_string())?, options),
+    }
+}
+
+fn process<T: MultiThreadedTokenizer<V>, V: Vocab>(tokenizer: &T, options: &Options) -> Result<(), String> {
+    let stdout = io::stdout();
+    let mut output = BufWriter::new(stdout.lock());
+    if options.command == Command::InspectVocab {
+        write_record(&mut output, &inspect_vocab(Tokenizer::vocab(tokenizer)))?;
+    } else {
+        for input in open_inputs(&options.input_paths)? {
+            let mut lines = input.lines();
+            loop {
+                let batch = lines
+                    .by_ref()
+                    .take(BATCH_SIZE)
+                    .collect::<io::Result<Vec<String>>>()
+                    .map_err(|e| e.to_string())?;
+                if batch.is_empty() {
+                    break;
+                }
+                for record in process_batch(tokenizer, options, &batch) {
+                    write_record(&mut output, &record)?;
+                }
+            }
+        }
+    }
+    output.flush().map_err(|e| e.to_string())
+}
+
+fn open_inputs(input_paths: &[String]) -> Result<Vec<Box<dyn BufRead>>, String> {
+    if input_paths.is_empty() {
+        return Ok(vec!(Box::new(BufReader::new(io::stdin()))));
+    }
+    input_paths
+        .iter()
+        .map(|path| match File::open(path) {
+            Ok(file) => Ok(Box::new(BufReader::new(file)) as Box<dyn BufRead>),
+            Err(e) => Err(format!("could not open {}: {}", path, e))
+        })
+        .collect()
+}
+
+fn write_record(output: &mut impl Write, record: &Value) -> Result<(), String> {
+    writeln!(output, "{}", record).map_err(|e| e.to_string())
+}
+
+fn process_batch<T: MultiThreadedTokenizer<V>, V: Vocab>(tokenizer: &T, options: &Options, lines: &[String]) -> Vec<Value> {
+    match options.command {
+        Command::Tokenize => MultiThreadedTokenizer::tokenize_list(tokenizer, lines.iter().map(String::as_str).collect())
+            .into_iter()
+            .map(|tokens| json!({ "tokens": tokens }))
+            .collect(),
+        Command::Encode if options.pairs => {
+            let pairs = lines.iter().map(|line| split_pair(line)).collect();
+            map_valid(pairs, |pairs| MultiThreadedTokenizer::try_encode_pair_list(
+                tokenizer, pairs, options.max_len, &options.truncation_strategy, options.stride, &PaddingStrategy::DoNotPad, &PaddingSide::Right)
+                .into_iter()
+                .map(encoding_record)
+                .collect())
+        }
+        Command::Encode => MultiThreadedTokenizer::try_encode_list(
+            tokenizer, lines.iter().map(String::as_str).collect(), options.max_len, &options.truncation_strategy, options.stride,
+            &PaddingStrategy::DoNotPad, &PaddingSide::Right)
+            .into_iter()
+            .map(encoding_record)
+            .collect(),
+        Command::Decode => {
+            let token_ids_list = lines.iter().map(|line| parse_token_ids(line)).collect();
+            map_valid(token_ids_list, |token_ids_list| MultiThreadedTokenizer::decode_list(
+                tokenizer, token_ids_list, options.skip_special_tokens, options.clean_up_tokenization_spaces)
+                .into_iter()
+                .map(|text| json!({ "text": text }))
+                .collect())
+        }
+        Command::InspectVocab => vec!()
+    }
+}
+
+/// Processes the valid inputs of a batch at once, the invalid ones being replaced by an error record at their position
+fn map_valid<I>(inputs: Vec<Result<I, String>>, process_inputs: impl FnOnce(Vec<I>) -> Vec<Value>) -> Vec<Value> {
+    let mut valid_inputs = vec!();
+    let mut errors = vec!();
+    for input in inputs {
+        match input {
+            Ok(input) => {
+                valid_inputs.push(input);
+                errors.push(None);
+            }
+            Err(e) => errors.push(Some(e))
+        }
+    }
+    let mut records = process_inputs(valid_inputs).into_iter();
+    errors
+        .into_iter()
+        .map(|error| match error {
+            Some(e) => json!({ "error": e }),
+            None => records.next().unwrap_or(Value::Null)
+        })
+        .collect()
+}
+
+fn split_pair(line: &str) -> Result<(&str, &str), String> {
+    let mut texts = line.splitn(2, '\t');
+    match (texts.next(), texts.next()) {
+        (Some(text_a), Some(text_b)) => Ok((text_a, text_b)),
+        _ => Err("expected two tab-separated texts".to_owned())
+    }
+}
+
+fn parse_token_ids(line: &str) -> Result<Vec<i64>, String> {
+    let line = line.trim();
+    if line.starts_with('[') || line.starts_with('{') {
+        let value: Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        let token_ids = if value.is_object() { value["token_ids"].clone() } else { value };
+        serde_json::from_value(token_ids).map_err(|e| format!("invalid token ids: {}", e))
+    } else {
+        line.split_whitespace()
+            .map(|token_id| token_id.parse().map_err(|_| format!("invalid token id `{}`", token_id)))
+            .collect()
+    }
+}
+
+fn encoding_record(result: Result<TokenizedInput, TokenizerError>) -> Value {
+    match result {
+        Ok(tokenized_input) => json!({
+            "token_ids": tokenized_input.token_ids,
+            "segment_ids": tokenized_input.segment_ids,
+            "special_tokens_mask": tokenized_input.special_tokens_mask,
+            "token_offsets": tokenized_input.token_offsets
+                .iter()
+                .map(|offset| offset.map(|offset| (offset.begin, offset.end)))
+                .collect::<Vec<Option<(usize, usize)>>>(),
+            "word_ids": tokenized_input.word_ids,
+            "overflowing_tokens": tokenized_input.overflowing_tokens,
+            "num_truncated_tokens": tokenized_input.num_truncated_tokens,
+        }),
+        Err(e) => json!({ "error": e.to_string() })
+    }
+}
+
+fn inspect_vocab<V: Vocab>(vocab: &V) -> Value {
+    let special_token_map = vocab.special_token_map();
+    json!({
+        "vocab_size": vocab.values().len(),
+        "special_tokens": {
+            "unk_token": special_token_map.unk_token,
+            "pad_token": special_token_map.pad_token,
+            "bos_token": special_token_map.bos_token,
+            "sep_token": special_token_map.sep_token,
+            "cls_token": special_token_map.cls_token,
+            "eos_token": special_token_map.eos_token,
+            "mask_token": special_token_map.mask_token,
+            "additional_special_tokens": special_token_map.additional_special_tokens,
+        },
+    })
+}
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    if let Err(e) = parse_args(&args).and_then(|options| run(&options)) {
+        eprintln!("error: {}\n\n{}", e, USAGE);
+        process::exit(1);
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_tokenizers::{BertVocab, Gpt2Vocab};
+    use rust_tokenizers::preprocessing::vocab::bpe_vocab::BpePairVocab;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_owned()).collect()
+    }
+
+    fn generate_test_tokenizer() -> BertTokenizer {
+        let values: HashMap<String, i64> = [
+            ("hello", 0),
+            ("world", 1),
+            ("[UNK]", 2),
+            ("!", 3),
+            ("[CLS]", 4),
+            ("[SEP]", 5),
+            ("[MASK]", 6),
+            ("[PAD]", 7)
+        ].iter().map(|(token, id)| ((*token).to_owned(), *id)).collect();
+        BertTokenizer::from_existing_vocab(Arc::new(BertVocab::from_values(values).unwrap()), true)
+    }
+
+    fn generate_test_gpt2_tokenizer() -> Gpt2Tokenizer {
+        let values: HashMap<String, i64> = [
+            ("Ã", 0),
+            ("©", 1),
+            ("t", 2),
+            ("<|endoftext|>", 3)
+        ].iter().map(|(token, id)| ((*token).to_owned(), *id)).collect();
+        Gpt2Tokenizer::from_existing_vocab_and_merges(Arc::new(Gpt2Vocab::from_values(values).unwrap()),
+                                                      Arc::new(BpePairVocab::from_values(HashMap::new())), false)
+    }
+
+    #[test]
+    fn test_parse_args() {
+//        Given
+        let valid_args = args(&["encode", "--tokenizer", "gpt2", "--vocab", "vocab.json", "--merges", "merges.txt",
+            "--max-len", "128", "--truncation", "head_tail", "--stride", "4", "--pairs", "--head", "32", "input.tsv"]);
+        let invalid_args = [
+            (args(&[]), "missing command"),
+            (args(&["split"]), "unknown command `split`"),
+            (args(&["encode", "--vocab", "vocab.txt"]), "missing --tokenizer"),
+            (args(&["encode", "--tokenizer", "gpt2", "--vocab", "vocab.json"]), "missing --merges"),
+            (args(&["encode", "--tokenizer", "bert", "--vocab"]), "missing value for `--vocab`"),
+            (args(&["encode", "--tokenizer", "bert", "--vocab", "vocab.txt", "--max-len", "-1"]), "invalid value `-1` for `--max-len`"),
+            (args(&["encode", "--tokenizer", "bert", "--vocab", "vocab.txt", "--truncation", "all"]), "unknown truncation strategy `all`"),
+            (args(&["encode", "--tokenizer", "bert", "--vocab", "vocab.txt", "--head", "first"]), "invalid value `first` for `--head`"),
+            (args(&["encode", "--tokenizer", "bert", "--vocab", "vocab.txt", "--padding"]), "unknown option `--padding`"),
+        ];
+
+//        When
+        let options = parse_args(&valid_args).unwrap();
+
+//        Then
+        assert_eq!(options, Options {
+            command: Command::Encode,
+            tokenizer_type: TokenizerType::Gpt2,
+            vocab_path: "vocab.json".to_owned(),
+            merges_path: Some("merges.txt".to_owned()),
+            lower_case: false,
+            max_len: 128,
+            truncation_strategy: TruncationStrategy::HeadTail { head: 32 },
+            stride: 4,
+            pairs: true,
+            skip_special_tokens: false,
+            clean_up_tokenization_spaces: true,
+            input_paths: vec!("input.tsv".to_owned()),
+        });
+        for (invalid_args, expected_error) in invalid_args.iter() {
+            assert_eq!(parse_args(invalid_args).unwrap_err(), *expected_error);
+        }
+    }
 
+    #[test]
+    fn test_parse_token_ids() {
+//        Given
+        let test_tuples = [
+            ("[4, 0, 5]", Ok(vec!(4, 0, 5))),
+            ("{\"token_ids\": [4, 0, 5], \"segment_ids\": [0, 0, 0]}", Ok(vec!(4, 0, 5))),
+            (" 4 0  5 ", Ok(vec!(4, 0, 5))),
+            ("", Ok(vec!())),
+            ("4 zero 5", Err("invalid token id `zero`".to_owned())),
+        ];
 
-    let vocab_path = env::var("bert_vocab").expect("`bert_vocab` environment variable not set");
-    let vocab = Arc::new(rust_tokenizers::BertVocab::from_file(vocab_path.as_str()));
+//        When & Then
+        for (line, expected_result) in test_tuples.iter() {
+            assert_eq!(parse_token_ids(line), *expected_result);
+        }
+    }
 
+    #[test]
+    fn test_process_batch() {
+//        Given
+        let bert_tokenizer = generate_test_tokenizer();
+        let gpt2_tokenizer = generate_test_gpt2_tokenizer();
+        let options = parse_args(&args(&["encode", "--tokenizer", "bert", "--vocab", "vocab.txt", "--pairs"])).unwrap();
+        let lines = args(&["hello\tworld!", "hello world"]);
 
-    let _test_sentence = Example::new_from_string("This is a sample sentence to be tokenized");
-    let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab.clone(), true);
+//        When
+        let encodings = process_batch(&bert_tokenizer, &options, &lines);
+        let decodings = process_batch(&bert_tokenizer, &Options { command: Command::Decode, ..options },
+                                      &[encodings[0].to_string(), "4 1 5".to_owned()]);
+        let gpt2_options = parse_args(&args(&["decode", "--tokenizer", "gpt2", "--vocab", "vocab.json", "--merges", "merges.txt"])).unwrap();
+        let gpt2_decodings = process_batch(&gpt2_tokenizer, &gpt2_options, &args(&["2 0 1", "2 0", "[0, 3]", "2 t"]));
 
-    println!("{:?}", bert_tokenizer.encode(_test_sentence.sentence_1.as_str(),
-                                           None,
-                                           128,
-                                           &TruncationStrategy::LongestFirst,
-                                           0));
+//        Then
+        assert_eq!(encodings[0]["token_ids"], json!([4, 0, 5, 1, 3, 5]));
+        assert_eq!(encodings[0]["segment_ids"], json!([0, 0, 0, 1, 1, 1]));
+        assert_eq!(encodings[0]["token_offsets"], json!([null, [0, 5], null, [0, 5], [5, 6], null]));
+        assert_eq!(encodings[1], json!({ "error": "expected two tab-separated texts" }));
+        assert_eq!(decodings, vec!(json!({ "text": "[CLS] hello [SEP] world! [SEP]" }), json!({ "text": "[CLS] world [SEP]" })));
+        assert_eq!(gpt2_decodings, vec!(
+            json!({ "text": "té" }),
+            json!({ "text": "t\u{FFFD}" }),
+            json!({ "text": "\u{FFFD}<|endoftext|>" }),
+            json!({ "error": "invalid token id `t`" })));
+    }
 }
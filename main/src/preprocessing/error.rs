@@ -0,0 +1,59 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Errors raised while loading vocabularies, looking up tokens or encoding inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenizerError {
+    /// A vocabulary or merges file could not be opened or read
+    IOError(String),
+    /// A vocabulary or merges file was read but its content is invalid
+    VocabularyParsingError(String),
+    /// A special token (e.g. the unknown token) is not present in the vocabulary
+    MissingSpecialToken(String),
+    /// The sequences could not be truncated to the requested length
+    TruncationError(String),
+    /// A serialized tokenizer uses a component or an option that is not supported
+    UnsupportedComponent(String),
+    /// A post-processing template is malformed
+    InvalidTemplate(String),
+}
+
+impl fmt::Display for TokenizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenizerError::IOError(message) => write!(f, "IO error: {}", message),
+            TokenizerError::VocabularyParsingError(message) => write!(f, "Vocabulary parsing error: {}", message),
+            TokenizerError::MissingSpecialToken(token) => write!(f, "The special value {} could not be found in the vocabulary", token),
+            TokenizerError::TruncationError(message) => write!(f, "Truncation error: {}", message),
+            TokenizerError::UnsupportedComponent(message) => write!(f, "Unsupported tokenizer component: {}", message),
+            TokenizerError::InvalidTemplate(message) => write!(f, "Invalid template: {}", message),
+        }
+    }
+}
+
+impl Error for TokenizerError {}
+
+impl From<io::Error> for TokenizerError {
+    fn from(error: io::Error) -> Self {
+        TokenizerError::IOError(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for TokenizerError {
+    fn from(error: serde_json::Error) -> Self {
+        TokenizerError::VocabularyParsingError(error.to_string())
+    }
+}
@@ -12,4 +12,6 @@
 
 pub mod vocab;
 pub mod tokenizer;
-pub mod adapters;
\ No newline at end of file
+pub mod trainer;
+pub mod adapters;
+pub mod error;
\ No newline at end of file
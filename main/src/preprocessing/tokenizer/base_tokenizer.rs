@@ -12,18 +12,119 @@
 // limitations under the License.
 
 use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::preprocessing::tokenizer::tokenization_utils::{tokenize_cjk_chars, whitespace_tokenize, strip_accents, split_on_punct, clean_text, truncate_sequences};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenization_utils::{tokenize_cjk_chars_with_offsets, whitespace_tokenize_with_offsets, strip_accents_with_offsets, split_on_punct_with_offsets, clean_text_with_offsets, truncate_sequences, lowercase_with_offsets};
 use std::sync::Arc;
 use rayon::prelude::*;
 use itertools::Itertools;
+use std::iter::repeat_n;
+use std::cmp::min;
+use std::ops::Range;
 
+/// Sequences truncated when the inputs do not fit in `max_len`, and which of their tokens are removed.
+/// `LongestFirst`, `OnlyFirst` and `OnlySecond` remove tokens from the end of the sequences, their `Left` counterparts
+/// remove tokens from the start, keeping the most recent ones (e.g. the last turns of a conversation).
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TruncationStrategy {
     LongestFirst,
     OnlyFirst,
     OnlySecond,
+    LongestFirstLeft,
+    OnlyFirstLeft,
+    OnlySecondLeft,
+    /// Truncates the first sequence by removing tokens from its middle, keeping its first `head` tokens and as many of its
+    /// last tokens as fit in `max_len`. Sequences shorter than `head` after truncation keep their first tokens only.
+    HeadTail { head: usize },
+    /// Keeps the inputs in full, even if they are longer than `max_len`
     DoNotTruncate,
 }
 
+impl TruncationStrategy {
+    /// Positions of the tokens removed from a sequence of `len` tokens truncated by `num_tokens_to_remove` tokens
+    pub(crate) fn truncated_range(&self, len: usize, num_tokens_to_remove: usize) -> Range<usize> {
+        match self {
+            TruncationStrategy::LongestFirstLeft | TruncationStrategy::OnlyFirstLeft | TruncationStrategy::OnlySecondLeft =>
+                0..num_tokens_to_remove,
+            TruncationStrategy::HeadTail { head } => {
+                let begin = min(*head, len - num_tokens_to_remove);
+                begin..begin + num_tokens_to_remove
+            }
+            _ => len - num_tokens_to_remove..len
+        }
+    }
+}
+
+/// Length the encoded sequences are padded to. `Longest` pads all sequences of a batch to the longest one.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PaddingStrategy {
+    DoNotPad,
+    MaxLength,
+    Longest,
+    MultipleOf(usize),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PaddingSide {
+    Left,
+    Right,
+}
+
+/// Character positions of a token in the original text (`begin` included, `end` excluded)
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct Offset {
+    pub begin: usize,
+    pub end: usize,
+}
+
+/// Token text along with the position in the original text of the characters it was built from.
+/// Until the token is split into sub-words, `reference_offsets` holds exactly one position per character of `text`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub text: String,
+    pub reference_offsets: Vec<usize>,
+}
+
+impl Token {
+    pub fn new(text: &str) -> Token {
+        Token { text: text.to_owned(), reference_offsets: (0..text.chars().count()).collect() }
+    }
+
+    /// Span of the original text covered by the token, `None` if the token does not originate from the text
+    pub fn offset(&self) -> Option<Offset> {
+        match (self.reference_offsets.iter().min(), self.reference_offsets.iter().max()) {
+            (Some(&begin), Some(&end)) => Some(Offset { begin, end: end + 1 }),
+            _ => None
+        }
+    }
+
+    /// Sub-token for the `begin..end` byte range of the token text
+    pub fn slice(&self, begin: usize, end: usize) -> Token {
+        let char_begin = self.text[..begin].chars().count();
+        let char_end = char_begin + self.text[begin..end].chars().count();
+        Token { text: self.text[begin..end].to_owned(), reference_offsets: self.reference_offsets[char_begin..char_end].to_vec() }
+    }
+
+    /// Sub-tokens for sorted, non-overlapping byte ranges of the token text, computed in a single pass over the text
+    pub fn slices(&self, ranges: &[(usize, usize)]) -> Vec<Token> {
+        let mut output = Vec::with_capacity(ranges.len());
+        let mut characters = self.text.chars();
+        let (mut byte_position, mut char_position) = (0, 0);
+        let mut advance_to = |target: usize| {
+            while byte_position < target {
+                byte_position += characters.next().unwrap().len_utf8();
+                char_position += 1;
+            }
+            char_position
+        };
+        for &(begin, end) in ranges {
+            let char_begin = advance_to(begin);
+            let char_end = advance_to(end);
+            output.push(Token { text: self.text[begin..end].to_owned(), reference_offsets: self.reference_offsets[char_begin..char_end].to_vec() });
+        }
+        output
+    }
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub struct TokenizedInput {
     pub token_ids: Vec<i64>,
@@ -31,12 +132,229 @@ pub struct TokenizedInput {
     pub special_tokens_mask: Vec<i8>,
     pub overflowing_tokens: Vec<i64>,
     pub num_truncated_tokens: usize,
+    pub token_offsets: Vec<Option<Offset>>,
+    pub attention_mask: Vec<i8>,
+    /// Index of the word each token originates from within its sequence, `None` for the added special tokens and padding
+    pub word_ids: Vec<Option<usize>>,
+    /// Sequence (0 for the first one, 1 for the second one) each token originates from, `None` for the added special tokens and padding
+    pub sequence_ids: Vec<Option<usize>>,
+}
+
+impl TokenizedInput {
+    /// Pads the input up to `target_length` with `pad_id`. Padding positions are masked in the attention mask,
+    /// flagged as special tokens and have no offset. Inputs longer than `target_length` are left unchanged.
+    pub fn pad(&mut self, target_length: usize, pad_id: i64, padding_side: &PaddingSide) {
+        let padding_length = target_length.saturating_sub(self.token_ids.len());
+        if padding_length == 0 {
+            return;
+        }
+        match padding_side {
+            PaddingSide::Right => {
+                self.token_ids.extend(repeat_n(pad_id, padding_length));
+                self.segment_ids.extend(repeat_n(0, padding_length));
+                self.special_tokens_mask.extend(repeat_n(1, padding_length));
+                self.token_offsets.extend(repeat_n(None, padding_length));
+                self.attention_mask.extend(repeat_n(0, padding_length));
+                self.word_ids.extend(repeat_n(None, padding_length));
+                self.sequence_ids.extend(repeat_n(None, padding_length));
+            }
+            PaddingSide::Left => {
+                self.token_ids.splice(0..0, repeat_n(pad_id, padding_length));
+                self.segment_ids.splice(0..0, repeat_n(0, padding_length));
+                self.special_tokens_mask.splice(0..0, repeat_n(1, padding_length));
+                self.token_offsets.splice(0..0, repeat_n(None, padding_length));
+                self.attention_mask.splice(0..0, repeat_n(0, padding_length));
+                self.word_ids.splice(0..0, repeat_n(None, padding_length));
+                self.sequence_ids.splice(0..0, repeat_n(None, padding_length));
+            }
+        }
+    }
+
+    /// Word index (within its sequence) of the token at `token_index`, `None` for special tokens and padding
+    pub fn token_to_word(&self, token_index: usize) -> Option<usize> {
+        self.word_ids.get(token_index).copied().flatten()
+    }
+
+    /// Span (`begin` included, `end` excluded) of the tokens of the word `word_index` of the sequence `sequence_id`,
+    /// `None` if none of its tokens is part of the input (e.g. after truncation)
+    pub fn word_to_tokens(&self, word_index: usize, sequence_id: usize) -> Option<(usize, usize)> {
+        let mut positions = self.word_ids
+            .iter()
+            .zip(self.sequence_ids.iter())
+            .enumerate()
+            .filter(|(_, ids)| *ids == (&Some(word_index), &Some(sequence_id)))
+            .map(|(position, _)| position);
+        let begin = positions.next()?;
+        let end = positions.next_back().unwrap_or(begin) + 1;
+        Some((begin, end))
+    }
+}
+
+/// Length a single encoded input of length `length` should be padded to. `Longest` is resolved at the batch level.
+fn padding_target_length(padding_strategy: &PaddingStrategy, length: usize, max_len: usize) -> usize {
+    match padding_strategy {
+        PaddingStrategy::DoNotPad | PaddingStrategy::Longest => length,
+        PaddingStrategy::MaxLength => max_len,
+        PaddingStrategy::MultipleOf(0) => length,
+        PaddingStrategy::MultipleOf(multiple) => length.div_ceil(*multiple) * multiple,
+    }
+}
+
+fn pad_to_longest(tokenized_inputs: &mut [TokenizedInput], pad_id: i64, padding_side: &PaddingSide) {
+    let longest = tokenized_inputs.iter().map(|input| input.token_ids.len()).max().unwrap_or(0);
+    for tokenized_input in tokenized_inputs.iter_mut() {
+        tokenized_input.pad(longest, pad_id, padding_side);
+    }
+}
+
+/// Pads the successfully encoded inputs of a batch to the longest one, ignoring the inputs that failed
+fn pad_results_to_longest(results: &mut [Result<TokenizedInput, TokenizerError>], pad_id: i64, padding_side: &PaddingSide) {
+    let longest = results.iter().flatten().map(|input| input.token_ids.len()).max().unwrap_or(0);
+    for tokenized_input in results.iter_mut().flatten() {
+        tokenized_input.pad(longest, pad_id, padding_side);
+    }
+}
+
+fn unwrap_or_panic(result: Result<TokenizedInput, TokenizerError>) -> TokenizedInput {
+    match result {
+        Ok(tokenized_input) => tokenized_input,
+        Err(err) => panic!("{}", err)
+    }
+}
+
+/// Flags the special tokens written in the text, such as a mask token or a token registered with `add_special_tokens`.
+/// The unknown value is not flagged, as it also stands for out-of-vocabulary words.
+fn text_special_tokens_mask<T: Vocab>(vocab: &T, tokens: &[String]) -> Vec<i8> {
+    tokens
+        .iter()
+        .map(|token| if token != vocab.unknown_value() && vocab.special_values().contains_key(token) { 1 } else { 0 })
+        .collect()
+}
+
+/// Ids of the tokens of an input sequence, with their offsets and the flags of the special tokens written in the text.
+#[derive(Clone)]
+struct EncodedSequence {
+    token_ids: Vec<i64>,
+    offsets: Vec<Option<Offset>>,
+    text_special_tokens: Vec<i8>,
+    word_ids: Vec<usize>,
+}
+
+impl EncodedSequence {
+    fn len(&self) -> usize {
+        self.token_ids.len()
+    }
+
+    /// Removes the tokens the truncation strategy cuts to bring the sequence down to `len` tokens
+    fn truncate(&mut self, len: usize, truncation_strategy: &TruncationStrategy) {
+        let range = truncation_strategy.truncated_range(self.len(), self.len() - len);
+        self.token_ids.drain(range.clone());
+        self.offsets.drain(range.clone());
+        self.text_special_tokens.drain(range.clone());
+        self.word_ids.drain(range);
+    }
+
+    fn slice(&self, begin: usize, end: usize) -> EncodedSequence {
+        EncodedSequence {
+            token_ids: self.token_ids[begin..end].to_vec(),
+            offsets: self.offsets[begin..end].to_vec(),
+            text_special_tokens: self.text_special_tokens[begin..end].to_vec(),
+            word_ids: self.word_ids[begin..end].to_vec(),
+        }
+    }
+}
+
+fn encode_sequence<T: Vocab, U: Tokenizer<T> + ?Sized>(tokenizer: &U, text: &str) -> Result<EncodedSequence, TokenizerError> {
+    let mut tokens = vec!();
+    let mut offsets = vec!();
+    let mut word_ids = vec!();
+    for (word_id, word) in tokenizer.tokenize_to_words(&Token::new(text)).into_iter().enumerate() {
+        for token in word {
+            offsets.push(token.offset());
+            tokens.push(token.text);
+            word_ids.push(word_id);
+        }
+    }
+    let token_ids = tokenizer.try_convert_tokens_to_ids(&tokens)?;
+    let text_special_tokens = text_special_tokens_mask(tokenizer.vocab(), &tokens);
+    Ok(EncodedSequence { token_ids, offsets, text_special_tokens, word_ids })
+}
+
+/// Number of special tokens the tokenizer adds around a single sequence or a pair of sequences
+fn num_added_tokens<T: Vocab, U: Tokenizer<T> + ?Sized>(tokenizer: &U, pair: bool) -> usize {
+    let (additional_tokens, _, _) = tokenizer.build_input_with_special_tokens(vec!(), if pair { Some(vec!()) } else { None });
+    additional_tokens.len()
+}
+
+/// Adds the special tokens around the sequences, without padding.
+fn build_tokenized_input<T: Vocab, U: Tokenizer<T> + ?Sized>(tokenizer: &U, sequence_1: EncodedSequence, sequence_2: Option<EncodedSequence>,
+                                                             overflowing_tokens: Vec<i64>, num_truncated_tokens: usize) -> TokenizedInput {
+    let (token_ids_2, offsets_2, text_special_tokens_2, word_ids_2) = match sequence_2 {
+        Some(sequence) => (Some(sequence.token_ids), sequence.offsets, sequence.text_special_tokens, sequence.word_ids),
+        None => (None, vec!(), vec!(), vec!())
+    };
+    let (token_ids, segment_ids, mut special_tokens_mask) = tokenizer.build_input_with_special_tokens(sequence_1.token_ids,
+                                                                                                       token_ids_2);
+
+//        Special tokens added around the sequences do not originate from the input text, the ones written in the text keep their offsets
+    let mut text_offsets = sequence_1.offsets.into_iter().chain(offsets_2);
+    let mut text_special_tokens = sequence_1.text_special_tokens.into_iter().chain(text_special_tokens_2);
+    let mut text_word_ids = sequence_1.word_ids
+        .into_iter()
+        .map(|word_id| (word_id, 0))
+        .chain(word_ids_2.into_iter().map(|word_id| (word_id, 1)));
+    let mut token_offsets = Vec::with_capacity(special_tokens_mask.len());
+    let mut word_ids = Vec::with_capacity(special_tokens_mask.len());
+    let mut sequence_ids = Vec::with_capacity(special_tokens_mask.len());
+    for is_special in special_tokens_mask.iter_mut() {
+        if *is_special == 1 {
+            token_offsets.push(None);
+            word_ids.push(None);
+            sequence_ids.push(None);
+        } else {
+            token_offsets.push(text_offsets.next().unwrap_or(None));
+            *is_special = text_special_tokens.next().unwrap_or(0);
+            let (word_id, sequence_id) = text_word_ids.next().unzip();
+            word_ids.push(word_id);
+            sequence_ids.push(sequence_id);
+        }
+    }
+
+    let attention_mask = vec![1; token_ids.len()];
+    TokenizedInput { token_ids, segment_ids, special_tokens_mask, overflowing_tokens, num_truncated_tokens, token_offsets, attention_mask, word_ids, sequence_ids }
 }
 
 pub trait Tokenizer<T: Vocab> {
     fn vocab(&self) -> &T;
 
-    fn tokenize(&self, text: &str) -> Vec<String>;
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token>;
+
+    /// Splits the text into the words the sub-word tokenization is applied to, each holding the tokens it is split into.
+    /// The tokens of all the words are the ones returned by `tokenize_to_tokens`. Tokenizers not overriding this
+    /// method consider each token a word.
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        self.tokenize_to_tokens(initial_token)
+            .into_iter()
+            .map(|token| vec!(token))
+            .collect()
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        self.tokenize_to_tokens(&Token::new(text))
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    fn tokenize_with_offsets(&self, text: &str) -> (Vec<String>, Vec<Option<Offset>>) {
+        self.tokenize_to_tokens(&Token::new(text))
+            .into_iter()
+            .map(|token| {
+                let offset = token.offset();
+                (token.text, offset)
+            })
+            .unzip()
+    }
 
     fn tokenize_list(&self, text_list: Vec<&str>) -> Vec<Vec<String>> {
         text_list.
@@ -49,49 +367,167 @@ pub trait Tokenizer<T: Vocab> {
         tokens.into_iter().map(|v| self.vocab().token_to_id(v)).collect()
     }
 
-    fn encode(&self, text_1: &str, text_2: Option<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize) -> TokenizedInput {
-        let token_ids_1 = self.convert_tokens_to_ids(&self.tokenize(text_1));
-        let len_1 = token_ids_1.len();
-        let (token_ids_2, len_2, pair) = {
-            if let Some(text) = text_2 {
-                let token_ids_2: Vec<i64> = self.convert_tokens_to_ids(&self.tokenize(text));
-                let len_2 = token_ids_2.len();
-                (Some(token_ids_2), len_2, Some(vec!()))
-            } else {
-                (None, 0, None)
-            }
+    fn try_convert_tokens_to_ids(&self, tokens: &[String]) -> Result<Vec<i64>, TokenizerError> {
+        tokens.iter().map(|v| self.vocab().try_token_to_id(v)).collect()
+    }
+
+    /// Encodes a sequence or a pair of sequences, panicking if the inputs cannot be truncated
+    /// to `max_len` with the given strategy. See `try_encode` for the fallible version.
+    #[allow(clippy::too_many_arguments)]
+    fn encode(&self, text_1: &str, text_2: Option<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+              padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> TokenizedInput {
+        unwrap_or_panic(self.try_encode(text_1, text_2, max_len, truncation_strategy, stride, padding_strategy, padding_side))
+    }
+
+    /// Encodes a sequence or a pair of sequences, returning an error if a token is missing from the vocabulary or if
+    /// the inputs cannot be truncated to `max_len` with the given strategy (e.g. `OnlySecond` for a single sequence).
+    /// `DoNotTruncate` returns the inputs in full, even if they are longer than `max_len`.
+    #[allow(clippy::too_many_arguments)]
+    fn try_encode(&self, text_1: &str, text_2: Option<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                  padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Result<TokenizedInput, TokenizerError> {
+        let mut sequence_1 = encode_sequence(self, text_1)?;
+        let mut sequence_2 = match text_2 {
+            Some(text) => Some(encode_sequence(self, text)?),
+            None => None
+        };
+        let total_len = sequence_1.len() + sequence_2.as_ref().map_or(0, EncodedSequence::len) + num_added_tokens(self, sequence_2.is_some());
+        let num_truncated_tokens = match truncation_strategy {
+            TruncationStrategy::DoNotTruncate => 0,
+            _ => total_len.saturating_sub(max_len)
         };
-        let (additional_tokens, _, _) = self.build_input_with_special_tokens(vec!(), pair);
-        let total_len = len_1 + len_2 + additional_tokens.len();
-        let num_truncated_tokens = if total_len > max_len { total_len - max_len } else { 0 };
         let (token_ids_1,
             token_ids_2,
-            overflowing_tokens) = truncate_sequences(token_ids_1,
-                                                     token_ids_2,
+            overflowing_tokens) = truncate_sequences(sequence_1.token_ids.clone(),
+                                                     sequence_2.as_ref().map(|sequence| sequence.token_ids.clone()),
                                                      num_truncated_tokens,
                                                      truncation_strategy,
-                                                     stride).unwrap();
+                                                     stride)?;
+        sequence_1.truncate(token_ids_1.len(), truncation_strategy);
+        if let (Some(sequence), Some(token_ids)) = (sequence_2.as_mut(), token_ids_2.as_ref()) {
+            sequence.truncate(token_ids.len(), truncation_strategy);
+        }
 
-        let (token_ids, segment_ids, special_tokens_mask) = self.build_input_with_special_tokens(token_ids_1,
-                                                                                                 token_ids_2);
+        let mut tokenized_input = build_tokenized_input(self, sequence_1, sequence_2, overflowing_tokens, num_truncated_tokens);
+        let target_length = padding_target_length(padding_strategy, tokenized_input.token_ids.len(), max_len);
+        tokenized_input.pad(target_length, self.pad_id(), padding_side);
+        Ok(tokenized_input)
+    }
+
+    /// Encodes a sequence or a pair of sequences too long for `max_len` into several inputs of at most `max_len` tokens,
+    /// the standard sliding window setup for question answering on long documents. Each input holds a window of one of the
+    /// sequences, overlapping the previous window by `stride` tokens, and the other sequence in full:
+    /// - `LongestFirst`, `OnlyFirst` and `HeadTail` slide the window over the first sequence, which is the only option for a single sequence,
+    /// - `OnlySecond` slides the window over the second sequence,
+    /// - the `Left` strategies slide the window the same way as their counterparts,
+    /// - `DoNotTruncate` keeps the inputs in full in a single encoding.
+    ///
+    /// Inputs fitting in `max_len` are returned as a single encoding. The windows have no overflowing tokens and are padded
+    /// following `padding_strategy`, `Longest` padding them to the longest window.
+    #[allow(clippy::too_many_arguments)]
+    fn try_encode_windows(&self, text_1: &str, text_2: Option<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                          padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let sequence_1 = encode_sequence(self, text_1)?;
+        let sequence_2 = match text_2 {
+            Some(text) => Some(encode_sequence(self, text)?),
+            None => None
+        };
+        let num_added_tokens = num_added_tokens(self, sequence_2.is_some());
+        let total_len = sequence_1.len() + sequence_2.as_ref().map_or(0, EncodedSequence::len) + num_added_tokens;
+
+        let mut tokenized_inputs = if total_len <= max_len || matches!(truncation_strategy, TruncationStrategy::DoNotTruncate) {
+            vec!(build_tokenized_input(self, sequence_1, sequence_2, vec!(), 0))
+        } else {
+//            The sequence kept in full is placed before or after each window
+            let (windowed_sequence, kept_first, kept_second) = match (truncation_strategy, sequence_2) {
+                (TruncationStrategy::OnlySecond, None) | (TruncationStrategy::OnlySecondLeft, None) =>
+                    return Err(TokenizerError::TruncationError("Invalid truncation strategy for single sentence truncation".to_owned())),
+                (TruncationStrategy::OnlySecond, Some(sequence_2)) | (TruncationStrategy::OnlySecondLeft, Some(sequence_2)) =>
+                    (sequence_2, Some(sequence_1), None),
+                (_, sequence_2) => (sequence_1, None, sequence_2),
+            };
+            let kept_len = kept_first.as_ref().or(kept_second.as_ref()).map_or(0, EncodedSequence::len);
+            let window_len = max_len.saturating_sub(kept_len + num_added_tokens);
+            if window_len == 0 {
+                return Err(TokenizerError::TruncationError("The sequence kept in full and the special tokens do not fit in max_len".to_owned()));
+            }
+            if stride >= window_len {
+                return Err(TokenizerError::TruncationError(format!("The stride ({}) must be smaller than the window length ({})", stride, window_len)));
+            }
+            let mut tokenized_inputs = vec!();
+            let mut start = 0;
+            loop {
+                let end = min(start + window_len, windowed_sequence.len());
+                let window = windowed_sequence.slice(start, end);
+                let (sequence_1, sequence_2) = match &kept_first {
+                    Some(sequence) => (sequence.clone(), Some(window)),
+                    None => (window, kept_second.clone())
+                };
+                tokenized_inputs.push(build_tokenized_input(self, sequence_1, sequence_2, vec!(), 0));
+                if end == windowed_sequence.len() {
+                    break;
+                }
+                start = end - stride;
+            }
+            tokenized_inputs
+        };
 
-        TokenizedInput { token_ids, segment_ids, special_tokens_mask, overflowing_tokens, num_truncated_tokens }
+        match padding_strategy {
+            PaddingStrategy::Longest => pad_to_longest(&mut tokenized_inputs, self.pad_id(), padding_side),
+            _ => for tokenized_input in tokenized_inputs.iter_mut() {
+                let target_length = padding_target_length(padding_strategy, tokenized_input.token_ids.len(), max_len);
+                tokenized_input.pad(target_length, self.pad_id(), padding_side);
+            }
+        }
+        Ok(tokenized_inputs)
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize) -> Vec<TokenizedInput> {
-        text_list
+    fn pad_id(&self) -> i64 {
+        self.vocab().token_to_id(self.vocab().pad_value())
+    }
+
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                   padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<TokenizedInput> {
+        Tokenizer::try_encode_list(self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side)
             .into_iter()
-            .map(|text| self.encode(text, None, max_len, truncation_strategy, stride))
+            .map(unwrap_or_panic)
             .collect()
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize) -> Vec<TokenizedInput> {
-        text_list
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                        padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<TokenizedInput> {
+        Tokenizer::try_encode_pair_list(self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side)
             .into_iter()
-            .map(|text| self.encode(text.0, Some(text.1), max_len, truncation_strategy, stride))
+            .map(unwrap_or_panic)
             .collect()
     }
 
+    /// Encodes a batch of sequences, returning the result of each input so that an input that cannot be encoded does not
+    /// abort the whole batch. `Longest` pads the inputs encoded successfully to the longest one.
+    fn try_encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                       padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<Result<TokenizedInput, TokenizerError>> {
+        let mut results: Vec<Result<TokenizedInput, TokenizerError>> = text_list
+            .into_iter()
+            .map(|text| self.try_encode(text, None, max_len, truncation_strategy, stride, padding_strategy, padding_side))
+            .collect();
+        if let PaddingStrategy::Longest = padding_strategy {
+            pad_results_to_longest(&mut results, self.pad_id(), padding_side);
+        }
+        results
+    }
+
+    /// Encodes a batch of sequence pairs, returning the result of each pair. See `try_encode_list`.
+    fn try_encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                            padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<Result<TokenizedInput, TokenizerError>> {
+        let mut results: Vec<Result<TokenizedInput, TokenizerError>> = text_list
+            .into_iter()
+            .map(|text| self.try_encode(text.0, Some(text.1), max_len, truncation_strategy, stride, padding_strategy, padding_side))
+            .collect();
+        if let PaddingStrategy::Longest = padding_strategy {
+            pad_results_to_longest(&mut results, self.pad_id(), padding_side);
+        }
+        results
+    }
+
     fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> String {
         let tokens: Vec<String> = if skip_special_tokens {
             token_ids
@@ -172,20 +608,46 @@ pub trait MultiThreadedTokenizer<T: Vocab>
             collect()
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize) -> Vec<TokenizedInput> {
-        text_list
-            .par_iter()
-            .map(|text| self.encode(text, None, max_len, truncation_strategy, stride))
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                   padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<TokenizedInput> {
+        MultiThreadedTokenizer::try_encode_list(self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side)
+            .into_iter()
+            .map(unwrap_or_panic)
             .collect()
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize) -> Vec<TokenizedInput> {
-        text_list
-            .par_iter()
-            .map(|text| self.encode(text.0, Some(text.1), max_len, truncation_strategy, stride))
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                        padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<TokenizedInput> {
+        MultiThreadedTokenizer::try_encode_pair_list(self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side)
+            .into_iter()
+            .map(unwrap_or_panic)
             .collect()
     }
 
+    fn try_encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                       padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<Result<TokenizedInput, TokenizerError>> {
+        let mut results: Vec<Result<TokenizedInput, TokenizerError>> = text_list
+            .par_iter()
+            .map(|text| self.try_encode(text, None, max_len, truncation_strategy, stride, padding_strategy, padding_side))
+            .collect();
+        if let PaddingStrategy::Longest = padding_strategy {
+            pad_results_to_longest(&mut results, self.pad_id(), padding_side);
+        }
+        results
+    }
+
+    fn try_encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &TruncationStrategy, stride: usize,
+                            padding_strategy: &PaddingStrategy, padding_side: &PaddingSide) -> Vec<Result<TokenizedInput, TokenizerError>> {
+        let mut results: Vec<Result<TokenizedInput, TokenizerError>> = text_list
+            .par_iter()
+            .map(|text| self.try_encode(text.0, Some(text.1), max_len, truncation_strategy, stride, padding_strategy, padding_side))
+            .collect();
+        if let PaddingStrategy::Longest = padding_strategy {
+            pad_results_to_longest(&mut results, self.pad_id(), padding_side);
+        }
+        results
+    }
+
     fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> Vec<String> {
         token_ids_list
             .par_iter()
@@ -201,14 +663,18 @@ pub struct BaseTokenizer<T: Vocab> {
 }
 
 impl<T: Vocab + Sync + Send> BaseTokenizer<T> {
-    pub fn from_file(path: &str, lower_case: bool) -> BaseTokenizer<T> {
-        let vocab = T::from_file(path);
-        BaseTokenizer { vocab: Arc::new(vocab), lower_case }
+    pub fn from_file(path: &str, lower_case: bool) -> Result<BaseTokenizer<T>, TokenizerError> {
+        let vocab = T::from_file(path)?;
+        Ok(BaseTokenizer { vocab: Arc::new(vocab), lower_case })
     }
 
     pub fn from_existing_vocab(vocab: Arc<T>, lower_case: bool) -> BaseTokenizer<T> {
         BaseTokenizer { vocab, lower_case }
     }
+
+    pub(crate) fn lower_case(&self) -> bool {
+        self.lower_case
+    }
 }
 
 impl<T: Vocab + Sync + Send> Tokenizer<T> for BaseTokenizer<T> {
@@ -216,33 +682,24 @@ impl<T: Vocab + Sync + Send> Tokenizer<T> for BaseTokenizer<T> {
         &self.vocab
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let tokenized_text: String = tokenize_cjk_chars(clean_text(text, true).as_str());
-        let mut tokenized_text: Vec<String> = whitespace_tokenize(tokenized_text.as_str()).into_iter().map(|s| s.to_string()).collect();
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        let cleaned_token = tokenize_cjk_chars_with_offsets(&clean_text_with_offsets(initial_token, true));
+        let mut tokens: Vec<Token> = whitespace_tokenize_with_offsets(&cleaned_token);
 
-        for string in tokenized_text.iter_mut() {
-            if !self.vocab.as_ref().special_values().contains_key(string) {
+        for token in tokens.iter_mut() {
+            if !self.vocab.as_ref().special_values().contains_key(&token.text) {
                 if self.lower_case {
-                    *string = string.to_lowercase();
+                    *token = lowercase_with_offsets(token);
                 }
-                *string = strip_accents(string.to_owned());
+                *token = strip_accents_with_offsets(token);
             }
         }
 
-        let tokenized_text: Vec<String> = tokenized_text
-            .into_iter()
-            .map(|v| split_on_punct(v, self.vocab.as_ref()))
-            .flatten()
-            .map(|s| s.to_string())
-            .collect();
-
-        let tokenized_text: String = tokenized_text.into_iter().join(" ");
-        let tokenized_text: Vec<String> = whitespace_tokenize(tokenized_text.as_str())
-            .into_iter()
-            .map(|s| s.to_string())
-            .collect();
-
-        tokenized_text
+        tokens
+            .iter()
+            .flat_map(|token| split_on_punct_with_offsets(token, self.vocab.as_ref()))
+            .flat_map(|token| whitespace_tokenize_with_offsets(&token))
+            .collect()
     }
 }
 
@@ -287,7 +744,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        BertVocab { values, indices, unknown_value: "[UNK]", special_values, special_indices }
+        BertVocab { values, indices, special_token_map: BertVocab::default_special_token_map(), special_values, special_indices }
     }
 
     #[test]
@@ -400,6 +857,45 @@ mod tests {
         assert_eq!(MultiThreadedTokenizer::tokenize_list(&base_tokenizer, source_texts.clone()), expected_results);
     }
 
+    #[test]
+    fn test_tokenize_with_offsets() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let base_tokenizer: BaseTokenizer<BertVocab> = BaseTokenizer::from_existing_vocab(vocab, true);
+        let test_tuples = [
+            (
+                "Sentence with [MASK] token.",
+                (vec!("sentence", "with", "[MASK]", "token", "."),
+                 vec!((0, 8), (9, 13), (14, 20), (21, 26), (26, 27)))
+            ),
+            (
+                "Délivre \t\n  moi!",
+                (vec!("delivre", "moi", "!"),
+                 vec!((0, 7), (12, 15), (15, 16)))
+            ),
+            (
+                "[UNK]中华 asdf",
+                (vec!("[UNK]", "中", "华", "asdf"),
+                 vec!((0, 5), (5, 6), (6, 7), (8, 12)))
+            ),
+            (
+                "",
+                (vec!(), vec!())
+            )
+        ];
+
+//        When & Then
+        for (source_text, (expected_tokens, expected_offsets)) in test_tuples.iter() {
+            let (tokens, offsets) = base_tokenizer.tokenize_with_offsets(source_text);
+            let expected_offsets: Vec<Option<Offset>> = expected_offsets
+                .iter()
+                .map(|&(begin, end)| Some(Offset { begin, end }))
+                .collect();
+            assert_eq!(tokens, *expected_tokens);
+            assert_eq!(offsets, expected_offsets);
+        }
+    }
+
     #[test]
     fn test_convert_tokens_to_ids() {
 //        Given
@@ -436,19 +932,19 @@ mod tests {
         let test_tuples = [
             (
                 "hello world!",
-                TokenizedInput { token_ids: vec!(0, 1, 3), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(0, 1, 3), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 11, end: 12 })), attention_mask: vec!(1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2)), sequence_ids: vec!(Some(0), Some(0), Some(0)) }
             ),
             (
                 "hello, unaffable world!",
-                TokenizedInput { token_ids: vec!(0, 2, 2, 1, 3), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(0, 2, 2, 1, 3), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 5, end: 6 }), Some(Offset { begin: 7, end: 16 }), Some(Offset { begin: 17, end: 22 }), Some(Offset { begin: 22, end: 23 })), attention_mask: vec!(1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0)) }
             ),
             (
                 "[UNK]中华人民共和国 [PAD] asdf",
-                TokenizedInput { token_ids: vec!(2, 7, 8, 9, 2, 2, 2, 2, 10, 2), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(2, 7, 8, 9, 2, 2, 2, 2, 10, 2), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 1, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 5, end: 6 }), Some(Offset { begin: 6, end: 7 }), Some(Offset { begin: 7, end: 8 }), Some(Offset { begin: 8, end: 9 }), Some(Offset { begin: 9, end: 10 }), Some(Offset { begin: 10, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 19, end: 23 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(7), Some(8), Some(9)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0)) }
             ),
             (
                 "[UNK] a ! c ! e ! g ! i ! [PAD] a ! c ! e ! g ! i !",
-                TokenizedInput { token_ids: vec!(2, 2, 3, 2, 3, 2, 3, 2, 3, 2), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(3, 10, 2, 3, 2, 3, 2, 3, 2, 3, 2, 3), num_truncated_tokens: 12 }
+                TokenizedInput { token_ids: vec!(2, 2, 3, 2, 3, 2, 3, 2, 3, 2), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(3, 10, 2, 3, 2, 3, 2, 3, 2, 3, 2, 3), num_truncated_tokens: 12, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 7 }), Some(Offset { begin: 8, end: 9 }), Some(Offset { begin: 10, end: 11 }), Some(Offset { begin: 12, end: 13 }), Some(Offset { begin: 14, end: 15 }), Some(Offset { begin: 16, end: 17 }), Some(Offset { begin: 18, end: 19 }), Some(Offset { begin: 20, end: 21 }), Some(Offset { begin: 22, end: 23 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(7), Some(8), Some(9)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0)) }
             )
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -456,11 +952,11 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(base_tokenizer.encode(source_text, None, 10, &truncation_strategy, 0),
+            assert_eq!(base_tokenizer.encode(source_text, None, 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(Tokenizer::encode_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0), expected_results);
-        assert_eq!(MultiThreadedTokenizer::encode_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
@@ -473,22 +969,22 @@ mod tests {
 //            No truncation required
             (
                 ("hello world!", "This is the second sentence"),
-                TokenizedInput { token_ids: vec!(0, 1, 3, 2, 2, 2, 2, 2), segment_ids: vec!(0, 0, 0, 1, 1, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(0, 1, 3, 2, 2, 2, 2, 2), segment_ids: vec!(0, 0, 0, 1, 1, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 0, end: 4 }), Some(Offset { begin: 5, end: 7 }), Some(Offset { begin: 8, end: 11 }), Some(Offset { begin: 12, end: 18 }), Some(Offset { begin: 19, end: 27 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(0), Some(1), Some(2), Some(3), Some(4)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1)) }
             ),
 //            Truncation of sentence 2 (longest)
             (
                 ("hello world!", "!This is the second sentence!!!"),
-                TokenizedInput { token_ids: vec!(0, 1, 3, 3, 2, 2, 2, 2, 2, 3), segment_ids: vec!(0, 0, 0, 1, 1, 1, 1, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 2 }
+                TokenizedInput { token_ids: vec!(0, 1, 3, 3, 2, 2, 2, 2, 2, 3), segment_ids: vec!(0, 0, 0, 1, 1, 1, 1, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 2, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), Some(Offset { begin: 20, end: 28 }), Some(Offset { begin: 28, end: 29 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1), Some(1), Some(1)) }
             ),
 //            Truncation of sentence 1 (longest)
             (
                 ("[UNK] hello  hello  hello  hello  hello  hello  hello  hello  hello  hello  hello", "!!!"),
-                TokenizedInput { token_ids: vec!(2, 0, 0, 0, 0, 0, 0, 3, 3, 3), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(0, 0, 0, 0, 0), num_truncated_tokens: 5 }
+                TokenizedInput { token_ids: vec!(2, 0, 0, 0, 0, 0, 0, 3, 3, 3), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(0, 0, 0, 0, 0), num_truncated_tokens: 5, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 34, end: 39 }), Some(Offset { begin: 41, end: 46 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(0), Some(1), Some(2)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), Some(1), Some(1)) }
             ),
 //            Truncation of both sentences (longest)
             (
                 ("[UNK] hello  hello  hello  hello  hello", "!!!!!!!!"),
-                TokenizedInput { token_ids: vec!(2, 0, 0, 0, 0, 3, 3, 3, 3, 3), segment_ids: vec!(0, 0, 0, 0, 0, 1, 1, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(0), num_truncated_tokens: 4 }
+                TokenizedInput { token_ids: vec!(2, 0, 0, 0, 0, 3, 3, 3, 3, 3), segment_ids: vec!(0, 0, 0, 0, 0, 1, 1, 1, 1, 1), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(0), num_truncated_tokens: 4, token_offsets: vec!(Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), Some(Offset { begin: 4, end: 5 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4), Some(0), Some(1), Some(2), Some(3), Some(4)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1)) }
             )
         ];
         let source_texts: Vec<(&str, &str)> = test_tuples.iter().map(|v| v.0).collect();
@@ -496,11 +992,11 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(base_tokenizer.encode(source_text.0, Some(source_text.1), 10, &truncation_strategy, 0),
+            assert_eq!(base_tokenizer.encode(source_text.0, Some(source_text.1), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(Tokenizer::encode_pair_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0), expected_results);
-        assert_eq!(MultiThreadedTokenizer::encode_pair_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_pair_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_pair_list(&base_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
@@ -11,27 +11,82 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::preprocessing::tokenizer::base_tokenizer::{MultiThreadedTokenizer, BaseTokenizer, Tokenizer};
+use crate::preprocessing::tokenizer::base_tokenizer::{MultiThreadedTokenizer, BaseTokenizer, Tokenizer, Token};
 use std::sync::Arc;
-use crate::preprocessing::tokenizer::tokenization_utils::{tokenize_wordpiece, split_on_special_tokens};
-use crate::preprocessing::vocab::base_vocab::Vocab;
+use crate::preprocessing::tokenizer::tokenization_utils::tokenize_wordpiece_with_offsets;
+use crate::preprocessing::tokenizer::special_token_matcher::{SpecialTokenMatcher, register_added_tokens, restore_added_tokens};
+use crate::preprocessing::tokenizer::template_processor::TemplateProcessor;
+use crate::preprocessing::vocab::base_vocab::{Vocab, role_value};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_files::{VOCAB_TXT_FILE, SPECIAL_TOKENS_MAP_FILE, create_directory, file_path, write_tokenizer_config, read_tokenizer_config, read_special_token_map, write_added_tokens, read_added_tokens};
+use crate::preprocessing::tokenizer::tokenizer_json::{read_tokenizer_json, bert_tokenizer_from_json};
 use crate::BertVocab;
 
 pub struct BertTokenizer {
     vocab: Arc<BertVocab>,
+    special_token_matcher: SpecialTokenMatcher,
+    post_processor: TemplateProcessor,
     base_tokenizer: BaseTokenizer<BertVocab>,
 }
 
 impl BertTokenizer {
-    pub fn from_file(path: &str, lower_case: bool) -> BertTokenizer {
-        let vocab = Arc::new(BertVocab::from_file(path));
+    pub fn from_file(path: &str, lower_case: bool) -> Result<BertTokenizer, TokenizerError> {
+        let vocab = Arc::new(BertVocab::from_file(path)?);
         let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case);
-        BertTokenizer { vocab, base_tokenizer }
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        let post_processor = TemplateProcessor::bert(vocab.token_to_id(role_value(vocab.cls_value(), "cls")), vocab.token_to_id(role_value(vocab.sep_value(), "sep")));
+        Ok(BertTokenizer { vocab, special_token_matcher, post_processor, base_tokenizer })
+    }
+
+    /// Loads the tokenizer from a HuggingFace `tokenizer.json` file, taking the lower casing setting from its normalizer.
+    pub fn from_tokenizer_json(path: &str) -> Result<BertTokenizer, TokenizerError> {
+        bert_tokenizer_from_json(&read_tokenizer_json(path)?)
     }
 
     pub fn from_existing_vocab(vocab: Arc<BertVocab>, lower_case: bool) -> BertTokenizer {
         let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case);
-        BertTokenizer { vocab, base_tokenizer }
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        let post_processor = TemplateProcessor::bert(vocab.token_to_id(role_value(vocab.cls_value(), "cls")), vocab.token_to_id(role_value(vocab.sep_value(), "sep")));
+        BertTokenizer { vocab, special_token_matcher, post_processor, base_tokenizer }
+    }
+
+    /// Writes the vocabulary, the special token map, the added tokens and a configuration holding the lower casing setting to `directory`, created if needed.
+    pub fn save(&self, directory: &str) -> Result<(), TokenizerError> {
+        create_directory(directory)?;
+        self.vocab.to_file(&file_path(directory, VOCAB_TXT_FILE))?;
+        self.vocab.special_token_map().to_file(&file_path(directory, SPECIAL_TOKENS_MAP_FILE))?;
+        write_added_tokens(directory, self.special_token_matcher.added_tokens())?;
+        write_tokenizer_config(directory, self.base_tokenizer.lower_case(), None)
+    }
+
+    /// Loads a tokenizer written by `save`.
+    pub fn from_directory(directory: &str) -> Result<BertTokenizer, TokenizerError> {
+        let (lower_case, _) = read_tokenizer_config(directory)?;
+        let special_token_map = read_special_token_map::<BertVocab>(directory)?;
+        let vocab = BertVocab::from_file_with_special_token_map(&file_path(directory, VOCAB_TXT_FILE), special_token_map)?;
+        let mut tokenizer = BertTokenizer::from_existing_vocab(Arc::new(vocab), lower_case);
+        restore_added_tokens(&mut tokenizer.vocab, &mut tokenizer.special_token_matcher, &read_added_tokens(directory)?);
+        tokenizer.base_tokenizer = BaseTokenizer::from_existing_vocab(tokenizer.vocab.clone(), lower_case);
+        Ok(tokenizer)
+    }
+
+    pub fn lower_case(&self) -> bool {
+        self.base_tokenizer.lower_case()
+    }
+
+    /// Adds tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer. Returns the id of each token.
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        let token_ids = register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, false);
+        self.base_tokenizer = BaseTokenizer::from_existing_vocab(self.vocab.clone(), self.base_tokenizer.lower_case());
+        token_ids
+    }
+
+    /// Adds special tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer, are flagged in the
+    /// special tokens mask and are skipped when decoding with `skip_special_tokens`. Returns the id of each token.
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        let token_ids = register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, true);
+        self.base_tokenizer = BaseTokenizer::from_existing_vocab(self.vocab.clone(), self.base_tokenizer.lower_case());
+        token_ids
     }
 }
 
@@ -40,41 +95,28 @@ impl Tokenizer<BertVocab> for BertTokenizer {
         self.vocab.as_ref()
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let mut tokenized_text: Vec<String> = Vec::with_capacity(text.len());
-        let temp_text = split_on_special_tokens(text, self.vocab.as_ref());
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(initial_token).into_iter().flatten().collect()
+    }
+
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        let mut words: Vec<Vec<Token>> = vec!();
+        let temp_text = self.special_token_matcher.split_with_offsets(initial_token);
         for text in temp_text {
-            tokenized_text.extend(self.base_tokenizer.tokenize(text));
+            if self.special_token_matcher.contains(&text.text) {
+                words.push(vec!(text));
+            } else {
+                words.extend(self.base_tokenizer
+                    .tokenize_to_tokens(&text)
+                    .iter()
+                    .map(|v| tokenize_wordpiece_with_offsets(v, self.vocab.as_ref(), 100)));
+            }
         }
-
-        let tokenized_text: Vec<String> = tokenized_text
-            .into_iter()
-            .map(|v| tokenize_wordpiece(v, self.vocab.as_ref(), 100))
-            .flatten()
-            .map(|s| s.to_string())
-            .collect();
-        tokenized_text
+        words
     }
 
     fn build_input_with_special_tokens(&self, tokens_1: Vec<i64>, tokens_2: Option<Vec<i64>>) -> (Vec<i64>, Vec<i8>, Vec<i8>) {
-        let mut output: Vec<i64> = vec!();
-        let mut token_segment_ids: Vec<i8> = vec!();
-        let mut special_tokens_mask: Vec<i8> = vec!();
-        special_tokens_mask.push(1);
-        special_tokens_mask.extend(vec![0; tokens_1.len()]);
-        special_tokens_mask.push(1);
-        token_segment_ids.extend(vec![0; tokens_1.len() + 2]);
-        output.push(self.vocab.token_to_id(BertVocab::cls_value()));
-        output.extend(tokens_1);
-        output.push(self.vocab.token_to_id(BertVocab::sep_value()));
-        if let Some(add_tokens) = tokens_2 {
-            special_tokens_mask.extend(vec![0; add_tokens.len()]);
-            special_tokens_mask.push(1);
-            token_segment_ids.extend(vec![1; add_tokens.len() + 1]);
-            output.extend(add_tokens);
-            output.push(self.vocab.token_to_id(BertVocab::sep_value()));
-        }
-        (output, token_segment_ids, special_tokens_mask)
+        self.post_processor.build_input_with_special_tokens(tokens_1, tokens_2)
     }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
@@ -93,8 +135,8 @@ mod tests {
     use super::*;
     use crate::BertVocab;
     use std::collections::HashMap;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput};
-    use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
+    use crate::preprocessing::vocab::base_vocab::{swap_key_values, SpecialTokenMap};
     use itertools::Itertools;
 
     fn generate_test_vocab() -> BertVocab {
@@ -126,7 +168,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        BertVocab { values, indices, unknown_value: "[UNK]", special_values, special_indices }
+        BertVocab { values, indices, special_token_map: BertVocab::default_special_token_map(), special_values, special_indices }
     }
 
     #[test]
@@ -160,6 +202,25 @@ mod tests {
         assert_eq!(MultiThreadedTokenizer::tokenize_list(&bert_tokenizer, source_texts.clone()), expected_results);
     }
 
+    #[test]
+    fn test_save_and_reload_bert_tokenizer() -> Result<(), TokenizerError> {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, false);
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path().join("bert").to_string_lossy().into_owned();
+
+//        When
+        bert_tokenizer.save(&directory)?;
+        let reloaded_tokenizer = BertTokenizer::from_directory(&directory)?;
+
+//        Then
+        assert_eq!(reloaded_tokenizer.vocab.values, bert_tokenizer.vocab.values);
+        assert_eq!(reloaded_tokenizer.tokenize("Hello [MASK] world!"), vec!("[UNK]", "[MASK]", "world", "!"));
+        assert_eq!(reloaded_tokenizer.tokenize("Hello [MASK] world!"), bert_tokenizer.tokenize("Hello [MASK] world!"));
+        Ok(())
+    }
+
     #[test]
     fn test_bert_tokenizer_no_lower_casing() {
 //        Given
@@ -200,15 +261,15 @@ mod tests {
         let test_tuples = [
             (
                 "hello[MASK] world!",
-                TokenizedInput { token_ids: vec!(4, 0, 6, 1, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(4, 0, 6, 1, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 1, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 5, end: 11 }), Some(Offset { begin: 12, end: 17 }), Some(Offset { begin: 17, end: 18 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(2), Some(3), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), Some(0), None) }
             ),
             (
                 "hello, unaffable world!",
-                TokenizedInput { token_ids: vec!(4, 0, 2, 11, 12, 13, 1, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(4, 0, 2, 11, 12, 13, 1, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 5, end: 6 }), Some(Offset { begin: 7, end: 10 }), Some(Offset { begin: 10, end: 13 }), Some(Offset { begin: 13, end: 16 }), Some(Offset { begin: 17, end: 22 }), Some(Offset { begin: 22, end: 23 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(2), Some(2), Some(2), Some(3), Some(4), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), None) }
             ),
             (
                 "[UNK]中华人民共和国 [PAD] asdf",
-                TokenizedInput { token_ids: vec!(4, 2, 7, 8, 9, 2, 2, 2, 2, 10, 2, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(4, 2, 7, 8, 9, 2, 2, 2, 2, 10, 2, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 5, end: 6 }), Some(Offset { begin: 6, end: 7 }), Some(Offset { begin: 7, end: 8 }), Some(Offset { begin: 8, end: 9 }), Some(Offset { begin: 9, end: 10 }), Some(Offset { begin: 10, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 19, end: 23 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(7), Some(8), Some(9), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), None) }
             )
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -216,11 +277,11 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(bert_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+            assert_eq!(bert_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(Tokenizer::encode_list(&bert_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0), expected_results);
-        assert_eq!(MultiThreadedTokenizer::encode_list(&bert_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_list(&bert_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_list(&bert_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
@@ -233,22 +294,22 @@ mod tests {
 //            No truncation required
             (
                 ("hello world", "This is the second sentence"),
-                TokenizedInput { token_ids: vec!(4, 0, 1, 5, 2, 2, 2, 2, 2, 5), segment_ids: vec!(0, 0, 0, 0, 1, 1, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 1, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(4, 0, 1, 5, 2, 2, 2, 2, 2, 5), segment_ids: vec!(0, 0, 0, 0, 1, 1, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 1, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 4 }), Some(Offset { begin: 5, end: 7 }), Some(Offset { begin: 8, end: 11 }), Some(Offset { begin: 12, end: 18 }), Some(Offset { begin: 19, end: 27 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), None, Some(0), Some(1), Some(2), Some(3), Some(4), None), sequence_ids: vec!(None, Some(0), Some(0), None, Some(1), Some(1), Some(1), Some(1), Some(1), None) }
             ),
 //            Truncation of sentence 2 (longest)
             (
                 ("hello world", "!This is the second sentence!!!"),
-                TokenizedInput { token_ids: vec!(4, 0, 1, 5, 3, 2, 2, 2, 2, 5), segment_ids: vec!(0, 0, 0, 0, 1, 1, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 1, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 4 }
+                TokenizedInput { token_ids: vec!(4, 0, 1, 5, 3, 2, 2, 2, 2, 5), segment_ids: vec!(0, 0, 0, 0, 1, 1, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 1, 0, 0, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 4, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), None, Some(0), Some(1), Some(2), Some(3), Some(4), None), sequence_ids: vec!(None, Some(0), Some(0), None, Some(1), Some(1), Some(1), Some(1), Some(1), None) }
             ),
 //            Truncation of sentence 1 (longest)
             (
                 ("[UNK] hello  hello  hello  hello  hello  hello  hello  hello  hello  hello  hello", "!!!"),
-                TokenizedInput { token_ids: vec!(4, 2, 0, 0, 0, 5, 3, 3, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 0, 0, 1, 0, 0, 0, 1), overflowing_tokens: vec!(0, 0, 0, 0, 0, 0, 0, 0), num_truncated_tokens: 8 }
+                TokenizedInput { token_ids: vec!(4, 2, 0, 0, 0, 5, 3, 3, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 0, 0, 1, 0, 0, 0, 1), overflowing_tokens: vec!(0, 0, 0, 0, 0, 0, 0, 0), num_truncated_tokens: 8, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(2), Some(3), None, Some(0), Some(1), Some(2), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), Some(0), None, Some(1), Some(1), Some(1), None) }
             ),
 //            Truncation of both sentences (longest)
             (
                 ("[UNK] hello  hello  hello  hello  hello", "!!!!!!!!"),
-                TokenizedInput { token_ids: vec!(4, 2, 0, 0, 5, 3, 3, 3, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 1, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 0, 1, 0, 0, 0, 0, 1), overflowing_tokens: vec!(0, 0, 0), num_truncated_tokens: 7 }
+                TokenizedInput { token_ids: vec!(4, 2, 0, 0, 5, 3, 3, 3, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0, 1, 1, 1, 1, 1), special_tokens_mask: vec!(1, 0, 0, 0, 1, 0, 0, 0, 0, 1), overflowing_tokens: vec!(0, 0, 0), num_truncated_tokens: 7, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(2), None, Some(0), Some(1), Some(2), Some(3), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None, Some(1), Some(1), Some(1), Some(1), None) }
             )
         ];
         let source_texts: Vec<(&str, &str)> = test_tuples.iter().map(|v| v.0).collect();
@@ -256,11 +317,166 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(bert_tokenizer.encode(source_text.0, Some(source_text.1), 10, &truncation_strategy, 0),
+            assert_eq!(bert_tokenizer.encode(source_text.0, Some(source_text.1), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(Tokenizer::encode_pair_list(&bert_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0), expected_results);
-        assert_eq!(MultiThreadedTokenizer::encode_pair_list(&bert_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_pair_list(&bert_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_pair_list(&bert_tokenizer, source_texts.clone(), 10, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+    }
+
+    #[test]
+    fn test_encode_with_padding() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+        let truncation_strategy = TruncationStrategy::LongestFirst;
+        let right_padded = TokenizedInput { token_ids: vec!(4, 0, 1, 5, 10, 10), segment_ids: vec!(0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 1, 1, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, None, None), attention_mask: vec!(1, 1, 1, 1, 0, 0), word_ids: vec!(None, Some(0), Some(1), None, None, None), sequence_ids: vec!(None, Some(0), Some(0), None, None, None) };
+        let left_padded = TokenizedInput { token_ids: vec!(10, 10, 4, 0, 1, 5), segment_ids: vec!(0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 1, 1, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, None, None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None), attention_mask: vec!(0, 0, 1, 1, 1, 1), word_ids: vec!(None, None, None, Some(0), Some(1), None), sequence_ids: vec!(None, None, None, Some(0), Some(0), None) };
+        let unpadded = TokenizedInput { token_ids: vec!(4, 0, 5), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(1, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), None), attention_mask: vec!(1, 1, 1), word_ids: vec!(None, Some(0), None), sequence_ids: vec!(None, Some(0), None) };
+        let mut padded_to_longest = unpadded.clone();
+        padded_to_longest.pad(4, 10, &PaddingSide::Right);
+
+//        When & Then
+        assert_eq!(bert_tokenizer.encode("hello world", None, 6, &truncation_strategy, 0, &PaddingStrategy::MaxLength, &PaddingSide::Right), right_padded);
+        assert_eq!(bert_tokenizer.encode("hello world", None, 6, &truncation_strategy, 0, &PaddingStrategy::MaxLength, &PaddingSide::Left), left_padded);
+        assert_eq!(bert_tokenizer.encode("hello world", None, 128, &truncation_strategy, 0, &PaddingStrategy::MultipleOf(3), &PaddingSide::Right), right_padded);
+        assert_eq!(bert_tokenizer.encode("hello", None, 128, &truncation_strategy, 0, &PaddingStrategy::Longest, &PaddingSide::Right), unpadded);
+        assert_eq!(Tokenizer::encode_list(&bert_tokenizer, vec!("hello", "hello world"), 128, &truncation_strategy, 0, &PaddingStrategy::Longest, &PaddingSide::Right),
+                   vec!(padded_to_longest.clone(), bert_tokenizer.encode("hello world", None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right)));
+        assert_eq!(MultiThreadedTokenizer::encode_list(&bert_tokenizer, vec!("hello", "hello world"), 128, &truncation_strategy, 0, &PaddingStrategy::Longest, &PaddingSide::Right),
+                   vec!(padded_to_longest, bert_tokenizer.encode("hello world", None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right)));
+        let pair_attention_masks: Vec<Vec<i8>> = MultiThreadedTokenizer::encode_pair_list(&bert_tokenizer, vec!(("hello", "world"), ("hello world", "hello world")), 128, &truncation_strategy, 0, &PaddingStrategy::Longest, &PaddingSide::Right)
+            .into_iter()
+            .map(|tokenized_input| tokenized_input.attention_mask)
+            .collect();
+        assert_eq!(pair_attention_masks, vec!(vec!(1, 1, 1, 1, 1, 0, 0), vec!(1, 1, 1, 1, 1, 1, 1)));
+    }
+
+    #[test]
+    fn test_try_encode_truncation_error() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+
+//        When & Then
+        assert!(bert_tokenizer.try_encode("hello world", Some("hello"), 128, &TruncationStrategy::DoNotTruncate, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right).is_ok());
+        assert_eq!(bert_tokenizer.try_encode("hello world", Some("hello"), 4, &TruncationStrategy::DoNotTruncate, 0, &PaddingStrategy::MaxLength, &PaddingSide::Right),
+                   bert_tokenizer.try_encode("hello world", Some("hello"), 128, &TruncationStrategy::DoNotTruncate, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right));
+        assert_eq!(bert_tokenizer.try_encode("hello", None, 2, &TruncationStrategy::OnlySecond, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
+                   Err(TokenizerError::TruncationError("Invalid truncation strategy for single sentence truncation".to_owned())));
+        assert_eq!(bert_tokenizer.try_encode("hello", Some("hello world"), 3, &TruncationStrategy::OnlyFirst, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
+                   Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned())));
+    }
+
+    #[test]
+    fn test_word_alignment() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+
+//        When
+        let tokenized_input = bert_tokenizer.encode("Hello, unaffable world!", Some("hello [MASK]"), 128, &TruncationStrategy::LongestFirst, 0,
+                                                    &PaddingStrategy::MaxLength, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(tokenized_input.token_ids[..12], [4, 0, 2, 11, 12, 13, 1, 3, 5, 0, 6, 5]);
+        assert_eq!(tokenized_input.word_ids[..13], [None, Some(0), Some(1), Some(2), Some(2), Some(2), Some(3), Some(4), None, Some(0), Some(1), None, None]);
+        assert_eq!(tokenized_input.sequence_ids[..13], [None, Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), None, Some(1), Some(1), None, None]);
+        assert_eq!(tokenized_input.word_to_tokens(2, 0), Some((3, 6)));
+        assert_eq!(tokenized_input.word_to_tokens(1, 1), Some((10, 11)));
+        assert_eq!(tokenized_input.word_to_tokens(5, 0), None);
+        assert_eq!(tokenized_input.token_to_word(4), Some(2));
+        assert_eq!(tokenized_input.token_to_word(0), None);
+        assert_eq!(tokenized_input.token_to_word(127), None);
+        assert_eq!(tokenized_input.token_to_word(128), None);
+    }
+
+    #[test]
+    fn test_try_encode_list_errors() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+        let text_list = vec!("hello world", "hello world ! hello", "hello");
+
+//        When
+        let results = Tokenizer::try_encode_list(&bert_tokenizer, text_list.clone(), 4, &TruncationStrategy::OnlySecond, 0,
+                                                 &PaddingStrategy::Longest, &PaddingSide::Right);
+        let multithreaded_results = MultiThreadedTokenizer::try_encode_list(&bert_tokenizer, text_list, 4, &TruncationStrategy::OnlySecond, 0,
+                                                                            &PaddingStrategy::Longest, &PaddingSide::Right);
+        let untruncated_inputs = Tokenizer::encode_list(&bert_tokenizer, vec!("hello world", "hello world ! hello"), 4, &TruncationStrategy::DoNotTruncate, 0,
+                                                               &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(results.iter().map(|result| result.as_ref().map(|input| input.token_ids.clone())).collect::<Vec<Result<Vec<i64>, &TokenizerError>>>(),
+                   vec!(Ok(vec!(4, 0, 1, 5)),
+                        Err(&TokenizerError::TruncationError("Invalid truncation strategy for single sentence truncation".to_owned())),
+                        Ok(vec!(4, 0, 5, 10))));
+        assert_eq!(results, multithreaded_results);
+        assert_eq!(untruncated_inputs.iter().map(|input| input.token_ids.clone()).collect::<Vec<Vec<i64>>>(),
+                   vec!(vec!(4, 0, 1, 5), vec!(4, 0, 1, 3, 0, 5)));
+        assert_eq!(untruncated_inputs[1].num_truncated_tokens, 0);
+    }
+
+    #[test]
+    fn test_encode_with_left_and_head_tail_truncation() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+        let text = "hello world ! hello world !";
+
+//        When
+        let left_truncated = bert_tokenizer.encode(text, None, 5, &TruncationStrategy::OnlyFirstLeft, 0,
+                                                   &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+        let head_tail_truncated = bert_tokenizer.encode(text, None, 5, &TruncationStrategy::HeadTail { head: 1 }, 1,
+                                                        &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(left_truncated, TokenizedInput { token_ids: vec!(4, 0, 1, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 1), overflowing_tokens: vec!(0, 1, 3), num_truncated_tokens: 3, token_offsets: vec!(None, Some(Offset { begin: 14, end: 19 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 26, end: 27 }), None), attention_mask: vec!(1, 1, 1, 1, 1), word_ids: vec!(None, Some(3), Some(4), Some(5), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None) });
+        assert_eq!(head_tail_truncated, TokenizedInput { token_ids: vec!(4, 0, 1, 3, 5), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 1), overflowing_tokens: vec!(0, 1, 3, 0, 1), num_truncated_tokens: 3, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 26, end: 27 }), None), attention_mask: vec!(1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(4), Some(5), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None) });
+    }
+
+    #[test]
+    fn test_encode_windows() -> Result<(), TokenizerError> {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+        let context = "hello world ! hello world !";
+
+//        When
+        let windows = bert_tokenizer.try_encode_windows(context, Some("hello"), 7, &TruncationStrategy::OnlyFirst, 1,
+                                                        &PaddingStrategy::Longest, &PaddingSide::Right)?;
+        let question_first_windows = bert_tokenizer.try_encode_windows("hello", Some(context), 7, &TruncationStrategy::OnlySecond, 1,
+                                                                       &PaddingStrategy::DoNotPad, &PaddingSide::Right)?;
+
+//        Then
+        assert_eq!(windows, vec!(
+            TokenizedInput { token_ids: vec!(4, 0, 1, 3, 5, 0, 5), segment_ids: vec!(0, 0, 0, 0, 0, 1, 1), special_tokens_mask: vec!(1, 0, 0, 0, 1, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 12, end: 13 }), None, Some(Offset { begin: 0, end: 5 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(2), None, Some(0), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None, Some(1), None) },
+            TokenizedInput { token_ids: vec!(4, 3, 0, 1, 5, 0, 5), segment_ids: vec!(0, 0, 0, 0, 0, 1, 1), special_tokens_mask: vec!(1, 0, 0, 0, 1, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 12, end: 13 }), Some(Offset { begin: 14, end: 19 }), Some(Offset { begin: 20, end: 25 }), None, Some(Offset { begin: 0, end: 5 }), None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1), word_ids: vec!(None, Some(2), Some(3), Some(4), None, Some(0), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None, Some(1), None) },
+            TokenizedInput { token_ids: vec!(4, 1, 3, 5, 0, 5, 10), segment_ids: vec!(0, 0, 0, 0, 1, 1, 0), special_tokens_mask: vec!(1, 0, 0, 1, 0, 1, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 26, end: 27 }), None, Some(Offset { begin: 0, end: 5 }), None, None), attention_mask: vec!(1, 1, 1, 1, 1, 1, 0), word_ids: vec!(None, Some(4), Some(5), None, Some(0), None, None), sequence_ids: vec!(None, Some(0), Some(0), None, Some(1), None, None) },
+        ));
+        assert_eq!(question_first_windows.iter().map(|window| window.token_ids.clone()).collect::<Vec<Vec<i64>>>(),
+                   vec!(vec!(4, 0, 5, 0, 1, 3, 5), vec!(4, 0, 5, 3, 0, 1, 5), vec!(4, 0, 5, 1, 3, 5)));
+        assert_eq!(question_first_windows[1].segment_ids, vec!(0, 0, 0, 1, 1, 1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_windows_errors() -> Result<(), TokenizerError> {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+        let context = "hello world ! hello world !";
+
+//        When & Then
+        assert_eq!(bert_tokenizer.try_encode_windows(context, Some("hello"), 128, &TruncationStrategy::DoNotTruncate, 1, &PaddingStrategy::DoNotPad, &PaddingSide::Right)?,
+                   vec!(bert_tokenizer.encode(context, Some("hello"), 128, &TruncationStrategy::DoNotTruncate, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right)));
+        assert_eq!(bert_tokenizer.try_encode_windows(context, Some("hello"), 7, &TruncationStrategy::DoNotTruncate, 1, &PaddingStrategy::DoNotPad, &PaddingSide::Right)?,
+                   vec!(bert_tokenizer.encode(context, Some("hello"), 128, &TruncationStrategy::DoNotTruncate, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right)));
+        assert_eq!(bert_tokenizer.try_encode_windows(context, Some("hello"), 7, &TruncationStrategy::OnlyFirst, 3, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
+                   Err(TokenizerError::TruncationError("The stride (3) must be smaller than the window length (3)".to_owned())));
+        assert_eq!(bert_tokenizer.try_encode_windows(context, Some("hello"), 4, &TruncationStrategy::OnlyFirst, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
+                   Err(TokenizerError::TruncationError("The sequence kept in full and the special tokens do not fit in max_len".to_owned())));
+        Ok(())
     }
 
     #[test]
@@ -320,4 +536,65 @@ mod tests {
         assert_eq!(Tokenizer::decode_list(&bert_tokenizer, source_ids.clone(), skip_special_tokens, clean_up_tokenization_spaces), expected_results);
         assert_eq!(MultiThreadedTokenizer::decode_list(&bert_tokenizer, source_ids.clone(), skip_special_tokens, clean_up_tokenization_spaces), expected_results);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_add_tokens() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let mut bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab.clone(), true);
+
+//        When
+        let token_ids = bert_tokenizer.add_tokens(&["<Domain>"]);
+        let special_token_ids = bert_tokenizer.add_special_tokens(&["[NEW]", "[MASK]"]);
+        let tokens = bert_tokenizer.tokenize("Hello<Domain>world [NEW]!");
+        let tokenized_input = bert_tokenizer.encode("hello<Domain>world [NEW]!", None, 128, &TruncationStrategy::LongestFirst, 0,
+                                                    &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(token_ids, vec!(14));
+        assert_eq!(special_token_ids, vec!(15, 6));
+        assert_eq!(tokens, vec!("hello", "<Domain>", "world", "[NEW]", "!"));
+        assert_eq!(tokenized_input.token_ids, vec!(4, 0, 14, 1, 15, 3, 5));
+        assert_eq!(tokenized_input.special_tokens_mask, vec!(1, 0, 0, 0, 1, 0, 1));
+        assert_eq!(tokenized_input.token_offsets[2], Some(Offset { begin: 5, end: 13 }));
+        assert_eq!(tokenized_input.token_offsets[4], Some(Offset { begin: 19, end: 24 }));
+        assert_eq!(bert_tokenizer.decode(tokenized_input.token_ids.clone(), false, false), "[CLS] hello <Domain> world [NEW] ! [SEP]");
+        assert_eq!(bert_tokenizer.decode(tokenized_input.token_ids, true, false), "hello <Domain> world !");
+//        The vocabulary shared with the tokenizer is left unchanged
+        assert!(!vocab.values.contains_key("<Domain>"));
+        assert_eq!(BertTokenizer::from_existing_vocab(vocab, true).tokenize("[NEW]"), vec!("[UNK]", "[UNK]", "[UNK]"));
+    }
+
+    #[test]
+    fn test_encode_with_special_token_map() -> Result<(), TokenizerError> {
+//        Given
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("world".to_owned(), 1),
+            ("<unk>".to_owned(), 2),
+            ("!".to_owned(), 3),
+            ("<s>".to_owned(), 4),
+            ("</s>".to_owned(), 5),
+            ("<pad>".to_owned(), 6)
+        ].iter().cloned().collect();
+        let special_token_map = SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            sep_token: Some("</s>".to_owned()),
+            cls_token: Some("<s>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        };
+        let vocab = Arc::new(BertVocab::from_values_with_special_token_map(values, special_token_map)?);
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true);
+
+//        When
+        let tokenized_input = bert_tokenizer.encode("hello unknown world!", None, 8, &TruncationStrategy::LongestFirst, 0,
+                                                    &PaddingStrategy::MaxLength, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(bert_tokenizer.tokenize("hello unknown"), vec!("hello", "<unk>"));
+        assert_eq!(tokenized_input.token_ids, vec!(4, 0, 2, 1, 3, 5, 6, 6));
+        assert_eq!(tokenized_input.special_tokens_mask, vec!(1, 0, 0, 0, 0, 1, 1, 1));
+        assert_eq!(bert_tokenizer.decode(tokenized_input.token_ids, false, false), "<s> hello <unk> world ! </s> <pad> <pad>");
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use lru::LruCache;
+
+pub const DEFAULT_BPE_CACHE_CAPACITY: usize = 10_000;
+const MAX_BPE_CACHE_SHARDS: usize = 16;
+const MIN_BPE_CACHE_SHARD_CAPACITY: usize = 64;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BpeCacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub size: usize,
+    pub capacity: usize,
+}
+
+/// Thread-safe cache of byte pair encoded words, shared by the BPE tokenizers.
+/// Words are spread by hash over up to 16 shards, each behind its own lock and holding an equal part of the capacity,
+/// so that threads tokenizing different words rarely wait on each other. Within a shard, the least recently used words
+/// are evicted first. A capacity of 0 disables caching.
+pub struct BpeCache {
+    shards: Vec<Mutex<LruCache<String, Vec<String>>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl BpeCache {
+    pub fn new(capacity: usize) -> BpeCache {
+        let shard_count = (capacity / MIN_BPE_CACHE_SHARD_CAPACITY).clamp(1, MAX_BPE_CACHE_SHARDS);
+        let shards = if capacity > 0 {
+            (0..shard_count)
+                .map(|index| {
+                    let shard_capacity = capacity / shard_count + if index < capacity % shard_count { 1 } else { 0 };
+                    Mutex::new(LruCache::new(shard_capacity))
+                })
+                .collect()
+        } else {
+            vec!()
+        };
+        BpeCache { shards, hits: AtomicUsize::new(0), misses: AtomicUsize::new(0) }
+    }
+
+    fn shard(&self, word: &str) -> Option<&Mutex<LruCache<String, Vec<String>>>> {
+        if self.shards.is_empty() {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        Some(&self.shards[(hasher.finish() % self.shards.len() as u64) as usize])
+    }
+
+    pub fn get(&self, word: &str) -> Option<Vec<String>> {
+        let value = match self.shard(word) {
+            Some(shard) => shard.lock().unwrap().get(word).cloned(),
+            None => None
+        };
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed)
+        };
+        value
+    }
+
+    pub fn insert(&self, word: String, value: Vec<String>) {
+        if let Some(shard) = self.shard(&word) {
+            shard.lock().unwrap().put(word, value);
+        }
+    }
+
+    /// Returns the cached value for the word, computing and caching it on a miss.
+    /// The computation runs without holding the lock.
+    pub fn get_or_insert_with<F>(&self, word: &str, compute: F) -> Vec<String>
+        where F: FnOnce() -> Vec<String> {
+        match self.get(word) {
+            Some(value) => value,
+            None => {
+                let value = compute();
+                self.insert(word.to_owned(), value.clone());
+                value
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().unwrap().clear();
+        }
+    }
+
+    pub fn reset_stats(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().cap()).sum()
+    }
+
+    pub fn stats(&self) -> BpeCacheStats {
+        BpeCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size: self.len(),
+            capacity: self.capacity(),
+        }
+    }
+}
+
+impl Default for BpeCache {
+    fn default() -> Self {
+        BpeCache::new(DEFAULT_BPE_CACHE_CAPACITY)
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_cache_hits_and_misses() {
+//        Given
+        let cache = BpeCache::new(10);
+
+//        When
+        let first = cache.get_or_insert_with("hello", || vec!("he".to_owned(), "llo".to_owned()));
+        let second = cache.get_or_insert_with("hello", || panic!("value should be cached"));
+
+//        Then
+        assert_eq!(first, vec!("he", "llo"));
+        assert_eq!(second, first);
+        assert_eq!(cache.stats(), BpeCacheStats { hits: 1, misses: 1, size: 1, capacity: 10 });
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+//        Given
+        let cache = BpeCache::new(2);
+        cache.insert("a".to_owned(), vec!("a".to_owned()));
+        cache.insert("b".to_owned(), vec!("b".to_owned()));
+
+//        When
+        cache.get("a");
+        cache.insert("c".to_owned(), vec!("c".to_owned()));
+
+//        Then
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("a"), Some(vec!("a".to_owned())));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(vec!("c".to_owned())));
+    }
+
+    #[test]
+    fn test_cache_clear_and_disable() {
+//        Given
+        let cache = BpeCache::new(10);
+        let disabled_cache = BpeCache::new(0);
+
+//        When
+        cache.insert("a".to_owned(), vec!("a".to_owned()));
+        disabled_cache.insert("a".to_owned(), vec!("a".to_owned()));
+        cache.clear();
+
+//        Then
+        assert!(cache.is_empty());
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(disabled_cache.get("a"), None);
+        assert_eq!(disabled_cache.stats(), BpeCacheStats { hits: 0, misses: 1, size: 0, capacity: 0 });
+        cache.reset_stats();
+        assert_eq!(cache.stats(), BpeCacheStats { hits: 0, misses: 0, size: 0, capacity: 10 });
+    }
+
+    #[test]
+    fn test_sharded_cache_stays_within_capacity() {
+//        Given
+        let cache = BpeCache::new(2000);
+
+//        When
+        for index in 0..10_000 {
+            cache.insert(index.to_string(), vec!(index.to_string()));
+        }
+
+//        Then
+        assert_eq!(cache.shards.len(), MAX_BPE_CACHE_SHARDS);
+        assert_eq!(cache.capacity(), 2000);
+        assert!(cache.len() <= 2000);
+        assert!(!cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_stats_consistent_across_threads() {
+//        Given
+        let cache = Arc::new(BpeCache::new(DEFAULT_BPE_CACHE_CAPACITY));
+        let thread_count = 8;
+        let rounds = 5;
+        let word_count = 200;
+
+//        When
+        let handles: Vec<_> = (0..thread_count)
+            .map(|_| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for _ in 0..rounds {
+                        for index in 0..word_count {
+                            let word = format!("word{}", index);
+                            let value = cache.get_or_insert_with(&word, || vec!(word.clone()));
+                            assert_eq!(value, vec!(word));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+//        Then
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, thread_count * rounds * word_count);
+        assert!(stats.misses >= word_count);
+        assert!(stats.misses <= thread_count * word_count);
+        assert_eq!(stats.size, word_count);
+        assert_eq!(stats.capacity, DEFAULT_BPE_CACHE_CAPACITY);
+    }
+}
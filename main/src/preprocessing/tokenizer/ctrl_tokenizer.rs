@@ -13,37 +13,91 @@
 
 use crate::OpenAiGptVocab;
 use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::preprocessing::tokenizer::base_tokenizer::Tokenizer;
-use std::collections::HashMap;
-use crate::preprocessing::tokenizer::tokenization_utils::{ctrl_bpe, split_on_special_tokens};
-use std::rc::Rc;
-use std::cell::RefCell;
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_files::{VOCAB_JSON_FILE, MERGES_FILE, SPECIAL_TOKENS_MAP_FILE, create_directory, file_path, write_tokenizer_config, read_tokenizer_config, read_special_token_map, write_added_tokens, read_added_tokens};
+use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, MultiThreadedTokenizer, Token};
+use crate::preprocessing::tokenizer::tokenization_utils::{ctrl_bpe, lowercase_with_offsets, bpe_sub_tokens_with_offsets};
+use crate::preprocessing::tokenizer::special_token_matcher::{SpecialTokenMatcher, register_added_tokens, restore_added_tokens};
+use std::sync::Arc;
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
 use regex::Regex;
-use itertools::Itertools;
 
 
 pub struct CtrlTokenizer {
-    vocab: Rc<OpenAiGptVocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, Vec<String>>>,
+    vocab: Arc<OpenAiGptVocab>,
+    special_token_matcher: SpecialTokenMatcher,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
     regex_pattern: Regex,
     lower_case: bool,
 }
 
 impl CtrlTokenizer {
-    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> CtrlTokenizer {
-        let vocab = Rc::new(OpenAiGptVocab::from_file(vocab_path));
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path));
-        let cache = RefCell::new(HashMap::new());
+    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> Result<CtrlTokenizer, TokenizerError> {
+        let vocab = Arc::new(OpenAiGptVocab::from_file(vocab_path)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::default();
         let regex_pattern = Regex::new(r"\S+\n?").unwrap();
-        CtrlTokenizer { vocab, bpe_ranks, cache, regex_pattern, lower_case }
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        Ok(CtrlTokenizer { vocab, special_token_matcher, bpe_ranks, cache, regex_pattern, lower_case })
     }
 
-    pub fn from_existing_vocab_and_merges(vocab: Rc<OpenAiGptVocab>, merges: Rc<BpePairVocab>, lower_case: bool) -> CtrlTokenizer {
-        let cache = RefCell::new(HashMap::new());
+    pub fn from_existing_vocab_and_merges(vocab: Arc<OpenAiGptVocab>, merges: Arc<BpePairVocab>, lower_case: bool) -> CtrlTokenizer {
+        let cache = BpeCache::default();
         let regex_pattern = Regex::new(r"\S+\n?").unwrap();
-        CtrlTokenizer { vocab, bpe_ranks: merges, cache, regex_pattern, lower_case }
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        CtrlTokenizer { vocab, special_token_matcher, bpe_ranks: merges, cache, regex_pattern, lower_case }
+    }
+
+    /// Writes the vocabulary, the merges, the special token map, the added tokens and a configuration holding the lower casing setting to `directory`, created if needed.
+    pub fn save(&self, directory: &str) -> Result<(), TokenizerError> {
+        create_directory(directory)?;
+        self.vocab.to_file(&file_path(directory, VOCAB_JSON_FILE))?;
+        self.bpe_ranks.to_file(&file_path(directory, MERGES_FILE))?;
+        self.vocab.special_token_map().to_file(&file_path(directory, SPECIAL_TOKENS_MAP_FILE))?;
+        write_added_tokens(directory, self.special_token_matcher.added_tokens())?;
+        write_tokenizer_config(directory, self.lower_case, None)
+    }
+
+    /// Loads a tokenizer written by `save`.
+    pub fn from_directory(directory: &str) -> Result<CtrlTokenizer, TokenizerError> {
+        let (lower_case, _) = read_tokenizer_config(directory)?;
+        let special_token_map = read_special_token_map::<OpenAiGptVocab>(directory)?;
+        let vocab = OpenAiGptVocab::from_file_with_special_token_map(&file_path(directory, VOCAB_JSON_FILE), special_token_map)?;
+        let merges = BpePairVocab::from_file(&file_path(directory, MERGES_FILE))?;
+        let mut tokenizer = CtrlTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), lower_case);
+        restore_added_tokens(&mut tokenizer.vocab, &mut tokenizer.special_token_matcher, &read_added_tokens(directory)?);
+        Ok(tokenizer)
+    }
+
+    /// Replaces the cache of byte pair encoded words by a cache bounded to `capacity` words. A capacity of 0 disables caching.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> CtrlTokenizer {
+        self.cache = BpeCache::new(capacity);
+        self
+    }
+
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
+
+    pub fn merges(&self) -> &BpePairVocab {
+        &self.bpe_ranks
+    }
+
+    pub fn lower_case(&self) -> bool {
+        self.lower_case
+    }
+
+    /// Adds tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer. Returns the id of each token.
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, false)
+    }
+
+    /// Adds special tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer, are flagged in the
+    /// special tokens mask and are skipped when decoding with `skip_special_tokens`. Returns the id of each token.
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, true)
     }
 }
 
@@ -52,35 +106,36 @@ impl Tokenizer<OpenAiGptVocab> for CtrlTokenizer {
         self.vocab.as_ref()
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let mut tokenized_text: Vec<String> = Vec::with_capacity(text.len());
-        let temp_text = split_on_special_tokens(text, self.vocab.as_ref());
-        let temp_text = temp_text
-            .into_iter()
-            .map(|v| if self.lower_case { v.to_lowercase() } else { v.to_owned() })
-            .collect_vec();
-
-        for text in temp_text {
-            if !self.vocab.special_values.contains_key(text.as_str()) {
-                for word in self.regex_pattern.find_iter(text.as_ref()) {
-                    let cached: bool = match self.cache.borrow().get(word.as_str()) {
-                        Some(value) => {
-                            tokenized_text.extend(value.clone());
-                            true
-                        }
-                        None => false
-                    };
-                    if !cached {
-                        let bpe_output = ctrl_bpe(word.as_str(), self.bpe_ranks.as_ref());
-                        self.cache.borrow_mut().insert(word.as_str().to_owned(), bpe_output.clone());
-                        tokenized_text.extend(bpe_output);
-                    }
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(initial_token).into_iter().flatten().collect()
+    }
+
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        let mut words: Vec<Vec<Token>> = vec!();
+
+        for text in self.special_token_matcher.split_with_offsets(initial_token) {
+            if !self.special_token_matcher.contains(&text.text) {
+                let text = if self.lower_case { lowercase_with_offsets(&text) } else { text };
+                let word_ranges: Vec<(usize, usize)> = self.regex_pattern
+                    .find_iter(text.text.as_str())
+                    .map(|hit| (hit.start(), hit.end()))
+                    .collect();
+                for word in text.slices(&word_ranges) {
+                    let bpe_output = self.cache.get_or_insert_with(&word.text, || ctrl_bpe(word.text.as_str(), self.bpe_ranks.as_ref()));
+//                    All sub-tokens but the last one carry an additional `@@` continuation marker
+                    let last_index = bpe_output.len().saturating_sub(1);
+                    let char_lengths = bpe_output
+                        .iter()
+                        .enumerate()
+                        .map(|(index, sub_token)| if index < last_index { sub_token.chars().count().saturating_sub(2) } else { sub_token.chars().count() })
+                        .collect();
+                    words.push(bpe_sub_tokens_with_offsets(bpe_output, char_lengths, &word.reference_offsets));
                 };
             } else {
-                tokenized_text.push(text);
+                words.push(vec!(text));
             }
         }
-        tokenized_text
+        words
     }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
@@ -88,12 +143,15 @@ impl Tokenizer<OpenAiGptVocab> for CtrlTokenizer {
     }
 }
 
+impl MultiThreadedTokenizer<OpenAiGptVocab> for CtrlTokenizer {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::OpenAiGptVocab;
     use std::collections::HashMap;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput};
+    use itertools::Itertools;
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
 
     fn generate_test_vocab() -> OpenAiGptVocab {
@@ -115,7 +173,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        OpenAiGptVocab { values, indices, unknown_value: "<unk>", special_values, special_indices }
+        OpenAiGptVocab { values, indices, special_token_map: OpenAiGptVocab::default_special_token_map(), special_values, special_indices }
     }
 
     fn generate_test_merges() -> BpePairVocab {
@@ -130,14 +188,14 @@ mod tests {
         ].iter().cloned().collect();
 
 
-        BpePairVocab { values }
+        BpePairVocab::from_values(values)
     }
 
     #[test]
     fn test_ctrl_tokenizer() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer = CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
             (
@@ -154,11 +212,11 @@ mod tests {
             ),
             (
                 " ",
-                vec!("<unk>")
+                vec!()
             ),
             (
                 " \n ",
-                vec!("<unk>")
+                vec!()
             ),
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -169,14 +227,15 @@ mod tests {
             assert_eq!(ctrl_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(ctrl_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()), expected_results);
     }
 
     #[test]
     fn test_ctrl_tokenizer_no_lower_casing() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer = CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
             (
@@ -193,11 +252,11 @@ mod tests {
             ),
             (
                 " ",
-                vec!("<unk>")
+                vec!()
             ),
             (
                 " \n ",
-                vec!("<unk>")
+                vec!()
             ),
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -208,28 +267,29 @@ mod tests {
             assert_eq!(ctrl_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(ctrl_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()), expected_results);
     }
 
     #[test]
     fn test_encode() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer = CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let truncation_strategy = TruncationStrategy::LongestFirst;
         let test_tuples = [
             (
                 "the earth",
-                TokenizedInput { token_ids: vec!(4, 6, 2, 5, 6, 1), segment_ids: vec!(0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(4, 6, 2, 5, 6, 1), segment_ids: vec!(0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 3 }), Some(Offset { begin: 4, end: 5 }), Some(Offset { begin: 5, end: 6 }), Some(Offset { begin: 6, end: 7 }), Some(Offset { begin: 7, end: 8 }), Some(Offset { begin: 8, end: 9 })), attention_mask: vec!(1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(1), Some(1), Some(1), Some(1)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(0)) }
             ),
             (
                 "Hello, world!",
-                TokenizedInput { token_ids: vec!(6, 6, 6, 8, 6, 6, 8, 5, 6, 6, 6), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(6, 6, 6, 8, 6, 6, 8, 5, 6, 6, 6), segment_ids: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0), special_tokens_mask: vec!(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 4 }), Some(Offset { begin: 4, end: 5 }), Some(Offset { begin: 5, end: 6 }), Some(Offset { begin: 7, end: 8 }), Some(Offset { begin: 8, end: 9 }), Some(Offset { begin: 9, end: 10 }), Some(Offset { begin: 10, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 12, end: 13 })), attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1), word_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1), Some(1)), sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0)) }
             ),
             (
                 "",
-                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(), attention_mask: vec!(), word_ids: vec!(), sequence_ids: vec!() }
             )
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -237,17 +297,18 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(ctrl_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+            assert_eq!(ctrl_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(ctrl_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_list(&ctrl_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_list(&ctrl_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
     fn test_decode() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer = CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let skip_special_tokens = false;
         let clean_up_tokenization_spaces = false;
@@ -271,8 +332,8 @@ mod tests {
     #[test]
     fn test_decode_skip_special_tokens() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer = CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let skip_special_tokens = true;
         let clean_up_tokenization_spaces = true;
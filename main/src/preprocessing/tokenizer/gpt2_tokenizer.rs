@@ -13,41 +13,94 @@
 
 use crate::Gpt2Vocab;
 use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::preprocessing::tokenizer::base_tokenizer::Tokenizer;
-use std::collections::HashMap;
-use crate::preprocessing::tokenizer::tokenization_utils::{bpe, split_on_special_tokens};
-use std::rc::Rc;
-use std::cell::RefCell;
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_files::{VOCAB_JSON_FILE, MERGES_FILE, SPECIAL_TOKENS_MAP_FILE, create_directory, file_path, write_tokenizer_config, read_tokenizer_config, read_special_token_map, write_added_tokens, read_added_tokens};
+use crate::preprocessing::tokenizer::tokenizer_json::{read_tokenizer_json, gpt2_tokenizer_from_json};
+use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, MultiThreadedTokenizer, Token};
+use crate::preprocessing::tokenizer::tokenization_utils::{bpe, lowercase_with_offsets, split_gpt2_words, bpe_sub_tokens_with_offsets};
+use crate::preprocessing::tokenizer::special_token_matcher::{SpecialTokenMatcher, register_added_tokens, restore_added_tokens};
+use std::sync::Arc;
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
-use regex::Regex;
 use crate::preprocessing::tokenizer::constants::{BYTES_TO_UNICODE, UNICODE_TO_BYTES};
-use std::iter::Iterator;
-use itertools::Itertools;
+use std::iter::{Iterator, repeat_n};
 
 pub struct Gpt2Tokenizer {
-    vocab: Rc<Gpt2Vocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, Vec<String>>>,
-    pattern_lookahead: Regex,
-    pattern_tokenization: Regex,
+    vocab: Arc<Gpt2Vocab>,
+    special_token_matcher: SpecialTokenMatcher,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
     lower_case: bool,
 }
 
 impl Gpt2Tokenizer {
-    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> Gpt2Tokenizer {
-        let vocab = Rc::new(Gpt2Vocab::from_file(vocab_path));
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path));
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization = Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+").unwrap();
-        Gpt2Tokenizer { vocab, bpe_ranks, cache, pattern_lookahead, pattern_tokenization, lower_case }
+    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> Result<Gpt2Tokenizer, TokenizerError> {
+        let vocab = Arc::new(Gpt2Vocab::from_file(vocab_path)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::default();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        Ok(Gpt2Tokenizer { vocab, special_token_matcher, bpe_ranks, cache, lower_case })
     }
 
-    pub fn from_existing_vocab_and_merges(vocab: Rc<Gpt2Vocab>, merges: Rc<BpePairVocab>, lower_case: bool) -> Gpt2Tokenizer {
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization = Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+").unwrap();
-        Gpt2Tokenizer { vocab, bpe_ranks: merges, cache, pattern_lookahead, pattern_tokenization, lower_case }
+    /// Loads the tokenizer from a HuggingFace `tokenizer.json` file, taking the lower casing setting from its normalizer.
+    pub fn from_tokenizer_json(path: &str) -> Result<Gpt2Tokenizer, TokenizerError> {
+        gpt2_tokenizer_from_json(&read_tokenizer_json(path)?)
+    }
+
+    pub fn from_existing_vocab_and_merges(vocab: Arc<Gpt2Vocab>, merges: Arc<BpePairVocab>, lower_case: bool) -> Gpt2Tokenizer {
+        let cache = BpeCache::default();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        Gpt2Tokenizer { vocab, special_token_matcher, bpe_ranks: merges, cache, lower_case }
+    }
+
+    /// Writes the vocabulary, the merges, the special token map, the added tokens and a configuration holding the lower casing setting to `directory`, created if needed.
+    pub fn save(&self, directory: &str) -> Result<(), TokenizerError> {
+        create_directory(directory)?;
+        self.vocab.to_file(&file_path(directory, VOCAB_JSON_FILE))?;
+        self.bpe_ranks.to_file(&file_path(directory, MERGES_FILE))?;
+        self.vocab.special_token_map().to_file(&file_path(directory, SPECIAL_TOKENS_MAP_FILE))?;
+        write_added_tokens(directory, self.special_token_matcher.added_tokens())?;
+        write_tokenizer_config(directory, self.lower_case, None)
+    }
+
+    /// Loads a tokenizer written by `save`.
+    pub fn from_directory(directory: &str) -> Result<Gpt2Tokenizer, TokenizerError> {
+        let (lower_case, _) = read_tokenizer_config(directory)?;
+        let special_token_map = read_special_token_map::<Gpt2Vocab>(directory)?;
+        let vocab = Gpt2Vocab::from_file_with_special_token_map(&file_path(directory, VOCAB_JSON_FILE), special_token_map)?;
+        let merges = BpePairVocab::from_file(&file_path(directory, MERGES_FILE))?;
+        let mut tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), lower_case);
+        restore_added_tokens(&mut tokenizer.vocab, &mut tokenizer.special_token_matcher, &read_added_tokens(directory)?);
+        Ok(tokenizer)
+    }
+
+    /// Replaces the cache of byte pair encoded words by a cache bounded to `capacity` words. A capacity of 0 disables caching.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Gpt2Tokenizer {
+        self.cache = BpeCache::new(capacity);
+        self
+    }
+
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
+
+    pub fn merges(&self) -> &BpePairVocab {
+        &self.bpe_ranks
+    }
+
+    pub fn lower_case(&self) -> bool {
+        self.lower_case
+    }
+
+    /// Adds tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer. Returns the id of each token.
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, false)
+    }
+
+    /// Adds special tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer, are flagged in the
+    /// special tokens mask and are skipped when decoding with `skip_special_tokens`. Returns the id of each token.
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, true)
     }
 }
 
@@ -56,78 +109,67 @@ impl Tokenizer<Gpt2Vocab> for Gpt2Tokenizer {
         self.vocab.as_ref()
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let mut tokenized_text: Vec<String> = Vec::with_capacity(text.len());
-        let temp_text = split_on_special_tokens(text, self.vocab.as_ref());
-        let temp_text = temp_text
-            .into_iter()
-            .map(|v| if self.lower_case { v.to_lowercase() } else { v.to_owned() })
-            .collect_vec();
-
-//        Rust regex's library does not include lookahead, decomposing the process in 2 steps
-        for text in temp_text {
-            if !self.vocab.special_values.contains_key(text.as_str()) {
-                let mut sub_words: Vec<&str> = vec!();
-                let mut splits: Vec<&str> = vec!();
-
-                let mut i: usize = 0;
-                let mut end: usize;
-                for hit in self.pattern_lookahead.find_iter(text.as_str()) {
-                    end = hit.end() - 1 - hit.as_str().chars().last().unwrap().len_utf8();
-                    splits.push(&text[i..end]);
-                    i = end;
-                }
-                splits.push(&text[i..]);
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(initial_token).into_iter().flatten().collect()
+    }
 
-                for sub_word in splits {
-                    for hit in self.pattern_tokenization.find_iter(sub_word) {
-                        sub_words.push(hit.as_str());
-                    }
-                }
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        let mut words: Vec<Vec<Token>> = vec!();
 
-                for word in sub_words {
-                    let word: String = word.as_bytes().iter().map(|v| BYTES_TO_UNICODE.get(&v).unwrap()).collect();
-                    let cached: bool = match self.cache.borrow().get(&word) {
-                        Some(value) => {
-                            tokenized_text.extend(value.clone());
-                            true
-                        }
-                        None => false
-                    };
-                    if !cached {
-                        let bpe_output = bpe(word.as_str(), self.bpe_ranks.as_ref());
-                        self.cache.borrow_mut().insert(word.to_owned(), bpe_output.clone());
-                        tokenized_text.extend(bpe_output);
-                    }
+        for text in self.special_token_matcher.split_with_offsets(initial_token) {
+            if !self.special_token_matcher.contains(&text.text) {
+                let text = if self.lower_case { lowercase_with_offsets(&text) } else { text };
+                for word in text.slices(&split_gpt2_words(&text.text)) {
+//                    Each byte of the word is mapped to a unicode character, referencing the character it belongs to
+                    let reference_offsets: Vec<usize> = word.text
+                        .chars()
+                        .zip(word.reference_offsets.iter())
+                        .flat_map(|(character, &position)| repeat_n(position, character.len_utf8()))
+                        .collect();
+                    let word: String = word.text.as_bytes().iter().map(|v| BYTES_TO_UNICODE.get(&v).unwrap()).collect();
+                    let bpe_output = self.cache.get_or_insert_with(&word, || bpe(word.as_str(), self.bpe_ranks.as_ref()));
+                    let char_lengths = bpe_output.iter().map(|sub_token| sub_token.chars().count()).collect();
+                    words.push(bpe_sub_tokens_with_offsets(bpe_output, char_lengths, &reference_offsets));
                 };
             } else {
-                tokenized_text.push(text);
+                words.push(vec!(text));
             }
         }
-        tokenized_text
+        words
     }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
-        let tokens = tokens
-            .iter()
-            .join("")
-            .replace(" ##", "")
-            .trim()
-            .chars()
-            .map(|character| UNICODE_TO_BYTES.get(&character).unwrap().clone())
-            .collect_vec();
-
-        String::from_utf8(tokens).unwrap()
+        let mut bytes: Vec<u8> = vec!();
+        for token in tokens {
+//            Special and added tokens are not byte-level encoded
+            if self.special_token_matcher.contains(&token) {
+                bytes.extend(token.as_bytes());
+            } else {
+                for character in token.chars() {
+                    match UNICODE_TO_BYTES.get(&character) {
+                        Some(byte) => bytes.push(*byte),
+                        None => bytes.extend(character.to_string().as_bytes())
+                    }
+                }
+            }
+        }
+
+//        Tokens cut in the middle of a multi-byte character, e.g. by truncation, decode to the replacement character
+        String::from_utf8_lossy(&bytes).into_owned()
     }
 }
 
+impl MultiThreadedTokenizer<Gpt2Vocab> for Gpt2Tokenizer {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::Gpt2Vocab;
     use std::collections::HashMap;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput};
-    use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use itertools::Itertools;
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
+    use crate::preprocessing::vocab::base_vocab::{swap_key_values, SpecialTokenMap};
+    use crate::preprocessing::tokenizer::bpe_cache::BpeCacheStats;
 
     fn generate_test_vocab() -> Gpt2Vocab {
         let values: HashMap<String, i64> = [
@@ -150,7 +192,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        Gpt2Vocab { values, indices, unknown_value: "<|endoftext|>", special_values, special_indices }
+        Gpt2Vocab { values, indices, special_token_map: Gpt2Vocab::default_special_token_map(), special_values, special_indices }
     }
 
     fn generate_test_merges() -> BpePairVocab {
@@ -168,14 +210,14 @@ mod tests {
         ].iter().cloned().collect();
 
 
-        BpePairVocab { values }
+        BpePairVocab::from_values(values)
     }
 
     #[test]
     fn test_gpt2_tokenizer() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
             (
@@ -188,11 +230,11 @@ mod tests {
             ),
             (
                 " ",
-                vec!("<|endoftext|>")
+                vec!()
             ),
             (
                 " \n ",
-                vec!("<|endoftext|>")
+                vec!()
             ),
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -203,14 +245,101 @@ mod tests {
             assert_eq!(gpt2_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(gpt2_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()), expected_results);
+    }
+
+    #[test]
+    fn test_gpt2_tokenizer_bounded_cache() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab.clone(), merges.clone(), true)
+            .with_cache_capacity(1);
+        let uncached_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true)
+            .with_cache_capacity(0);
+
+//        When
+        let tokens = gpt2_tokenizer.tokenize("the Earth the");
+        let uncached_tokens = uncached_tokenizer.tokenize("the Earth the");
+
+//        Then
+        assert_eq!(tokens, vec!("the", "Ġear", "th", "Ġthe"));
+        assert_eq!(uncached_tokens, tokens);
+        assert_eq!(gpt2_tokenizer.cache().stats(), BpeCacheStats { hits: 0, misses: 3, size: 1, capacity: 1 });
+        assert_eq!(uncached_tokenizer.cache().stats(), BpeCacheStats { hits: 0, misses: 3, size: 0, capacity: 0 });
+        gpt2_tokenizer.cache().clear();
+        assert!(gpt2_tokenizer.cache().is_empty());
+    }
+
+    #[test]
+    fn test_save_and_reload_gpt2_tokenizer() -> Result<(), TokenizerError> {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path().to_str().unwrap();
+
+//        When
+        gpt2_tokenizer.save(directory)?;
+        let reloaded_tokenizer = Gpt2Tokenizer::from_directory(directory)?;
+
+//        Then
+        assert_eq!(reloaded_tokenizer.vocab.values, gpt2_tokenizer.vocab.values);
+        assert_eq!(reloaded_tokenizer.tokenize("the Earth the"), vec!("the", "Ġear", "th", "Ġthe"));
+        assert_eq!(reloaded_tokenizer.tokenize("the Earth the"), gpt2_tokenizer.tokenize("the Earth the"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_reload_gpt2_tokenizer_special_token_map() -> Result<(), TokenizerError> {
+//        Given
+        let mut values = generate_test_vocab().values;
+        values.insert("<pad>".to_owned(), 10);
+        let special_token_map = SpecialTokenMap { pad_token: Some("<pad>".to_owned()), ..Gpt2Vocab::default_special_token_map() };
+        let vocab = Arc::new(Gpt2Vocab::from_values_with_special_token_map(values, special_token_map.clone())?);
+        let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, Arc::new(generate_test_merges()), true);
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path().to_str().unwrap();
+
+//        When
+        gpt2_tokenizer.save(directory)?;
+        let reloaded_tokenizer = Gpt2Tokenizer::from_directory(directory)?;
+
+//        Then
+        assert_eq!(reloaded_tokenizer.vocab.special_token_map(), &special_token_map);
+        assert_eq!(reloaded_tokenizer.tokenize("the<pad>"), vec!("the", "<pad>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_reload_gpt2_tokenizer_added_tokens() -> Result<(), TokenizerError> {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let mut gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+        gpt2_tokenizer.add_tokens(&["EarTh"]);
+        gpt2_tokenizer.add_special_tokens(&["<sep>"]);
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path().to_str().unwrap();
+
+//        When
+        gpt2_tokenizer.save(directory)?;
+        let reloaded_tokenizer = Gpt2Tokenizer::from_directory(directory)?;
+
+//        Then
+        assert_eq!(reloaded_tokenizer.vocab.values, gpt2_tokenizer.vocab.values);
+        assert_eq!(reloaded_tokenizer.tokenize("the EarTh<sep>"), vec!("the", "EarTh", "<sep>"));
+        assert_eq!(reloaded_tokenizer.tokenize("the EarTh<sep>"), gpt2_tokenizer.tokenize("the EarTh<sep>"));
+        Ok(())
     }
 
     #[test]
     fn test_gpt2_tokenizer_no_lower_casing() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
             (
@@ -223,11 +352,11 @@ mod tests {
             ),
             (
                 " ",
-                vec!("<|endoftext|>")
+                vec!()
             ),
             (
                 " \n ",
-                vec!("<|endoftext|>")
+                vec!()
             ),
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -238,28 +367,29 @@ mod tests {
             assert_eq!(gpt2_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(gpt2_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()), expected_results);
     }
 
     #[test]
     fn test_encode() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let truncation_strategy = TruncationStrategy::LongestFirst;
         let test_tuples = [
             (
                 "the earth",
-                TokenizedInput { token_ids: vec!(4, 8, 9), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(4, 8, 9), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 3 }), Some(Offset { begin: 3, end: 7 }), Some(Offset { begin: 7, end: 9 })), attention_mask: vec!(1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(1)), sequence_ids: vec!(Some(0), Some(0), Some(0)) }
             ),
             (
                 " ",
-                TokenizedInput { token_ids: vec!(6), segment_ids: vec!(0), special_tokens_mask: vec!(0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(), attention_mask: vec!(), word_ids: vec!(), sequence_ids: vec!() }
             ),
             (
                 "",
-                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(), attention_mask: vec!(), word_ids: vec!(), sequence_ids: vec!() }
             )
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -267,17 +397,18 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(gpt2_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+            assert_eq!(gpt2_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(gpt2_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_list(&gpt2_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_list(&gpt2_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
     fn test_decode() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let skip_special_tokens = false;
         let clean_up_tokenization_spaces = false;
@@ -297,4 +428,79 @@ mod tests {
         }
         assert_eq!(Tokenizer::decode_list(&gpt2_tokenizer, source_ids.clone(), skip_special_tokens, clean_up_tokenization_spaces), expected_results);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_convert_partial_multi_byte_character_to_string() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+
+//        When & Then
+        assert_eq!(gpt2_tokenizer.convert_tokens_to_string(vec!("the".to_owned(), "Ġ".to_owned(), "Ã©".to_owned())), "the é");
+        assert_eq!(gpt2_tokenizer.convert_tokens_to_string(vec!("the".to_owned(), "Ġ".to_owned(), "Ã".to_owned())), "the \u{FFFD}");
+        assert_eq!(gpt2_tokenizer.convert_tokens_to_string(vec!("©".to_owned(), "th".to_owned())), "\u{FFFD}th");
+    }
+
+    #[test]
+    fn test_add_special_tokens() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let mut gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+
+//        When
+        let special_token_ids = gpt2_tokenizer.add_special_tokens(&["<|sép|>"]);
+        let tokenized_input = gpt2_tokenizer.encode("the<|sép|>the", None, 128, &TruncationStrategy::LongestFirst, 0,
+                                                    &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(special_token_ids, vec!(10));
+        assert_eq!(tokenized_input.token_ids, vec!(4, 10, 4));
+        assert_eq!(tokenized_input.special_tokens_mask, vec!(0, 1, 0));
+        assert_eq!(gpt2_tokenizer.decode(tokenized_input.token_ids.clone(), false, false), "the<|sép|>the");
+        assert_eq!(gpt2_tokenizer.decode(tokenized_input.token_ids, true, false), "thethe");
+    }
+
+    #[test]
+    fn test_add_mixed_case_tokens_with_lower_casing() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let mut gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+
+//        When
+        let token_ids = gpt2_tokenizer.add_tokens(&["[Domain]"]);
+        let special_token_ids = gpt2_tokenizer.add_special_tokens(&["<|Sep|>"]);
+        let tokenized_input = gpt2_tokenizer.encode("The[Domain]the<|Sep|>", None, 128, &TruncationStrategy::LongestFirst, 0,
+                                                    &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(token_ids, vec!(10));
+        assert_eq!(special_token_ids, vec!(11));
+        assert_eq!(gpt2_tokenizer.tokenize("The[Domain]the<|Sep|>"), vec!("the", "[Domain]", "the", "<|Sep|>"));
+        assert_eq!(tokenized_input.token_ids, vec!(4, 10, 4, 11));
+        assert_eq!(tokenized_input.special_tokens_mask, vec!(0, 0, 0, 1));
+    }
+
+    #[test]
+    fn test_encode_with_pad_token() -> Result<(), TokenizerError> {
+//        Given
+        let mut values = generate_test_vocab().values;
+        values.insert("<pad>".to_owned(), 10);
+        let special_token_map = SpecialTokenMap { pad_token: Some("<pad>".to_owned()), ..Gpt2Vocab::default_special_token_map() };
+        let vocab = Arc::new(Gpt2Vocab::from_values_with_special_token_map(values, special_token_map)?);
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+
+//        When
+        let tokenized_input = gpt2_tokenizer.encode("the earth", None, 5, &TruncationStrategy::LongestFirst, 0,
+                                                    &PaddingStrategy::MaxLength, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(tokenized_input.token_ids, vec!(4, 8, 9, 10, 10));
+        assert_eq!(tokenized_input.attention_mask, vec!(1, 1, 1, 0, 0));
+        assert_eq!(gpt2_tokenizer.decode(tokenized_input.token_ids, true, false), "the earth");
+        Ok(())
+    }
+}
@@ -15,5 +15,14 @@ pub mod ctrl_tokenizer;
 pub mod openai_gpt_tokenizer;
 pub mod gpt2_tokenizer;
 pub mod roberta_tokenizer;
+pub mod albert_tokenizer;
+pub mod xlnet_tokenizer;
+pub mod t5_tokenizer;
+pub mod xlm_roberta_tokenizer;
+pub mod bpe_cache;
+pub mod special_token_matcher;
+pub mod template_processor;
+pub mod tokenizer_json;
+pub mod tokenizer_files;
 pub mod tokenization_utils;
-mod constants;
\ No newline at end of file
+pub(crate) mod constants;
\ No newline at end of file
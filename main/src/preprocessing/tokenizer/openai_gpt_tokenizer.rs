@@ -13,34 +13,93 @@
 
 use crate::OpenAiGptVocab;
 use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, BaseTokenizer};
-use std::collections::HashMap;
-use crate::preprocessing::tokenizer::tokenization_utils::{split_on_special_tokens, openai_gpt_bpe};
-use std::rc::Rc;
-use std::cell::RefCell;
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_files::{VOCAB_JSON_FILE, MERGES_FILE, SPECIAL_TOKENS_MAP_FILE, create_directory, file_path, write_tokenizer_config, read_tokenizer_config, read_special_token_map, write_added_tokens, read_added_tokens};
+use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, MultiThreadedTokenizer, BaseTokenizer, Token};
+use crate::preprocessing::tokenizer::tokenization_utils::{openai_gpt_bpe, bpe_sub_tokens_with_offsets};
+use crate::preprocessing::tokenizer::special_token_matcher::{SpecialTokenMatcher, register_added_tokens, restore_added_tokens};
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
 use std::sync::Arc;
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 
 pub struct OpenAiGptTokenizer {
     vocab: Arc<OpenAiGptVocab>,
+    special_token_matcher: SpecialTokenMatcher,
     base_tokenizer: BaseTokenizer<OpenAiGptVocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, Vec<String>>>,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
 }
 
 impl OpenAiGptTokenizer {
-    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> OpenAiGptTokenizer {
-        let vocab = Arc::new(OpenAiGptVocab::from_file(vocab_path));
+    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> Result<OpenAiGptTokenizer, TokenizerError> {
+        let vocab = Arc::new(OpenAiGptVocab::from_file(vocab_path)?);
         let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case);
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path));
-        let cache = RefCell::new(HashMap::new());
-        OpenAiGptTokenizer { vocab, base_tokenizer, bpe_ranks, cache }
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::default();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        Ok(OpenAiGptTokenizer { vocab, special_token_matcher, base_tokenizer, bpe_ranks, cache })
     }
 
-    pub fn from_existing_vocab_and_merges(vocab: Arc<OpenAiGptVocab>, merges: Rc<BpePairVocab>, lower_case: bool) -> OpenAiGptTokenizer {
+    pub fn from_existing_vocab_and_merges(vocab: Arc<OpenAiGptVocab>, merges: Arc<BpePairVocab>, lower_case: bool) -> OpenAiGptTokenizer {
         let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case);
-        let cache = RefCell::new(HashMap::new());
-        OpenAiGptTokenizer { vocab, base_tokenizer, bpe_ranks: merges, cache }
+        let cache = BpeCache::default();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        OpenAiGptTokenizer { vocab, special_token_matcher, base_tokenizer, bpe_ranks: merges, cache }
+    }
+
+    /// Writes the vocabulary, the merges, the special token map, the added tokens and a configuration holding the lower casing setting to `directory`, created if needed.
+    pub fn save(&self, directory: &str) -> Result<(), TokenizerError> {
+        create_directory(directory)?;
+        self.vocab.to_file(&file_path(directory, VOCAB_JSON_FILE))?;
+        self.bpe_ranks.to_file(&file_path(directory, MERGES_FILE))?;
+        self.vocab.special_token_map().to_file(&file_path(directory, SPECIAL_TOKENS_MAP_FILE))?;
+        write_added_tokens(directory, self.special_token_matcher.added_tokens())?;
+        write_tokenizer_config(directory, self.base_tokenizer.lower_case(), None)
+    }
+
+    /// Loads a tokenizer written by `save`.
+    pub fn from_directory(directory: &str) -> Result<OpenAiGptTokenizer, TokenizerError> {
+        let (lower_case, _) = read_tokenizer_config(directory)?;
+        let special_token_map = read_special_token_map::<OpenAiGptVocab>(directory)?;
+        let vocab = OpenAiGptVocab::from_file_with_special_token_map(&file_path(directory, VOCAB_JSON_FILE), special_token_map)?;
+        let merges = BpePairVocab::from_file(&file_path(directory, MERGES_FILE))?;
+        let mut tokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), lower_case);
+        restore_added_tokens(&mut tokenizer.vocab, &mut tokenizer.special_token_matcher, &read_added_tokens(directory)?);
+        tokenizer.base_tokenizer = BaseTokenizer::from_existing_vocab(tokenizer.vocab.clone(), lower_case);
+        Ok(tokenizer)
+    }
+
+    /// Replaces the cache of byte pair encoded words by a cache bounded to `capacity` words. A capacity of 0 disables caching.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> OpenAiGptTokenizer {
+        self.cache = BpeCache::new(capacity);
+        self
+    }
+
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
+
+    pub fn merges(&self) -> &BpePairVocab {
+        &self.bpe_ranks
+    }
+
+    pub fn lower_case(&self) -> bool {
+        self.base_tokenizer.lower_case()
+    }
+
+    /// Adds tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer. Returns the id of each token.
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        let token_ids = register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, false);
+        self.base_tokenizer = BaseTokenizer::from_existing_vocab(self.vocab.clone(), self.base_tokenizer.lower_case());
+        token_ids
+    }
+
+    /// Adds special tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer, are flagged in the
+    /// special tokens mask and are skipped when decoding with `skip_special_tokens`. Returns the id of each token.
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        let token_ids = register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, true);
+        self.base_tokenizer = BaseTokenizer::from_existing_vocab(self.vocab.clone(), self.base_tokenizer.lower_case());
+        token_ids
     }
 }
 
@@ -49,33 +108,34 @@ impl Tokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {
         self.vocab.as_ref()
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let mut tokenized_text: Vec<String> = Vec::with_capacity(text.len());
-        let temp_text = split_on_special_tokens(text, self.vocab.as_ref());
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(initial_token).into_iter().flatten().collect()
+    }
+
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        let mut words: Vec<Vec<Token>> = vec!();
+        let temp_text = self.special_token_matcher.split_with_offsets(initial_token);
 
         for text in temp_text {
-            if !self.vocab.special_values.contains_key(text) {
-                let sub_words: Vec<String> = self.base_tokenizer.tokenize(text);
+            if !self.special_token_matcher.contains(&text.text) {
+                let sub_words: Vec<Token> = self.base_tokenizer.tokenize_to_tokens(&text);
 
                 for word in sub_words {
-                    let cached: bool = match self.cache.borrow().get(&word) {
-                        Some(value) => {
-                            tokenized_text.extend(value.clone());
-                            true
-                        }
-                        None => false
-                    };
-                    if !cached {
-                        let bpe_output = openai_gpt_bpe(&word, &self.bpe_ranks);
-                        self.cache.borrow_mut().insert(word.to_owned(), bpe_output.clone());
-                        tokenized_text.extend(bpe_output);
-                    }
+                    let bpe_output = self.cache.get_or_insert_with(&word.text, || openai_gpt_bpe(&word.text, &self.bpe_ranks));
+//                    The last sub-token carries the `</w>` end of word marker
+                    let last_index = bpe_output.len().saturating_sub(1);
+                    let char_lengths = bpe_output
+                        .iter()
+                        .enumerate()
+                        .map(|(index, sub_token)| if index < last_index { sub_token.chars().count() } else { sub_token.chars().count().saturating_sub(4) })
+                        .collect();
+                    words.push(bpe_sub_tokens_with_offsets(bpe_output, char_lengths, &word.reference_offsets));
                 };
             } else {
-                tokenized_text.push(text.to_owned());
+                words.push(vec!(text));
             }
         }
-        tokenized_text
+        words
     }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
@@ -83,12 +143,14 @@ impl Tokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {
     }
 }
 
+impl MultiThreadedTokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::OpenAiGptVocab;
     use std::collections::HashMap;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput};
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
     use itertools::Itertools;
 
@@ -114,7 +176,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        OpenAiGptVocab { values, indices, unknown_value: "<unk>", special_values, special_indices }
+        OpenAiGptVocab { values, indices, special_token_map: OpenAiGptVocab::default_special_token_map(), special_values, special_indices }
     }
 
     fn generate_test_merges() -> BpePairVocab {
@@ -131,14 +193,14 @@ mod tests {
         ].iter().cloned().collect();
 
 
-        BpePairVocab { values }
+        BpePairVocab::from_values(values)
     }
 
     #[test]
     fn test_openai_gpt_tokenizer() {
 //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
             (
@@ -151,11 +213,11 @@ mod tests {
             ),
             (
                 " ",
-                vec!("<unk>")
+                vec!()
             ),
             (
                 " \n ",
-                vec!("<unk>")
+                vec!()
             ),
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -166,14 +228,15 @@ mod tests {
             assert_eq!(openai_gpt_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(openai_gpt_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()), expected_results);
     }
 
     #[test]
     fn test_openai_gpt_tokenizer_no_lower_casing() {
 //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
             (
@@ -186,11 +249,11 @@ mod tests {
             ),
             (
                 " ",
-                vec!("<unk>")
+                vec!()
             ),
             (
                 " \n ",
-                vec!("<unk>")
+                vec!()
             ),
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -201,7 +264,8 @@ mod tests {
             assert_eq!(openai_gpt_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(openai_gpt_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()), expected_results);
     }
 
 
@@ -209,21 +273,21 @@ mod tests {
     fn test_encode() {
 //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let truncation_strategy = TruncationStrategy::LongestFirst;
         let test_tuples = [
             (
                 "the earth",
-                TokenizedInput { token_ids: vec!(8, 10, 9), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(8, 10, 9), segment_ids: vec!(0, 0, 0), special_tokens_mask: vec!(0, 0, 0), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(Some(Offset { begin: 0, end: 3 }), Some(Offset { begin: 4, end: 6 }), Some(Offset { begin: 6, end: 9 })), attention_mask: vec!(1, 1, 1), word_ids: vec!(Some(0), Some(1), Some(1)), sequence_ids: vec!(Some(0), Some(0), Some(0)) }
             ),
             (
                 " ",
-                TokenizedInput { token_ids: vec!(6), segment_ids: vec!(0), special_tokens_mask: vec!(0), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(), attention_mask: vec!(), word_ids: vec!(), sequence_ids: vec!() }
             ),
             (
                 "",
-                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(), segment_ids: vec!(), special_tokens_mask: vec!(), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(), attention_mask: vec!(), word_ids: vec!(), sequence_ids: vec!() }
             )
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -231,17 +295,18 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(openai_gpt_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+            assert_eq!(openai_gpt_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(openai_gpt_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_list(&openai_gpt_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_list(&openai_gpt_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
     fn test_decode() {
 //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let skip_special_tokens = false;
         let clean_up_tokenization_spaces = false;
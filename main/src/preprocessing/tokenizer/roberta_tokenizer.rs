@@ -12,42 +12,99 @@
 // limitations under the License.
 
 use crate::RobertaVocab;
-use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::preprocessing::tokenizer::base_tokenizer::Tokenizer;
-use std::collections::HashMap;
-use crate::preprocessing::tokenizer::tokenization_utils::{bpe, split_on_special_tokens};
-use std::rc::Rc;
-use std::cell::RefCell;
+use crate::preprocessing::vocab::base_vocab::{Vocab, role_value};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_files::{VOCAB_JSON_FILE, MERGES_FILE, SPECIAL_TOKENS_MAP_FILE, create_directory, file_path, write_tokenizer_config, read_tokenizer_config, read_special_token_map, write_added_tokens, read_added_tokens};
+use crate::preprocessing::tokenizer::tokenizer_json::{read_tokenizer_json, roberta_tokenizer_from_json};
+use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, MultiThreadedTokenizer, Token};
+use crate::preprocessing::tokenizer::tokenization_utils::{bpe, lowercase_with_offsets, split_gpt2_words, bpe_sub_tokens_with_offsets};
+use crate::preprocessing::tokenizer::special_token_matcher::{SpecialTokenMatcher, register_added_tokens, restore_added_tokens};
+use crate::preprocessing::tokenizer::template_processor::TemplateProcessor;
+use std::sync::Arc;
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
-use regex::Regex;
 use crate::preprocessing::tokenizer::constants::{BYTES_TO_UNICODE, UNICODE_TO_BYTES};
-use std::iter::Iterator;
-use itertools::Itertools;
+use std::iter::{Iterator, repeat_n};
 
 pub struct RobertaTokenizer {
-    vocab: Rc<RobertaVocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, Vec<String>>>,
-    pattern_lookahead: Regex,
-    pattern_tokenization: Regex,
+    vocab: Arc<RobertaVocab>,
+    special_token_matcher: SpecialTokenMatcher,
+    post_processor: TemplateProcessor,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
     lower_case: bool,
 }
 
 impl RobertaTokenizer {
-    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> RobertaTokenizer {
-        let vocab = Rc::new(RobertaVocab::from_file(vocab_path));
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path));
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization = Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+").unwrap();
-        RobertaTokenizer { vocab, bpe_ranks, cache, pattern_lookahead, pattern_tokenization, lower_case }
+    pub fn from_file(vocab_path: &str, merges_path: &str, lower_case: bool) -> Result<RobertaTokenizer, TokenizerError> {
+        let vocab = Arc::new(RobertaVocab::from_file(vocab_path)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::default();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        let post_processor = TemplateProcessor::roberta(vocab.token_to_id(role_value(vocab.cls_value(), "cls")), vocab.token_to_id(role_value(vocab.sep_value(), "sep")), 1);
+        Ok(RobertaTokenizer { vocab, special_token_matcher, post_processor, bpe_ranks, cache, lower_case })
     }
 
-    pub fn from_existing_vocab_and_merges(vocab: Rc<RobertaVocab>, merges: Rc<BpePairVocab>, lower_case: bool) -> RobertaTokenizer {
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization = Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+").unwrap();
-        RobertaTokenizer { vocab, bpe_ranks: merges, cache, pattern_lookahead, pattern_tokenization, lower_case }
+    /// Loads the tokenizer from a HuggingFace `tokenizer.json` file, taking the lower casing setting from its normalizer.
+    pub fn from_tokenizer_json(path: &str) -> Result<RobertaTokenizer, TokenizerError> {
+        roberta_tokenizer_from_json(&read_tokenizer_json(path)?)
+    }
+
+    pub fn from_existing_vocab_and_merges(vocab: Arc<RobertaVocab>, merges: Arc<BpePairVocab>, lower_case: bool) -> RobertaTokenizer {
+        let cache = BpeCache::default();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        let post_processor = TemplateProcessor::roberta(vocab.token_to_id(role_value(vocab.cls_value(), "cls")), vocab.token_to_id(role_value(vocab.sep_value(), "sep")), 1);
+        RobertaTokenizer { vocab, special_token_matcher, post_processor, bpe_ranks: merges, cache, lower_case }
+    }
+
+    /// Writes the vocabulary, the merges, the special token map, the added tokens and a configuration holding the lower casing setting to `directory`, created if needed.
+    pub fn save(&self, directory: &str) -> Result<(), TokenizerError> {
+        create_directory(directory)?;
+        self.vocab.to_file(&file_path(directory, VOCAB_JSON_FILE))?;
+        self.bpe_ranks.to_file(&file_path(directory, MERGES_FILE))?;
+        self.vocab.special_token_map().to_file(&file_path(directory, SPECIAL_TOKENS_MAP_FILE))?;
+        write_added_tokens(directory, self.special_token_matcher.added_tokens())?;
+        write_tokenizer_config(directory, self.lower_case, None)
+    }
+
+    /// Loads a tokenizer written by `save`.
+    pub fn from_directory(directory: &str) -> Result<RobertaTokenizer, TokenizerError> {
+        let (lower_case, _) = read_tokenizer_config(directory)?;
+        let special_token_map = read_special_token_map::<RobertaVocab>(directory)?;
+        let vocab = RobertaVocab::from_file_with_special_token_map(&file_path(directory, VOCAB_JSON_FILE), special_token_map)?;
+        let merges = BpePairVocab::from_file(&file_path(directory, MERGES_FILE))?;
+        let mut tokenizer = RobertaTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), lower_case);
+        restore_added_tokens(&mut tokenizer.vocab, &mut tokenizer.special_token_matcher, &read_added_tokens(directory)?);
+        Ok(tokenizer)
+    }
+
+    /// Replaces the cache of byte pair encoded words by a cache bounded to `capacity` words. A capacity of 0 disables caching.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> RobertaTokenizer {
+        self.cache = BpeCache::new(capacity);
+        self
+    }
+
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
+
+    pub fn merges(&self) -> &BpePairVocab {
+        &self.bpe_ranks
+    }
+
+    pub fn lower_case(&self) -> bool {
+        self.lower_case
+    }
+
+    /// Adds tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer. Returns the id of each token.
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, false)
+    }
+
+    /// Adds special tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer, are flagged in the
+    /// special tokens mask and are skipped when decoding with `skip_special_tokens`. Returns the id of each token.
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, true)
     }
 }
 
@@ -56,101 +113,69 @@ impl Tokenizer<RobertaVocab> for RobertaTokenizer {
         self.vocab.as_ref()
     }
 
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        let mut tokenized_text: Vec<String> = Vec::with_capacity(text.len());
-        let temp_text = split_on_special_tokens(text, self.vocab.as_ref());
-        let temp_text = temp_text
-            .into_iter()
-            .map(|v| if self.lower_case { v.to_lowercase() } else { v.to_owned() })
-            .collect_vec();
-
-//        Rust regex's library does not include lookahead, decomposing the process in 2 steps
-        for text in temp_text {
-            if !self.vocab.special_values.contains_key(text.as_str()) {
-                let mut sub_words: Vec<&str> = vec!();
-                let mut splits: Vec<&str> = vec!();
-
-                let mut i: usize = 0;
-                let mut end: usize;
-                for hit in self.pattern_lookahead.find_iter(text.as_str()) {
-                    end = hit.end() - 1 - hit.as_str().chars().last().unwrap().len_utf8();
-                    splits.push(&text[i..end]);
-                    i = end;
-                }
-                splits.push(&text[i..]);
-
-                for sub_word in splits {
-                    for hit in self.pattern_tokenization.find_iter(sub_word) {
-                        sub_words.push(hit.as_str());
-                    }
-                }
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(initial_token).into_iter().flatten().collect()
+    }
 
-                for word in sub_words {
-                    let word: String = word.as_bytes().iter().map(|v| BYTES_TO_UNICODE.get(&v).unwrap()).collect();
-                    let cached: bool = match self.cache.borrow().get(&word) {
-                        Some(value) => {
-                            tokenized_text.extend(value.clone());
-                            true
-                        }
-                        None => false
-                    };
-                    if !cached {
-                        let bpe_output = bpe(word.as_str(), self.bpe_ranks.as_ref());
-                        self.cache.borrow_mut().insert(word.to_owned(), bpe_output.clone());
-                        tokenized_text.extend(bpe_output);
-                    }
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        let mut words: Vec<Vec<Token>> = vec!();
+
+        for text in self.special_token_matcher.split_with_offsets(initial_token) {
+            if !self.special_token_matcher.contains(&text.text) {
+                let text = if self.lower_case { lowercase_with_offsets(&text) } else { text };
+                for word in text.slices(&split_gpt2_words(&text.text)) {
+//                    Each byte of the word is mapped to a unicode character, referencing the character it belongs to
+                    let reference_offsets: Vec<usize> = word.text
+                        .chars()
+                        .zip(word.reference_offsets.iter())
+                        .flat_map(|(character, &position)| repeat_n(position, character.len_utf8()))
+                        .collect();
+                    let word: String = word.text.as_bytes().iter().map(|v| BYTES_TO_UNICODE.get(&v).unwrap()).collect();
+                    let bpe_output = self.cache.get_or_insert_with(&word, || bpe(word.as_str(), self.bpe_ranks.as_ref()));
+                    let char_lengths = bpe_output.iter().map(|sub_token| sub_token.chars().count()).collect();
+                    words.push(bpe_sub_tokens_with_offsets(bpe_output, char_lengths, &reference_offsets));
                 };
             } else {
-                tokenized_text.push(text);
+                words.push(vec!(text));
             }
         }
-        tokenized_text
+        words
     }
 
     fn build_input_with_special_tokens(&self, tokens_1: Vec<i64>, tokens_2: Option<Vec<i64>>) -> (Vec<i64>, Vec<i8>, Vec<i8>) {
-        let mut output: Vec<i64> = vec!();
-        let mut token_segment_ids: Vec<i8> = vec!();
-        let mut special_tokens_mask: Vec<i8> = vec!();
-        special_tokens_mask.push(1);
-        special_tokens_mask.extend(vec![0; tokens_1.len()]);
-        special_tokens_mask.push(1);
-        token_segment_ids.extend(vec![0; tokens_1.len() + 2]);
-        output.push(self.vocab.token_to_id(RobertaVocab::cls_value()));
-        output.extend(tokens_1);
-        output.push(self.vocab.token_to_id(RobertaVocab::sep_value()));
-        if let Some(add_tokens) = tokens_2 {
-            special_tokens_mask.push(1);
-            special_tokens_mask.extend(vec![0; add_tokens.len()]);
-            special_tokens_mask.push(1);
-            token_segment_ids.push(0);
-            token_segment_ids.extend(vec![1; add_tokens.len() + 1]);
-            output.push(self.vocab.token_to_id(RobertaVocab::sep_value()));
-            output.extend(add_tokens);
-            output.push(self.vocab.token_to_id(RobertaVocab::sep_value()));
-        }
-        (output, token_segment_ids, special_tokens_mask)
+        self.post_processor.build_input_with_special_tokens(tokens_1, tokens_2)
     }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
-        let tokens = tokens
-            .iter()
-            .join("")
-            .replace(" ##", "")
-            .trim()
-            .chars()
-            .map(|character| UNICODE_TO_BYTES.get(&character).unwrap().clone())
-            .collect_vec();
-
-        String::from_utf8(tokens).unwrap()
+        let mut bytes: Vec<u8> = vec!();
+        for token in tokens {
+//            Special and added tokens are not byte-level encoded
+            if self.special_token_matcher.contains(&token) {
+                bytes.extend(token.as_bytes());
+            } else {
+                for character in token.chars() {
+                    match UNICODE_TO_BYTES.get(&character) {
+                        Some(byte) => bytes.push(*byte),
+                        None => bytes.extend(character.to_string().as_bytes())
+                    }
+                }
+            }
+        }
+
+//        Tokens cut in the middle of a multi-byte character, e.g. by truncation, decode to the replacement character
+        String::from_utf8_lossy(&bytes).into_owned()
     }
 }
 
+impl MultiThreadedTokenizer<RobertaVocab> for RobertaTokenizer {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::RobertaVocab;
     use std::collections::HashMap;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput};
+    use itertools::Itertools;
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
 
     fn generate_test_vocab() -> RobertaVocab {
@@ -182,7 +207,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        RobertaVocab { values, indices, unknown_value: "<unk>", special_values, special_indices }
+        RobertaVocab { values, indices, special_token_map: RobertaVocab::default_special_token_map(), special_values, special_indices }
     }
 
     fn generate_test_merges() -> BpePairVocab {
@@ -200,14 +225,14 @@ mod tests {
         ].iter().cloned().collect();
 
 
-        BpePairVocab { values }
+        BpePairVocab::from_values(values)
     }
 
     #[test]
     fn test_roberta_tokenizer() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let roberta_tokenizer: RobertaTokenizer = RobertaTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
             (
@@ -231,14 +256,15 @@ mod tests {
             assert_eq!(roberta_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(roberta_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()), expected_results);
     }
 
     #[test]
     fn test_roberta_tokenizer_no_lower_casing() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let roberta_tokenizer: RobertaTokenizer = RobertaTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
             (
@@ -262,29 +288,30 @@ mod tests {
             assert_eq!(roberta_tokenizer.tokenize(*source_text), *expected_result);
         }
 
-        assert_eq!(roberta_tokenizer.tokenize_list(source_texts.clone()), expected_results);
+        assert_eq!(Tokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()), expected_results);
     }
 
 
     #[test]
     fn test_encode() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let roberta_tokenizer: RobertaTokenizer = RobertaTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let truncation_strategy = TruncationStrategy::LongestFirst;
         let test_tuples = [
             (
                 "the earth",
-                TokenizedInput { token_ids: vec!(8, 4, 12, 13, 9), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(8, 4, 12, 13, 9), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 3 }), Some(Offset { begin: 3, end: 7 }), Some(Offset { begin: 7, end: 9 }), None), attention_mask: vec!(1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(1), Some(1), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None) }
             ),
             (
                 "✿",
-                TokenizedInput { token_ids: vec!(8, 6, 6, 6, 9), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(8, 6, 6, 6, 9), segment_ids: vec!(0, 0, 0, 0, 0), special_tokens_mask: vec!(1, 0, 0, 0, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 0, end: 1 }), None), attention_mask: vec!(1, 1, 1, 1, 1), word_ids: vec!(None, Some(0), Some(0), Some(0), None), sequence_ids: vec!(None, Some(0), Some(0), Some(0), None) }
             ),
             (
                 "",
-                TokenizedInput { token_ids: vec!(8, 9), segment_ids: vec!(0, 0), special_tokens_mask: vec!(1, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0 }
+                TokenizedInput { token_ids: vec!(8, 9), segment_ids: vec!(0, 0), special_tokens_mask: vec!(1, 1), overflowing_tokens: vec!(), num_truncated_tokens: 0, token_offsets: vec!(None, None), attention_mask: vec!(1, 1), word_ids: vec!(None, None), sequence_ids: vec!(None, None) }
             )
         ];
         let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
@@ -292,17 +319,18 @@ mod tests {
 
 //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            assert_eq!(roberta_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+            assert_eq!(roberta_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right),
                        *expected_result);
         }
-        assert_eq!(roberta_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0), expected_results);
+        assert_eq!(Tokenizer::encode_list(&roberta_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
+        assert_eq!(MultiThreadedTokenizer::encode_list(&roberta_tokenizer, source_texts.clone(), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right), expected_results);
     }
 
     #[test]
     fn test_decode() {
 //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let roberta_tokenizer: RobertaTokenizer = RobertaTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let skip_special_tokens = false;
         let clean_up_tokenization_spaces = false;
@@ -322,4 +350,17 @@ mod tests {
         }
         assert_eq!(Tokenizer::decode_list(&roberta_tokenizer, source_ids.clone(), skip_special_tokens, clean_up_tokenization_spaces), expected_results);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_convert_partial_multi_byte_character_to_string() {
+//        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let roberta_tokenizer: RobertaTokenizer = RobertaTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+
+//        When & Then
+        assert_eq!(roberta_tokenizer.convert_tokens_to_string(vec!("the".to_owned(), "Ġ".to_owned(), "Ã©".to_owned())), "the é");
+        assert_eq!(roberta_tokenizer.convert_tokens_to_string(vec!("the".to_owned(), "Ġ".to_owned(), "Ã".to_owned())), "the \u{FFFD}");
+        assert_eq!(roberta_tokenizer.convert_tokens_to_string(vec!("©".to_owned(), "th".to_owned())), "\u{FFFD}th");
+    }
+}
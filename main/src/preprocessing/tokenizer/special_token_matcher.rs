@@ -0,0 +1,294 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::preprocessing::vocab::base_vocab::Vocab;
+use crate::preprocessing::tokenizer::base_tokenizer::Token;
+
+/// Token added to a tokenizer after its creation, recorded so that it can be added again when the tokenizer is reloaded.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AddedToken {
+    pub(crate) content: String,
+    pub(crate) special: bool,
+}
+
+#[derive(Clone, Default)]
+struct TrieNode {
+    children: HashMap<char, usize>,
+    is_token: bool,
+}
+
+/// Prefix tree of the special tokens of a vocabulary, splitting texts on special tokens in a single pass.
+///
+/// At each position of the text the longest special token starting there is matched, so that the result does not depend
+/// on the order of the special tokens when one of them is a prefix of another. Built once per vocabulary by the tokenizers.
+#[derive(Clone)]
+pub struct SpecialTokenMatcher {
+    nodes: Vec<TrieNode>,
+    num_tokens: usize,
+    /// Tokens registered with `register_added_tokens`, in the order they were added
+    added_tokens: Vec<AddedToken>,
+}
+
+impl SpecialTokenMatcher {
+    pub fn new<I, S>(tokens: I) -> SpecialTokenMatcher
+        where I: IntoIterator<Item=S>,
+              S: AsRef<str> {
+        let mut matcher = SpecialTokenMatcher { nodes: vec!(TrieNode::default()), num_tokens: 0, added_tokens: vec!() };
+        for token in tokens {
+            matcher.insert(token.as_ref());
+        }
+        matcher
+    }
+
+    /// Creates a matcher for the special values of the vocabulary.
+    pub fn from_vocab(vocab: &impl Vocab) -> SpecialTokenMatcher {
+        SpecialTokenMatcher::new(vocab.special_values().keys())
+    }
+
+    /// Adds a token to the matcher. Empty tokens are ignored.
+    pub fn insert(&mut self, token: &str) {
+        if token.is_empty() {
+            return;
+        }
+        let mut node = 0;
+        for character in token.chars() {
+            node = match self.nodes[node].children.get(&character) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(character, child);
+                    child
+                }
+            };
+        }
+        if !self.nodes[node].is_token {
+            self.nodes[node].is_token = true;
+            self.num_tokens += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_tokens == 0
+    }
+
+    pub(crate) fn added_tokens(&self) -> &[AddedToken] {
+        &self.added_tokens
+    }
+
+    /// Returns true if the text is one of the tokens of the matcher.
+    pub fn contains(&self, text: &str) -> bool {
+        !text.is_empty() && self.longest_match(text) == Some(text.len())
+    }
+
+    /// Returns the byte length of the longest token the text starts with.
+    pub fn longest_match(&self, text: &str) -> Option<usize> {
+        let mut node = 0;
+        let mut longest = None;
+        for (position, character) in text.char_indices() {
+            match self.nodes[node].children.get(&character) {
+                Some(&child) => {
+                    node = child;
+                    if self.nodes[node].is_token {
+                        longest = Some(position + character.len_utf8());
+                    }
+                }
+                None => break
+            }
+        }
+        longest
+    }
+
+    /// Splits the text on special tokens. The text between special tokens is trimmed, and dropped if empty.
+    /// A text without any content is returned as a single empty string.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        self.split_with_positions(text)
+            .into_iter()
+            .map(|(_, subtext)| subtext)
+            .collect()
+    }
+
+    /// Splits the token on special tokens, keeping the reference offsets of each sub-token.
+    pub fn split_with_offsets(&self, token: &Token) -> Vec<Token> {
+        let ranges: Vec<(usize, usize)> = self.split_with_positions(token.text.as_str())
+            .into_iter()
+            .map(|(position, subtext)| (position, position + subtext.len()))
+            .collect();
+        token.slices(&ranges)
+    }
+
+    /// Splits the text on special tokens, returning the byte position of each sub-text in `text`.
+    fn split_with_positions<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        if self.is_empty() {
+            return vec!((0, text));
+        }
+        let mut output: Vec<(usize, &str)> = vec!();
+        let mut chunk_start = 0;
+        let mut position = 0;
+        while position < text.len() {
+            match self.longest_match(&text[position..]) {
+                Some(length) => {
+                    push_trimmed(&mut output, text, chunk_start, position);
+                    output.push((position, &text[position..position + length]));
+                    position += length;
+                    chunk_start = position;
+                }
+                None => position += text[position..].chars().next().unwrap().len_utf8()
+            }
+        }
+        push_trimmed(&mut output, text, chunk_start, text.len());
+        if output.is_empty() {
+            output.push((0, &text[..0]));
+        }
+        output
+    }
+}
+
+/// Adds tokens to the vocabulary of a tokenizer and to its matcher, so that they are never split. The vocabulary is copied first
+/// if it is shared with other tokenizers, which are left unchanged.
+pub(crate) fn register_added_tokens<T: Vocab + Clone>(vocab: &mut Arc<T>, matcher: &mut SpecialTokenMatcher, tokens: &[&str], special: bool)
+                                                      -> Vec<i64> {
+    let vocab = Arc::make_mut(vocab);
+    let token_ids = if special { vocab.add_special_tokens(tokens) } else { vocab.add_tokens(tokens) };
+    for token in tokens.iter().filter(|token| !token.is_empty()) {
+        matcher.insert(token);
+        matcher.added_tokens.push(AddedToken { content: (*token).to_owned(), special });
+    }
+    token_ids
+}
+
+/// Adds again the tokens recorded by the matcher of a saved tokenizer, in their original order so that they get the same ids.
+pub(crate) fn restore_added_tokens<T: Vocab + Clone>(vocab: &mut Arc<T>, matcher: &mut SpecialTokenMatcher, added_tokens: &[AddedToken]) {
+    for added_token in added_tokens {
+        register_added_tokens(vocab, matcher, &[added_token.content.as_str()], added_token.special);
+    }
+}
+
+fn push_trimmed<'a>(output: &mut Vec<(usize, &'a str)>, text: &'a str, start: usize, end: usize) {
+    let chunk = &text[start..end];
+    let trimmed_chunk = chunk.trim();
+    if !trimmed_chunk.is_empty() {
+        output.push((start + chunk.len() - chunk.trim_start().len(), trimmed_chunk));
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::base_tokenizer::Offset;
+
+    #[test]
+    fn test_longest_match() {
+//        Given
+        let matcher = SpecialTokenMatcher::new(vec!("<s>", "<sep>", "<s>x", "[中]"));
+
+//        When & Then
+        assert_eq!(matcher.len(), 4);
+        assert_eq!(matcher.longest_match("<s> text"), Some(3));
+        assert_eq!(matcher.longest_match("<s>xyz"), Some(4));
+        assert_eq!(matcher.longest_match("<sep>"), Some(5));
+        assert_eq!(matcher.longest_match("<se"), None);
+        assert_eq!(matcher.longest_match("[中]"), Some(5));
+        assert_eq!(matcher.longest_match(" <s>"), None);
+        assert_eq!(matcher.longest_match(""), None);
+        assert!(matcher.contains("<s>x"));
+        assert!(!matcher.contains("<s>xy"));
+        assert!(!matcher.contains("<se"));
+        assert!(!matcher.contains(""));
+    }
+
+    #[test]
+    fn test_split_on_prefix_tokens() {
+//        Given
+        let test_tuples = [
+            (
+                "a<s>xb<s>c",
+                vec!("a", "<s>x", "b", "<s>", "c")
+            ),
+            (
+                "<s><s>x <sep>",
+                vec!("<s>", "<s>x", "<sep>")
+            ),
+            (
+                "  a  <s>  ",
+                vec!("a", "<s>")
+            ),
+            (
+                "   ",
+                vec!("")
+            ),
+            (
+                "",
+                vec!("")
+            ),
+        ];
+
+        let matcher = SpecialTokenMatcher::new(vec!("<s>", "<s>x", "<sep>"));
+        let reversed_matcher = SpecialTokenMatcher::new(vec!("<sep>", "<s>x", "<s>"));
+
+//        When & Then
+        for (source_text, expected_result) in test_tuples.iter() {
+            assert_eq!(matcher.split(source_text), *expected_result);
+            assert_eq!(reversed_matcher.split(source_text), *expected_result);
+        }
+    }
+
+    #[test]
+    fn test_split_without_special_tokens() {
+//        Given
+        let matcher = SpecialTokenMatcher::new(Vec::<String>::new());
+
+//        When & Then
+        assert!(matcher.is_empty());
+        assert_eq!(matcher.split(" some text "), vec!(" some text "));
+    }
+
+    #[test]
+    fn test_split_with_offsets() {
+//        Given
+        let matcher = SpecialTokenMatcher::new(vec!("[MASK]"));
+
+//        When
+        let tokens = matcher.split_with_offsets(&Token::new("中 [MASK] b"));
+
+//        Then
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        let offsets: Vec<Option<Offset>> = tokens.iter().map(|token| token.offset()).collect();
+        assert_eq!(texts, vec!("中", "[MASK]", "b"));
+        assert_eq!(offsets, vec!(Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 2, end: 8 }), Some(Offset { begin: 9, end: 10 })));
+    }
+
+    #[test]
+    fn test_split_with_many_special_tokens() {
+//        Given
+        let tokens: Vec<String> = (0..500).map(|index| format!("<extra_id_{}>", index)).collect();
+        let matcher = SpecialTokenMatcher::new(&tokens);
+        let text = (0..500).rev().map(|index| format!("word<extra_id_{}>", index)).collect::<Vec<String>>().join(" ");
+
+//        When
+        let split_text = matcher.split(&text);
+
+//        Then
+        assert_eq!(split_text.len(), 1000);
+        assert_eq!(split_text[0], "word");
+        assert_eq!(split_text[1], "<extra_id_499>");
+        assert_eq!(split_text[999], "<extra_id_0>");
+    }
+}
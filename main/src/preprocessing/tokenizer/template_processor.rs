@@ -0,0 +1,354 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, MultiThreadedTokenizer, Token};
+use crate::preprocessing::vocab::base_vocab::Vocab;
+use crate::preprocessing::error::TokenizerError;
+
+/// Item of a post-processing template: one of the input sequences or a special token, with the segment id of its tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemplatePiece {
+    /// The first (`$A`, index 0) or the second (`$B`, index 1) sequence
+    Sequence { index: usize, segment_id: i8 },
+    SpecialToken { id: i64, segment_id: i8 },
+}
+
+/// Post-processor placing the special tokens around the sequences following a template, for a single sequence and for a pair.
+///
+/// Templates are written as space separated pieces: `$A` and `$B` stand for the first and second sequences and any other piece
+/// is a token of the vocabulary. A piece can be followed by `:` and its segment id, which defaults to 0. For example the BERT
+/// layout is `[CLS] $A [SEP]` for single sequences and `[CLS] $A [SEP] $B:1 [SEP]:1` for pairs.
+/// The first sequence must come before the second one, as the offsets of the tokens are assigned in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateProcessor {
+    single: Vec<TemplatePiece>,
+    pair: Vec<TemplatePiece>,
+}
+
+impl TemplateProcessor {
+    /// Parses the single and pair templates, the special tokens being looked up in `vocab`.
+    pub fn new<T: Vocab>(single: &str, pair: &str, vocab: &T) -> Result<TemplateProcessor, TokenizerError> {
+        TemplateProcessor::from_pieces(parse_template(single, vocab)?, parse_template(pair, vocab)?)
+    }
+
+    /// Creates a post-processor from pieces, checking that the single template contains the first sequence only
+    /// and that the pair template contains the first and then the second sequence.
+    pub fn from_pieces(single: Vec<TemplatePiece>, pair: Vec<TemplatePiece>) -> Result<TemplateProcessor, TokenizerError> {
+        check_sequences(&single, &[0], "single")?;
+        check_sequences(&pair, &[0, 1], "pair")?;
+        Ok(TemplateProcessor { single, pair })
+    }
+
+    /// `[CLS] $A [SEP]` and `[CLS] $A [SEP] $B:1 [SEP]:1`, the layout of BERT and ALBERT.
+    pub fn bert(cls_id: i64, sep_id: i64) -> TemplateProcessor {
+        TemplateProcessor {
+            single: vec!(special_token(cls_id, 0), sequence(0, 0), special_token(sep_id, 0)),
+            pair: vec!(special_token(cls_id, 0), sequence(0, 0), special_token(sep_id, 0), sequence(1, 1), special_token(sep_id, 1)),
+        }
+    }
+
+    /// `<s> $A </s>` and `<s> $A </s> </s> $B </s>`, the second sequence and its separators having the segment id
+    /// `second_segment_id`: 1 for RoBERTa, 0 for XLM-RoBERTa which has a single token type.
+    pub fn roberta(cls_id: i64, sep_id: i64, second_segment_id: i8) -> TemplateProcessor {
+        TemplateProcessor {
+            single: vec!(special_token(cls_id, 0), sequence(0, 0), special_token(sep_id, 0)),
+            pair: vec!(special_token(cls_id, 0), sequence(0, 0), special_token(sep_id, 0),
+                       special_token(sep_id, 0), sequence(1, second_segment_id), special_token(sep_id, second_segment_id)),
+        }
+    }
+
+    /// `$A <sep> <cls>:2` and `$A <sep> $B:1 <sep>:1 <cls>:2`: XLNet appends the classification token at the end of the sequences.
+    pub fn xlnet(sep_id: i64, cls_id: i64) -> TemplateProcessor {
+        TemplateProcessor {
+            single: vec!(sequence(0, 0), special_token(sep_id, 0), special_token(cls_id, 2)),
+            pair: vec!(sequence(0, 0), special_token(sep_id, 0), sequence(1, 1), special_token(sep_id, 1), special_token(cls_id, 2)),
+        }
+    }
+
+    /// `$A </s>` and `$A </s> $B </s>`: T5 terminates each sequence with the end of sequence token and does not use segment ids.
+    pub fn t5(eos_id: i64) -> TemplateProcessor {
+        TemplateProcessor {
+            single: vec!(sequence(0, 0), special_token(eos_id, 0)),
+            pair: vec!(sequence(0, 0), special_token(eos_id, 0), sequence(1, 0), special_token(eos_id, 0)),
+        }
+    }
+
+    /// Returns the token ids, the segment ids and the special tokens mask of the sequences laid out following the template.
+    pub fn build_input_with_special_tokens(&self, tokens_1: Vec<i64>, tokens_2: Option<Vec<i64>>) -> (Vec<i64>, Vec<i8>, Vec<i8>) {
+        let template = if tokens_2.is_some() { &self.pair } else { &self.single };
+        let sequences = [Some(tokens_1), tokens_2];
+        let capacity = template.len() + sequences.iter().flatten().map(Vec::len).sum::<usize>();
+        let mut output: Vec<i64> = Vec::with_capacity(capacity);
+        let mut token_segment_ids: Vec<i8> = Vec::with_capacity(capacity);
+        let mut special_tokens_mask: Vec<i8> = Vec::with_capacity(capacity);
+        for piece in template {
+            match *piece {
+                TemplatePiece::Sequence { index, segment_id } => {
+                    let tokens = sequences[index].as_deref().unwrap_or_default();
+                    output.extend_from_slice(tokens);
+                    token_segment_ids.extend(vec![segment_id; tokens.len()]);
+                    special_tokens_mask.extend(vec![0; tokens.len()]);
+                }
+                TemplatePiece::SpecialToken { id, segment_id } => {
+                    output.push(id);
+                    token_segment_ids.push(segment_id);
+                    special_tokens_mask.push(1);
+                }
+            }
+        }
+        (output, token_segment_ids, special_tokens_mask)
+    }
+}
+
+fn sequence(index: usize, segment_id: i8) -> TemplatePiece {
+    TemplatePiece::Sequence { index, segment_id }
+}
+
+fn special_token(id: i64, segment_id: i8) -> TemplatePiece {
+    TemplatePiece::SpecialToken { id, segment_id }
+}
+
+fn parse_template<T: Vocab>(template: &str, vocab: &T) -> Result<Vec<TemplatePiece>, TokenizerError> {
+    template
+        .split_whitespace()
+        .map(|piece| {
+//            A colon is part of the token unless it is followed by a valid segment id
+            let (name, segment_id) = match piece.rsplit_once(':') {
+                Some((name, segment_id)) if !name.is_empty() => match segment_id.parse::<i8>() {
+                    Ok(segment_id) => (name, segment_id),
+                    Err(_) => (piece, 0)
+                },
+                _ => (piece, 0)
+            };
+            match name {
+                "$A" => Ok(sequence(0, segment_id)),
+                "$B" => Ok(sequence(1, segment_id)),
+                _ if name.starts_with('$') => Err(TokenizerError::InvalidTemplate(format!("unknown sequence {} in {}", name, template))),
+                _ => match vocab.values().get(name) {
+                    Some(id) => Ok(special_token(*id, segment_id)),
+                    None => Err(TokenizerError::MissingSpecialToken(name.to_owned()))
+                }
+            }
+        })
+        .collect()
+}
+
+fn check_sequences(template: &[TemplatePiece], expected: &[usize], name: &str) -> Result<(), TokenizerError> {
+    let sequences: Vec<usize> = template
+        .iter()
+        .filter_map(|piece| match piece {
+            TemplatePiece::Sequence { index, .. } => Some(*index),
+            TemplatePiece::SpecialToken { .. } => None
+        })
+        .collect();
+    if sequences != expected {
+        let expected = expected.iter().map(|index| if *index == 0 { "$A" } else { "$B" }).collect::<Vec<&str>>().join(" then ");
+        return Err(TokenizerError::InvalidTemplate(format!("the {} template must contain {}", name, expected)));
+    }
+    Ok(())
+}
+
+/// Tokenizer placing the special tokens following a `TemplateProcessor` instead of the layout of the wrapped tokenizer,
+/// for example to surround GPT-2 inputs with `<|endoftext|>` tokens or to use a custom prompt layout.
+pub struct TemplateTokenizer<T> {
+    tokenizer: T,
+    template: TemplateProcessor,
+}
+
+impl<T> TemplateTokenizer<T> {
+    pub fn new(tokenizer: T, template: TemplateProcessor) -> TemplateTokenizer<T> {
+        TemplateTokenizer { tokenizer, template }
+    }
+
+    /// Returns the wrapped tokenizer.
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn template(&self) -> &TemplateProcessor {
+        &self.template
+    }
+}
+
+impl<V: Vocab, T: Tokenizer<V>> Tokenizer<V> for TemplateTokenizer<T> {
+    fn vocab(&self) -> &V {
+        self.tokenizer.vocab()
+    }
+
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenizer.tokenize_to_tokens(initial_token)
+    }
+
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        self.tokenizer.tokenize_to_words(initial_token)
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
+        self.tokenizer.convert_tokens_to_string(tokens)
+    }
+
+    fn build_input_with_special_tokens(&self, tokens_1: Vec<i64>, tokens_2: Option<Vec<i64>>) -> (Vec<i64>, Vec<i8>, Vec<i8>) {
+        self.template.build_input_with_special_tokens(tokens_1, tokens_2)
+    }
+}
+
+impl<V: Vocab, T: MultiThreadedTokenizer<V>> MultiThreadedTokenizer<V> for TemplateTokenizer<T> {}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
+    use crate::{BertVocab, Gpt2Vocab, Gpt2Tokenizer};
+
+    fn generate_test_vocab() -> BertVocab {
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("world".to_owned(), 1),
+            ("[UNK]".to_owned(), 2),
+            ("!".to_owned(), 3),
+            ("[CLS]".to_owned(), 4),
+            ("[SEP]".to_owned(), 5),
+            ("[MASK]".to_owned(), 6),
+            ("[PAD]".to_owned(), 7),
+            ("Question:".to_owned(), 8),
+        ].iter().cloned().collect();
+
+        let special_values: HashMap<String, i64> = [
+            ("[UNK]".to_owned(), 2),
+            ("[CLS]".to_owned(), 4),
+            ("[SEP]".to_owned(), 5),
+            ("[MASK]".to_owned(), 6),
+            ("[PAD]".to_owned(), 7)
+        ].iter().cloned().collect();
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+
+        BertVocab { values, indices, special_token_map: BertVocab::default_special_token_map(), special_values, special_indices }
+    }
+
+    #[test]
+    fn test_parse_template() -> Result<(), TokenizerError> {
+//        Given
+        let vocab = generate_test_vocab();
+
+//        When
+        let template = TemplateProcessor::new("[CLS] $A [SEP]", "[CLS] $A [SEP] $B:1 [SEP]:1", &vocab)?;
+
+//        Then
+        assert_eq!(template, TemplateProcessor::bert(4, 5));
+        assert_eq!(TemplateProcessor::new("Question: $A", "Question: $A [SEP]:2 $B:1", &vocab)?.pair,
+                   vec!(special_token(8, 0), sequence(0, 0), special_token(5, 2), sequence(1, 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_template() {
+//        Given
+        let vocab = generate_test_vocab();
+        let test_tuples = [
+            (("[CLS] $A [SEP]", "[CLS] $A [SEP] [SEP]"), TokenizerError::InvalidTemplate("the pair template must contain $A then $B".to_owned())),
+            (("[CLS] $A $B", "$A $B"), TokenizerError::InvalidTemplate("the single template must contain $A".to_owned())),
+            (("$A", "$B $A"), TokenizerError::InvalidTemplate("the pair template must contain $A then $B".to_owned())),
+            (("$A", "$A $C"), TokenizerError::InvalidTemplate("unknown sequence $C in $A $C".to_owned())),
+            (("<s> $A", "$A $B"), TokenizerError::MissingSpecialToken("<s>".to_owned())),
+        ];
+
+//        When & Then
+        for ((single, pair), expected_error) in test_tuples.iter() {
+            assert_eq!(TemplateProcessor::new(single, pair, &vocab).err().as_ref(), Some(expected_error));
+        }
+    }
+
+    #[test]
+    fn test_build_input_with_special_tokens() {
+//        Given
+        let test_tuples = [
+            (
+                TemplateProcessor::bert(4, 5),
+                (vec!(4, 0, 1, 5, 3, 3, 5), vec!(0, 0, 0, 0, 1, 1, 1), vec!(1, 0, 0, 1, 0, 0, 1))
+            ),
+            (
+                TemplateProcessor::roberta(4, 5, 1),
+                (vec!(4, 0, 1, 5, 5, 3, 3, 5), vec!(0, 0, 0, 0, 0, 1, 1, 1), vec!(1, 0, 0, 1, 1, 0, 0, 1))
+            ),
+            (
+                TemplateProcessor::xlnet(5, 4),
+                (vec!(0, 1, 5, 3, 3, 5, 4), vec!(0, 0, 0, 1, 1, 1, 2), vec!(0, 0, 1, 0, 0, 1, 1))
+            ),
+            (
+                TemplateProcessor::t5(5),
+                (vec!(0, 1, 5, 3, 3, 5), vec!(0, 0, 0, 0, 0, 0), vec!(0, 0, 1, 0, 0, 1))
+            ),
+        ];
+
+//        When & Then
+        for (template, expected_result) in test_tuples.iter() {
+            assert_eq!(template.build_input_with_special_tokens(vec!(0, 1), Some(vec!(3, 3))), *expected_result);
+        }
+        assert_eq!(TemplateProcessor::xlnet(5, 4).build_input_with_special_tokens(vec!(0, 1), None),
+                   (vec!(0, 1, 5, 4), vec!(0, 0, 0, 2), vec!(0, 0, 1, 1)));
+    }
+
+    #[test]
+    fn test_encode_with_template() -> Result<(), TokenizerError> {
+//        Given
+        let values: HashMap<String, i64> = [
+            ("the".to_owned(), 0),
+            ("Ġearth".to_owned(), 1),
+            ("<|endoftext|>".to_owned(), 2),
+        ].iter().cloned().collect();
+        let vocab = Arc::new(Gpt2Vocab::from_values(values)?);
+        let merges = BpePairVocab::from_values([
+            (("t".to_owned(), "h".to_owned()), 0),
+            (("th".to_owned(), "e".to_owned()), 1),
+            (("Ġ".to_owned(), "e".to_owned()), 2),
+            (("Ġe".to_owned(), "a".to_owned()), 3),
+            (("Ġea".to_owned(), "r".to_owned()), 4),
+            (("Ġear".to_owned(), "th".to_owned()), 5),
+        ].iter().cloned().collect());
+        let gpt2_tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, Arc::new(merges), false);
+        let template = TemplateProcessor::new("<|endoftext|> $A <|endoftext|>", "$A <|endoftext|> $B:1", Tokenizer::vocab(&gpt2_tokenizer))?;
+        let tokenizer = TemplateTokenizer::new(gpt2_tokenizer, template);
+
+//        When
+        let tokenized_input = tokenizer.encode("the earth", None, 3, &TruncationStrategy::LongestFirst, 0,
+                                               &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+        let tokenized_pair = tokenizer.encode("the earth", Some("the"), 10, &TruncationStrategy::LongestFirst, 0,
+                                              &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(tokenized_input, TokenizedInput {
+            token_ids: vec!(2, 0, 2),
+            segment_ids: vec!(0, 0, 0),
+            special_tokens_mask: vec!(1, 0, 1),
+            overflowing_tokens: vec!(1),
+            num_truncated_tokens: 1,
+            token_offsets: vec!(None, Some(Offset { begin: 0, end: 3 }), None),
+            attention_mask: vec!(1, 1, 1),
+            word_ids: vec!(None, Some(0), None),
+            sequence_ids: vec!(None, Some(0), None),
+        });
+        assert_eq!(tokenized_pair.token_ids, vec!(0, 1, 2, 0));
+        assert_eq!(tokenized_pair.segment_ids, vec!(0, 0, 0, 1));
+        assert_eq!(tokenized_pair.token_offsets, vec!(Some(Offset { begin: 0, end: 3 }), Some(Offset { begin: 3, end: 9 }), None,
+                                                      Some(Offset { begin: 0, end: 3 })));
+        assert_eq!(tokenizer.decode(tokenized_input.token_ids, true, false), "the");
+        Ok(())
+    }
+}
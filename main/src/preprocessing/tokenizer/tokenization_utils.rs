@@ -12,86 +12,76 @@
 // limitations under the License.
 
 use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::BertVocab;
 use crate::preprocessing::tokenizer::constants::{WHITESPACE_CHARS, ADDITIONAL_WHITESPACE_CHARS,
                                                  PUNCTUATION_CHARS, CONTROL_CHARS, ACCENT_MARKERS};
 use unicode_normalization::char::decompose_canonical;
 use std::char;
 use std::char::REPLACEMENT_CHARACTER;
-use std::error::Error;
+use crate::preprocessing::error::TokenizerError;
 use std::cmp::min;
-use crate::preprocessing::tokenizer::base_tokenizer::TruncationStrategy;
-use std::collections::HashSet;
+use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, Token};
+use std::collections::{HashSet, BinaryHeap};
+use std::cmp::Reverse;
+use itertools::Itertools;
 use crate::preprocessing::vocab::bpe_vocab::{BpePairRef, BpePairVocab};
+use crate::preprocessing::tokenizer::special_token_matcher::SpecialTokenMatcher;
+use regex::Regex;
 
+lazy_static! {
+    static ref GPT2_LOOKAHEAD_PATTERN: Regex = Regex::new(r"\s+\S").unwrap();
+    static ref GPT2_TOKENIZATION_PATTERN: Regex = Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+").unwrap();
+}
 
 pub fn clean_text(text: &str, strict: bool) -> String {
-    let mut output = String::new();
-    for character in text.chars() {
+    clean_text_with_offsets(&Token::new(text), strict).text
+}
+
+pub fn clean_text_with_offsets(token: &Token, strict: bool) -> Token {
+    let mut text = String::with_capacity(token.text.len());
+    let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+    for (character, &position) in token.text.chars().zip(token.reference_offsets.iter()) {
         if is_control(&character, strict) || character == '\x00' || character == REPLACEMENT_CHARACTER {
             continue;
         }
         if is_whitespace(&character) {
-            output.push(' ');
+            text.push(' ');
         } else {
-            output.push(character);
+            text.push(character);
         }
+        reference_offsets.push(position);
     }
-    output
+    Token { text, reference_offsets }
 }
 
-pub fn split_on_special_tokens<'a>(text: &'a str, vocab: &'a impl Vocab) -> Vec<&'a str> {
-    let mut text_list: Vec<&str> = vec!(text);
-    let mut temp_list: Vec<&str>;
-
-    for special_value in vocab.special_values() {
-        temp_list = vec!();
-        for subtext in &text_list {
-            let new_items = split_with_separator(subtext, special_value.0);
-            temp_list.extend(new_items);
-        }
-        text_list = temp_list;
-    }
-    text_list
+/// Splits the text on the special values of the vocabulary. Tokenizers splitting many texts should keep a `SpecialTokenMatcher` instead.
+pub fn split_on_special_tokens<'a>(text: &'a str, vocab: &impl Vocab) -> Vec<&'a str> {
+    SpecialTokenMatcher::from_vocab(vocab).split(text)
 }
 
-fn split_with_separator<'a>(text: &'a str, separator: &'a str) -> Vec<&'a str> {
-    let split_text: Vec<&str> = text.split(separator).collect();
-    let mut result: Vec<&str> = vec!();
-    if text.is_empty() {
-        result.push(text);
-        return result;
-    }
-    for (i, subtext) in split_text.iter().enumerate() {
-        let trimmed_subtext = subtext.trim();
-        if (i == 0) & trimmed_subtext.is_empty() {
-            result.push(separator);
-        } else if i == split_text.len() - 1 {
-            if !trimmed_subtext.is_empty() {
-                result.push(trimmed_subtext);
-            }
-        } else {
-            if !trimmed_subtext.is_empty() {
-                result.push(trimmed_subtext);
-            }
-            result.push(separator);
-        }
-    }
-    result
+/// Splits the token on the special values of the vocabulary. Tokenizers splitting many texts should keep a `SpecialTokenMatcher` instead.
+pub fn split_on_special_tokens_with_offsets(token: &Token, vocab: &impl Vocab) -> Vec<Token> {
+    SpecialTokenMatcher::from_vocab(vocab).split_with_offsets(token)
 }
 
 pub fn tokenize_cjk_chars(text: &str) -> String {
-    let mut output = String::new();
-    for character in text.chars() {
+    tokenize_cjk_chars_with_offsets(&Token::new(text)).text
+}
+
+pub fn tokenize_cjk_chars_with_offsets(token: &Token) -> Token {
+    let mut text = String::with_capacity(token.text.len());
+    let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+    for (character, &position) in token.text.chars().zip(token.reference_offsets.iter()) {
         if is_cjk_char(&character) {
-            output.push(' ');
-            output.push(character);
-            output.push(' ');
+            text.push(' ');
+            text.push(character);
+            text.push(' ');
+            reference_offsets.extend(&[position, position, position]);
         } else {
-            output.push(character);
+            text.push(character);
+            reference_offsets.push(position);
         }
     }
-    output
+    Token { text, reference_offsets }
 }
 
 fn is_cjk_char(character: &char) -> bool {
@@ -160,55 +150,100 @@ pub fn whitespace_tokenize(text: &str) -> Vec<&str> {
     text.trim().split(' ').filter(|v| !v.is_empty()).collect()
 }
 
+pub fn whitespace_tokenize_with_offsets(token: &Token) -> Vec<Token> {
+    let mut output: Vec<Token> = vec!();
+    let mut current_token = Token { text: String::new(), reference_offsets: vec!() };
+    for (character, &position) in token.text.chars().zip(token.reference_offsets.iter()) {
+        if character == ' ' {
+            if !current_token.text.is_empty() {
+                output.push(current_token);
+                current_token = Token { text: String::new(), reference_offsets: vec!() };
+            }
+        } else {
+            current_token.text.push(character);
+            current_token.reference_offsets.push(position);
+        }
+    }
+    if !current_token.text.is_empty() {
+        output.push(current_token);
+    }
+    output
+}
+
+pub fn lowercase_with_offsets(token: &Token) -> Token {
+    let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+    for (character, &position) in token.text.chars().zip(token.reference_offsets.iter()) {
+        reference_offsets.extend(character.to_lowercase().map(|_| position));
+    }
+    Token { text: token.text.to_lowercase(), reference_offsets }
+}
+
 pub fn strip_accents(text: String) -> String {
-    let mut decomposed_string: String = String::with_capacity(text.capacity());
-    for character in text.chars() {
+    strip_accents_with_offsets(&Token::new(text.as_str())).text
+}
+
+pub fn strip_accents_with_offsets(token: &Token) -> Token {
+    let mut text: String = String::with_capacity(token.text.len());
+    let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+    for (character, &position) in token.text.chars().zip(token.reference_offsets.iter()) {
         decompose_canonical(character, |c| if !ACCENT_MARKERS.contains(&(c as u32)) {
-            decomposed_string.push(c)
+            text.push(c);
+            reference_offsets.push(position);
         });
     }
-    decomposed_string
+    Token { text, reference_offsets }
 }
 
 pub fn split_on_punct(text: String, vocab: &impl Vocab) -> Vec<String> {
-    let mut output: Vec<String> = Vec::new();
-    let mut start_new_word: bool = true;
-    let mut temp_string = String::new();
-    if vocab.special_values().contains_key(&text) {
-        output.push(text);
+    split_on_punct_with_offsets(&Token::new(text.as_str()), vocab)
+        .into_iter()
+        .map(|token| token.text)
+        .collect()
+}
+
+pub fn split_on_punct_with_offsets(token: &Token, vocab: &impl Vocab) -> Vec<Token> {
+    let mut output: Vec<Token> = Vec::new();
+    if vocab.special_values().contains_key(&token.text) {
+        output.push(token.clone());
         output
     } else {
-        for character in text.chars() {
+        let mut current_token = Token { text: String::new(), reference_offsets: vec!() };
+        for (character, &position) in token.text.chars().zip(token.reference_offsets.iter()) {
             if is_punctuation(&character) {
-                if !&temp_string.is_empty() {
-                    output.push(temp_string.clone());
-                    temp_string = String::new();
+                if !current_token.text.is_empty() {
+                    output.push(current_token);
+                    current_token = Token { text: String::new(), reference_offsets: vec!() };
                 }
-                output.push(character.to_string());
-                start_new_word = true
+                output.push(Token { text: character.to_string(), reference_offsets: vec!(position) });
             } else {
-                if start_new_word {
-                    temp_string = String::new();
-                }
-                start_new_word = false;
-                temp_string.push(character);
+                current_token.text.push(character);
+                current_token.reference_offsets.push(position);
             }
         }
-        if !start_new_word & !&temp_string.is_empty() {
-            output.push(temp_string.clone());
+        if !current_token.text.is_empty() {
+            output.push(current_token);
         }
         output
     }
 }
 
 pub fn tokenize_wordpiece(token: String, vocab: &impl Vocab, max_word_len: usize) -> Vec<String> {
-    let mut tokenized_text: Vec<String> = Vec::new();
-    if token.chars().count() > max_word_len {
-        tokenized_text.push(BertVocab::unknown_value().to_owned());
+    tokenize_wordpiece_with_offsets(&Token::new(token.as_str()), vocab, max_word_len)
+        .into_iter()
+        .map(|token| token.text)
+        .collect()
+}
+
+pub fn tokenize_wordpiece_with_offsets(token: &Token, vocab: &impl Vocab, max_word_len: usize) -> Vec<Token> {
+    let mut tokenized_text: Vec<Token> = Vec::new();
+    let unknown_token = Token { text: vocab.unknown_value().to_owned(), reference_offsets: token.reference_offsets.clone() };
+    if token.text.chars().count() > max_word_len {
+        tokenized_text.push(unknown_token);
     } else {
-        let char_indices: Vec<usize> = token.char_indices().map(|v| v.0).collect();
-        let max_end: usize = char_indices.last().unwrap() + token.chars().last().unwrap().len_utf8();
+        let char_indices: Vec<usize> = token.text.char_indices().map(|v| v.0).collect();
+        let max_end: usize = char_indices.last().unwrap() + token.text.chars().last().unwrap().len_utf8();
         let mut start: usize = 0;
+        let mut pos_start: usize = 0;
         let mut pos_end;
         let mut end;
         while start < max_end {
@@ -216,12 +251,12 @@ pub fn tokenize_wordpiece(token: String, vocab: &impl Vocab, max_word_len: usize
             pos_end = char_indices.len();
             let mut is_bad: bool = true;
             while start < end {
-                let mut substr = token[start..end].to_owned();
+                let mut substr = token.text[start..end].to_owned();
                 if start > 0 {
                     substr = format!("##{}", substr);
                 }
                 if vocab.values().contains_key(&substr) {
-                    tokenized_text.push(substr);
+                    tokenized_text.push(Token { text: substr, reference_offsets: token.reference_offsets[pos_start..pos_end].to_vec() });
                     is_bad = false;
                     break;
                 }
@@ -229,11 +264,10 @@ pub fn tokenize_wordpiece(token: String, vocab: &impl Vocab, max_word_len: usize
                 end = char_indices[pos_end];
             }
             if is_bad {
-                let mut tokenized_text: Vec<String> = Vec::new();
-                tokenized_text.push(BertVocab::unknown_value().to_owned());
-                return tokenized_text;
+                return vec!(unknown_token);
             }
             start = end;
+            pos_start = pos_end;
         }
     }
     tokenized_text
@@ -241,78 +275,77 @@ pub fn tokenize_wordpiece(token: String, vocab: &impl Vocab, max_word_len: usize
 
 pub fn truncate_sequences(mut tokens_1: Vec<i64>, tokens_2: Option<Vec<i64>>,
                           num_tokens_to_remove: usize, truncation_strategy: &TruncationStrategy, stride: usize)
-                          -> Result<(Vec<i64>, Option<Vec<i64>>, Vec<i64>), Box<dyn Error>> {
+                          -> Result<(Vec<i64>, Option<Vec<i64>>, Vec<i64>), TokenizerError> {
     if num_tokens_to_remove == 0 {
         Ok((tokens_1, tokens_2, Vec::new()))
     } else {
         match tokens_2 {
             Some(mut tokens_2) => {
                 match truncation_strategy {
-                    TruncationStrategy::LongestFirst => {
+                    TruncationStrategy::LongestFirst | TruncationStrategy::LongestFirstLeft => {
                         if (tokens_1.len() + tokens_2.len()) >= num_tokens_to_remove {
-                            let mut overflow_tokens: Vec<i64> = Vec::with_capacity(num_tokens_to_remove + stride);
+                            let (mut num_tokens_to_remove_1, mut num_tokens_to_remove_2) = (0, 0);
                             for _ in 0..num_tokens_to_remove {
-                                if tokens_1.len() >= tokens_2.len() {
-                                    overflow_tokens.insert(0, tokens_1.pop().unwrap());
+                                if tokens_1.len() - num_tokens_to_remove_1 >= tokens_2.len() - num_tokens_to_remove_2 {
+                                    num_tokens_to_remove_1 += 1;
                                 } else {
-                                    tokens_2.pop();
+                                    num_tokens_to_remove_2 += 1;
                                 }
                             }
-                            let window_len = min(tokens_1.len(), stride);
-                            if window_len > 0 {
-                                let slice: &[i64] = &tokens_1[&tokens_1.len() - window_len..];
-                                overflow_tokens.splice(0..0, slice.iter().cloned());
-                            }
+                            let overflow_tokens = truncate_with_overflow(&mut tokens_1, num_tokens_to_remove_1, truncation_strategy, stride);
+                            tokens_2.drain(truncation_strategy.truncated_range(tokens_2.len(), num_tokens_to_remove_2));
                             Ok((tokens_1, Some(tokens_2), overflow_tokens))
                         } else {
-                            Err("Combined sequence length too short for requested truncation amount".into())
+                            Err(TokenizerError::TruncationError("Combined sequence length too short for requested truncation amount".to_owned()))
                         }
                     }
-                    TruncationStrategy::OnlyFirst => {
+                    TruncationStrategy::OnlyFirst | TruncationStrategy::OnlyFirstLeft | TruncationStrategy::HeadTail { .. } => {
                         if tokens_1.len() >= num_tokens_to_remove {
-                            let overflow_tokens = truncate_with_overflow(&mut tokens_1, num_tokens_to_remove, stride);
+                            let overflow_tokens = truncate_with_overflow(&mut tokens_1, num_tokens_to_remove, truncation_strategy, stride);
                             Ok((tokens_1, Some(tokens_2), overflow_tokens))
                         } else {
-                            Err("First sequence too short for first only truncation".into())
+                            Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
                         }
                     }
-                    TruncationStrategy::OnlySecond => {
+                    TruncationStrategy::OnlySecond | TruncationStrategy::OnlySecondLeft => {
                         if tokens_2.len() >= num_tokens_to_remove {
-                            let overflow_tokens = truncate_with_overflow(&mut tokens_2, num_tokens_to_remove, stride);
+                            let overflow_tokens = truncate_with_overflow(&mut tokens_2, num_tokens_to_remove, truncation_strategy, stride);
                             Ok((tokens_1, Some(tokens_2), overflow_tokens))
                         } else {
-                            Err("Second sequence too short for second only truncation".into())
+                            Err(TokenizerError::TruncationError("Second sequence too short for second only truncation".to_owned()))
                         }
                     }
-                    TruncationStrategy::DoNotTruncate => Err("Truncation needed but no truncation requested".into())
+                    TruncationStrategy::DoNotTruncate => Ok((tokens_1, Some(tokens_2), Vec::new()))
                 }
             }
             None => {
-                if tokens_1.len() >= num_tokens_to_remove {
-                    match truncation_strategy {
-                        TruncationStrategy::LongestFirst | TruncationStrategy::OnlyFirst => {
-                            let overflow_tokens = truncate_with_overflow(&mut tokens_1, num_tokens_to_remove, stride);
+                match truncation_strategy {
+                    TruncationStrategy::OnlySecond | TruncationStrategy::OnlySecondLeft =>
+                        Err(TokenizerError::TruncationError("Invalid truncation strategy for single sentence truncation".to_owned())),
+                    TruncationStrategy::DoNotTruncate => Ok((tokens_1, None, Vec::new())),
+                    _ => {
+                        if tokens_1.len() >= num_tokens_to_remove {
+                            let overflow_tokens = truncate_with_overflow(&mut tokens_1, num_tokens_to_remove, truncation_strategy, stride);
                             Ok((tokens_1, None, overflow_tokens))
+                        } else {
+                            Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
                         }
-                        TruncationStrategy::OnlySecond => Err("Invalid truncation strategy for single sentence truncation".into()),
-                        TruncationStrategy::DoNotTruncate => Err("Truncation needed but no truncation requested".into())
                     }
-                } else {
-                    Err("First sequence too short for first only truncation".into())
                 }
             }
         }
     }
 }
 
-fn truncate_with_overflow(sequence: &mut Vec<i64>, num_tokens_to_remove: usize, stride: usize) -> Vec<i64> {
-    let cutoff = sequence.len() - num_tokens_to_remove;
-    let mut overflow_tokens = sequence.split_off(cutoff);
-    let window_len = min(sequence.len(), stride);
-    if window_len > 0 {
-        let slice: &[i64] = &sequence[&sequence.len() - window_len..];
-        overflow_tokens.splice(0..0, slice.iter().cloned());
-    }
+/// Removes the tokens cut by the truncation strategy and returns them as overflowing tokens, along with up to `stride`
+/// of the kept tokens on each side of the removed tokens so that the overflow overlaps the truncated sequence.
+fn truncate_with_overflow(sequence: &mut Vec<i64>, num_tokens_to_remove: usize, truncation_strategy: &TruncationStrategy,
+                          stride: usize) -> Vec<i64> {
+    let range = truncation_strategy.truncated_range(sequence.len(), num_tokens_to_remove);
+    let overflow_start = range.start.saturating_sub(stride);
+    let overflow_end = min(range.end + stride, sequence.len());
+    let overflow_tokens = sequence[overflow_start..overflow_end].to_vec();
+    sequence.drain(range);
     overflow_tokens
 }
 
@@ -379,57 +412,167 @@ pub fn group_common_pairs(tokens: Vec<String>, bpe_ranks: &BpePairVocab) -> (Vec
 }
 
 pub fn ctrl_bpe(token: &str, bpe_ranks: &BpePairVocab) -> Vec<String> {
-    let mut sub_tokens = token.chars().map(|v| v.to_string()).collect::<Vec<String>>();
+    let (word, symbol_ends) = end_of_word_symbols(token);
+    let sub_tokens = bpe_merge(&word, &symbol_ends, bpe_ranks);
+    if sub_tokens.is_empty() {
+        return vec!(String::new());
+    }
 
-    if !sub_tokens.is_empty() {
-        sub_tokens.last_mut().unwrap().push_str("</w>");
-    };
+    let last = sub_tokens.len() - 1;
+    sub_tokens
+        .iter()
+        .enumerate()
+        .map(|(index, sub_token)| if index < last {
+            format!("{}@@", sub_token)
+        } else {
+            sub_token[..sub_token.len() - 4].to_owned()
+        })
+        .collect()
+}
 
-    let mut output = (sub_tokens, false);
-    loop {
-        output = group_common_pairs(output.0, &bpe_ranks);
-        if output.1 {
-            break;
+pub fn openai_gpt_bpe(token: &str, bpe_ranks: &BpePairVocab) -> Vec<String> {
+    let (word, symbol_ends) = end_of_word_symbols(token);
+    bpe_merge(&word, &symbol_ends, bpe_ranks).into_iter().map(|sub_token| sub_token.to_owned()).collect()
+}
+
+/// Splits a text into words with the GPT-2 regular expression, returning the byte range of each word. Whitespaces are attached to
+/// the following word, except for the last whitespace character of a run which starts it.
+pub fn split_gpt2_words(text: &str) -> Vec<(usize, usize)> {
+//    Rust regex's library does not include lookahead, decomposing the process in 2 steps
+    let mut splits: Vec<(usize, &str)> = vec!();
+    let mut i: usize = 0;
+    let mut end: usize;
+    for hit in GPT2_LOOKAHEAD_PATTERN.find_iter(text) {
+        end = hit.end() - 1 - hit.as_str().chars().last().unwrap().len_utf8();
+        splits.push((i, &text[i..end]));
+        i = end;
+    }
+    splits.push((i, &text[i..]));
+
+    let mut word_ranges: Vec<(usize, usize)> = vec!();
+    for (position, sub_word) in splits {
+        for hit in GPT2_TOKENIZATION_PATTERN.find_iter(sub_word) {
+            word_ranges.push((position + hit.start(), position + hit.end()));
         }
     }
+    word_ranges
+}
 
-    let word = output.0.join("@@ ");
-    if !word.is_empty() {
-        (&word[..word.len() - 4]).split(' ').map(|v| v.to_owned()).collect()
-    } else {
-        vec!(word)
+pub fn bpe(token: &str, bpe_ranks: &BpePairVocab) -> Vec<String> {
+    let symbol_ends = token.char_indices().map(|(position, character)| position + character.len_utf8()).collect_vec();
+    bpe_merge(token, &symbol_ends, bpe_ranks).into_iter().map(|sub_token| sub_token.to_owned()).collect()
+}
+
+/// Appends the `</w>` end of word marker to the token, returning the end positions of its characters, the marker being part of the last one.
+fn end_of_word_symbols(token: &str) -> (String, Vec<usize>) {
+    let word = format!("{}</w>", token);
+    let mut symbol_ends = token.char_indices().map(|(position, character)| position + character.len_utf8()).collect_vec();
+    if let Some(last) = symbol_ends.last_mut() {
+        *last = word.len();
     }
+    (word, symbol_ends)
 }
 
-pub fn openai_gpt_bpe(token: &str, bpe_ranks: &BpePairVocab) -> Vec<String> {
-    let mut sub_tokens = token.chars().map(|v| v.to_string()).collect::<Vec<String>>();
+/// Symbol of a word during BPE merging: a byte range of the word, linked to its neighbours.
+struct BpeSymbol {
+    id: Option<u32>,
+    start: usize,
+    end: usize,
+    previous: Option<usize>,
+    next: Option<usize>,
+}
+
+/// Candidate merge: rank, symbol ids of the pair, position of its first symbol and id of the merged symbol.
+type BpeMergeCandidate = Reverse<(i64, u32, u32, usize, u32)>;
+
+/// Merges the symbols of `word`, initially delimited by their end positions `symbol_ends`, and returns the merged sub-words.
+/// At each step, all the non-overlapping occurrences of the pair with the lowest rank are merged from left to right,
+/// until no ranked pair remains. Candidate merges are kept in a priority queue, stale candidates being skipped.
+fn bpe_merge<'a>(word: &'a str, symbol_ends: &[usize], bpe_ranks: &BpePairVocab) -> Vec<&'a str> {
+    let mut symbols: Vec<BpeSymbol> = Vec::with_capacity(symbol_ends.len());
+    let mut start = 0;
+    for (index, &end) in symbol_ends.iter().enumerate() {
+        symbols.push(BpeSymbol {
+            id: bpe_ranks.symbol_id(&word[start..end]),
+            start,
+            end,
+            previous: index.checked_sub(1),
+            next: if index + 1 < symbol_ends.len() { Some(index + 1) } else { None },
+        });
+        start = end;
+    }
+
+    let mut queue: BinaryHeap<BpeMergeCandidate> = BinaryHeap::with_capacity(symbols.len());
+    for position in 0..symbols.len() {
+        push_merge_candidate(&mut queue, &symbols, position, bpe_ranks);
+    }
 
-    if !sub_tokens.is_empty() {
-        sub_tokens.last_mut().unwrap().push_str("</w>");
-    };
+    let mut positions: Vec<usize> = vec!();
+    while let Some(Reverse((rank, id_1, id_2, position, merged_id))) = queue.pop() {
+//        Collects the occurrences of the pair first, the merges of this step must not consider the pairs they create
+        positions.clear();
+        positions.push(position);
+        while let Some(&Reverse((next_rank, next_id_1, next_id_2, next_position, _))) = queue.peek() {
+            if (next_rank, next_id_1, next_id_2) != (rank, id_1, id_2) {
+                break;
+            }
+            positions.push(next_position);
+            queue.pop();
+        }
 
-    let mut output = (sub_tokens, false);
-    loop {
-        output = group_common_pairs(output.0, &bpe_ranks);
-        if output.1 {
-            break;
+        for &position in positions.iter() {
+            let next = match symbols[position].next {
+                Some(next) if symbols[position].id == Some(id_1) && symbols[next].id == Some(id_2) => next,
+                _ => continue
+            };
+            let next_next = symbols[next].next;
+            symbols[position].id = Some(merged_id);
+            symbols[position].end = symbols[next].end;
+            symbols[position].next = next_next;
+            symbols[next].id = None;
+            if let Some(next_next) = next_next {
+                symbols[next_next].previous = Some(position);
+            }
+            if let Some(previous) = symbols[position].previous {
+                push_merge_candidate(&mut queue, &symbols, previous, bpe_ranks);
+            }
+            push_merge_candidate(&mut queue, &symbols, position, bpe_ranks);
         }
     }
-    output.0
-}
 
-pub fn bpe(token: &str, bpe_ranks: &BpePairVocab) -> Vec<String> {
-    let sub_tokens = token.chars().map(|v| v.to_string()).collect::<Vec<String>>();
+    let mut sub_tokens = Vec::with_capacity(symbols.len());
+    let mut position = if symbols.is_empty() { None } else { Some(0) };
+    while let Some(index) = position {
+        sub_tokens.push(&word[symbols[index].start..symbols[index].end]);
+        position = symbols[index].next;
+    }
+    sub_tokens
+}
 
-    let mut output = (sub_tokens, false);
-    loop {
-        output = group_common_pairs(output.0, &bpe_ranks);
-        if output.1 {
-            break;
+fn push_merge_candidate(queue: &mut BinaryHeap<BpeMergeCandidate>, symbols: &[BpeSymbol], position: usize, bpe_ranks: &BpePairVocab) {
+    if let (Some(id_1), Some(next)) = (symbols[position].id, symbols[position].next) {
+        if let Some(id_2) = symbols[next].id {
+            if let Some((rank, merged_id)) = bpe_ranks.merge(id_1, id_2) {
+                queue.push(Reverse((rank, id_1, id_2, position, merged_id)));
+            }
         }
     }
+}
 
-    output.0
+/// Assigns to each BPE sub-token the reference offsets of the characters of the word it was built from,
+/// `char_lengths` holding the number of word characters covered by each sub-token.
+pub fn bpe_sub_tokens_with_offsets(sub_tokens: Vec<String>, char_lengths: Vec<usize>, reference_offsets: &[usize]) -> Vec<Token> {
+    let mut position = 0;
+    sub_tokens
+        .into_iter()
+        .zip(char_lengths)
+        .map(|(text, length)| {
+            let begin = min(position, reference_offsets.len());
+            let end = min(position + length, reference_offsets.len());
+            position += length;
+            Token { text, reference_offsets: reference_offsets[begin..end].to_vec() }
+        })
+        .collect()
 }
 
 //==============================
@@ -441,6 +584,7 @@ mod tests {
     use std::collections::HashMap;
     use std::iter::FromIterator;
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::BertVocab;
 
     fn generate_test_vocab() -> BertVocab {
         let values: HashMap<String, i64> = [
@@ -471,7 +615,7 @@ mod tests {
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        BertVocab { values, indices, unknown_value: "[UNK]", special_values, special_indices }
+        BertVocab { values, indices, special_token_map: BertVocab::default_special_token_map(), special_values, special_indices }
     }
 
     #[test]
@@ -919,11 +1063,13 @@ mod tests {
         }
     }
 
+    type TruncationTestCase<'a> = ((usize, &'a TruncationStrategy, usize), Result<(Vec<i64>, Option<Vec<i64>>, Vec<i64>), TokenizerError>);
+
     #[test]
     fn test_truncate_single_sentence() {
 //        Given
         let test_token_ids: Vec<i64> = (0..15).collect();
-        let test_tuples: [((usize, &TruncationStrategy, usize), std::result::Result<(std::vec::Vec<i64>, std::option::Option<std::vec::Vec<i64>>, std::vec::Vec<i64>), Box<dyn Error>>);
+        let test_tuples: [TruncationTestCase;
             12] = [
 //            Baseline
             (
@@ -943,7 +1089,7 @@ mod tests {
 //            Truncate amount larger than sequence length
             (
                 (20, &TruncationStrategy::LongestFirst, 0),
-                Err("First sequence too short for first only truncation".into())
+                Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
             ),
 //            Truncate entire sequence with stride = 2
             (
@@ -978,12 +1124,12 @@ mod tests {
 //            No truncation requested, but needed
             (
                 (1, &TruncationStrategy::DoNotTruncate, 0),
-                Err("Truncation needed but no truncation requested".into())
+                Ok(((0..15).collect::<Vec<i64>>(), None::<Vec<i64>>, (15..15).collect::<Vec<i64>>()))
             ),
 //            Invalid truncation requested
             (
                 (1, &TruncationStrategy::OnlySecond, 0),
-                Err("Invalid truncation strategy for single sentence truncation".into())
+                Err(TokenizerError::TruncationError("Invalid truncation strategy for single sentence truncation".to_owned()))
             ),
         ];
 
@@ -991,7 +1137,7 @@ mod tests {
             let test_results = truncate_sequences(test_token_ids.clone(), None, parameters.0, parameters.1, parameters.2);
             match test_results {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
-                Err(e) => assert_eq!(e.to_string(), (**expected_outputs.as_ref().err().unwrap()).to_string())
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
             }
         }
     }
@@ -1001,7 +1147,7 @@ mod tests {
 //        Given
         let test_token_ids: Vec<i64> = (0..15).collect();
         let test_pair_token_ids: Vec<i64> = (42..51).collect();
-        let test_tuples: [((usize, &TruncationStrategy, usize), std::result::Result<(std::vec::Vec<i64>, std::option::Option<std::vec::Vec<i64>>, std::vec::Vec<i64>), Box<dyn Error>>);
+        let test_tuples: [TruncationTestCase;
             10] = [
 //            Baseline
             (
@@ -1036,7 +1182,7 @@ mod tests {
 //            Request truncation amount greater than combined length
             (
                 (15 + 9 + 1, &TruncationStrategy::LongestFirst, 2),
-                Err("Combined sequence length too short for requested truncation amount".into())
+                Err(TokenizerError::TruncationError("Combined sequence length too short for requested truncation amount".to_owned()))
             ),
 //            No truncation
             (
@@ -1051,7 +1197,7 @@ mod tests {
 //            No truncation requested, but needed
             (
                 (1, &TruncationStrategy::DoNotTruncate, 0),
-                Err("Truncation needed but no truncation requested".into())
+                Ok(((0..15).collect::<Vec<i64>>(), Some((42..51).collect::<Vec<i64>>()), (15..15).collect::<Vec<i64>>()))
             ),
         ];
 
@@ -1059,7 +1205,7 @@ mod tests {
             let test_results = truncate_sequences(test_token_ids.clone(), Some(test_pair_token_ids.clone()), parameters.0, parameters.1, parameters.2);
             match test_results {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
-                Err(e) => assert_eq!(e.to_string(), (**expected_outputs.as_ref().err().unwrap()).to_string())
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
             }
         }
     }
@@ -1069,7 +1215,7 @@ mod tests {
 //        Given
         let test_token_ids: Vec<i64> = (0..15).collect();
         let test_pair_token_ids: Vec<i64> = (42..51).collect();
-        let test_tuples: [((usize, &TruncationStrategy, usize), std::result::Result<(std::vec::Vec<i64>, std::option::Option<std::vec::Vec<i64>>, std::vec::Vec<i64>), Box<dyn Error>>);
+        let test_tuples: [TruncationTestCase;
             5] = [
 //            Baseline
             (
@@ -1089,7 +1235,7 @@ mod tests {
 //            Request truncation amount greater than sentence 1
             (
                 (16, &TruncationStrategy::OnlyFirst, 2),
-                Err("First sequence too short for first only truncation".into())
+                Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
             ),
 //            No truncation
             (
@@ -1102,7 +1248,7 @@ mod tests {
             let test_results = truncate_sequences(test_token_ids.clone(), Some(test_pair_token_ids.clone()), parameters.0, parameters.1, parameters.2);
             match test_results {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
-                Err(e) => assert_eq!(e.to_string(), (**expected_outputs.as_ref().err().unwrap()).to_string())
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
             }
         }
     }
@@ -1112,7 +1258,7 @@ mod tests {
 //        Given
         let test_token_ids: Vec<i64> = (0..15).collect();
         let test_pair_token_ids: Vec<i64> = (42..51).collect();
-        let test_tuples: [((usize, &TruncationStrategy, usize), std::result::Result<(std::vec::Vec<i64>, std::option::Option<std::vec::Vec<i64>>, std::vec::Vec<i64>), Box<dyn Error>>);
+        let test_tuples: [TruncationTestCase;
             5] = [
 //            Baseline
             (
@@ -1132,7 +1278,7 @@ mod tests {
 //            Request truncation amount greater than sentence 1
             (
                 (10, &TruncationStrategy::OnlySecond, 2),
-                Err("Second sequence too short for second only truncation".into())
+                Err(TokenizerError::TruncationError("Second sequence too short for second only truncation".to_owned()))
             ),
 //            No truncation
             (
@@ -1145,7 +1291,225 @@ mod tests {
             let test_results = truncate_sequences(test_token_ids.clone(), Some(test_pair_token_ids.clone()), parameters.0, parameters.1, parameters.2);
             match test_results {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
-                Err(e) => assert_eq!(e.to_string(), (**expected_outputs.as_ref().err().unwrap()).to_string())
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncate_single_sentence_left() {
+//        Given
+        let test_token_ids: Vec<i64> = (0..15).collect();
+        let test_tuples: [TruncationTestCase;
+            7] = [
+//            Baseline
+            (
+                (5, &TruncationStrategy::LongestFirstLeft, 0),
+                Ok(((5..15).collect::<Vec<i64>>(), None::<Vec<i64>>, (0..5).collect::<Vec<i64>>()))
+            ),
+//            With stride = 2
+            (
+                (5, &TruncationStrategy::LongestFirstLeft, 2),
+                Ok(((5..15).collect::<Vec<i64>>(), None::<Vec<i64>>, (0..7).collect::<Vec<i64>>()))
+            ),
+//            Truncate entire sequence with stride = 2
+            (
+                (15, &TruncationStrategy::LongestFirstLeft, 2),
+                Ok(((0..0).collect::<Vec<i64>>(), None::<Vec<i64>>, (0..15).collect::<Vec<i64>>()))
+            ),
+//            stride larger than remaining elements
+            (
+                (10, &TruncationStrategy::OnlyFirstLeft, 7),
+                Ok(((10..15).collect::<Vec<i64>>(), None::<Vec<i64>>, (0..15).collect::<Vec<i64>>()))
+            ),
+//            Truncate amount larger than sequence length
+            (
+                (20, &TruncationStrategy::LongestFirstLeft, 0),
+                Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
+            ),
+//            No truncation
+            (
+                (0, &TruncationStrategy::OnlyFirstLeft, 2),
+                Ok(((0..15).collect::<Vec<i64>>(), None::<Vec<i64>>, (15..15).collect::<Vec<i64>>()))
+            ),
+//            Invalid truncation strategy
+            (
+                (1, &TruncationStrategy::OnlySecondLeft, 0),
+                Err(TokenizerError::TruncationError("Invalid truncation strategy for single sentence truncation".to_owned()))
+            ),
+        ];
+
+        for (parameters, expected_outputs) in &test_tuples {
+            let test_results = truncate_sequences(test_token_ids.clone(), None, parameters.0, parameters.1, parameters.2);
+            match test_results {
+                Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncate_single_sentence_head_tail() {
+//        Given
+        let test_token_ids: Vec<i64> = (0..15).collect();
+        let test_tuples: [TruncationTestCase;
+            6] = [
+//            Baseline
+            (
+                (5, &TruncationStrategy::HeadTail { head: 4 }, 0),
+                Ok((vec!(0, 1, 2, 3, 9, 10, 11, 12, 13, 14), None::<Vec<i64>>, (4..9).collect::<Vec<i64>>()))
+            ),
+//            With stride = 2, overlapping both the head and the tail
+            (
+                (5, &TruncationStrategy::HeadTail { head: 4 }, 2),
+                Ok((vec!(0, 1, 2, 3, 9, 10, 11, 12, 13, 14), None::<Vec<i64>>, (2..11).collect::<Vec<i64>>()))
+            ),
+//            Empty head
+            (
+                (5, &TruncationStrategy::HeadTail { head: 0 }, 0),
+                Ok(((5..15).collect::<Vec<i64>>(), None::<Vec<i64>>, (0..5).collect::<Vec<i64>>()))
+            ),
+//            Head longer than the truncated sequence
+            (
+                (5, &TruncationStrategy::HeadTail { head: 12 }, 1),
+                Ok(((0..10).collect::<Vec<i64>>(), None::<Vec<i64>>, (9..15).collect::<Vec<i64>>()))
+            ),
+//            Truncate entire sequence with stride = 2
+            (
+                (15, &TruncationStrategy::HeadTail { head: 4 }, 2),
+                Ok(((0..0).collect::<Vec<i64>>(), None::<Vec<i64>>, (0..15).collect::<Vec<i64>>()))
+            ),
+//            Truncate amount larger than sequence length
+            (
+                (20, &TruncationStrategy::HeadTail { head: 4 }, 0),
+                Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
+            ),
+        ];
+
+        for (parameters, expected_outputs) in &test_tuples {
+            let test_results = truncate_sequences(test_token_ids.clone(), None, parameters.0, parameters.1, parameters.2);
+            match test_results {
+                Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncate_sentence_pair_longest_first_left() {
+//        Given
+        let test_token_ids: Vec<i64> = (0..15).collect();
+        let test_pair_token_ids: Vec<i64> = (42..51).collect();
+        let test_tuples: [TruncationTestCase;
+            5] = [
+//            Baseline
+            (
+                (5, &TruncationStrategy::LongestFirstLeft, 0),
+                Ok(((5..15).collect::<Vec<i64>>(), Some((42..51).collect::<Vec<i64>>()), (0..5).collect::<Vec<i64>>()))
+            ),
+//            With stride = 2
+            (
+                (5, &TruncationStrategy::LongestFirstLeft, 2),
+                Ok(((5..15).collect::<Vec<i64>>(), Some((42..51).collect::<Vec<i64>>()), (0..7).collect::<Vec<i64>>()))
+            ),
+//            Both sentences affected
+            (
+                (10, &TruncationStrategy::LongestFirstLeft, 2),
+                Ok(((8..15).collect::<Vec<i64>>(), Some((44..51).collect::<Vec<i64>>()), (0..10).collect::<Vec<i64>>()))
+            ),
+//            Truncate entire sentence 1
+            (
+                (15 + 8, &TruncationStrategy::LongestFirstLeft, 2),
+                Ok(((0..0).collect::<Vec<i64>>(), Some((50..51).collect::<Vec<i64>>()), (0..15).collect::<Vec<i64>>()))
+            ),
+//            Request truncation amount greater than combined length
+            (
+                (15 + 9 + 1, &TruncationStrategy::LongestFirstLeft, 2),
+                Err(TokenizerError::TruncationError("Combined sequence length too short for requested truncation amount".to_owned()))
+            ),
+        ];
+
+        for (parameters, expected_outputs) in &test_tuples {
+            let test_results = truncate_sequences(test_token_ids.clone(), Some(test_pair_token_ids.clone()), parameters.0, parameters.1, parameters.2);
+            match test_results {
+                Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncate_sentence_pair_first_only_left_and_head_tail() {
+//        Given
+        let test_token_ids: Vec<i64> = (0..15).collect();
+        let test_pair_token_ids: Vec<i64> = (42..51).collect();
+        let test_tuples: [TruncationTestCase;
+            4] = [
+//            Left truncation with stride = 2
+            (
+                (5, &TruncationStrategy::OnlyFirstLeft, 2),
+                Ok(((5..15).collect::<Vec<i64>>(), Some((42..51).collect::<Vec<i64>>()), (0..7).collect::<Vec<i64>>()))
+            ),
+//            Head and tail with stride = 1
+            (
+                (5, &TruncationStrategy::HeadTail { head: 3 }, 1),
+                Ok((vec!(0, 1, 2, 8, 9, 10, 11, 12, 13, 14), Some((42..51).collect::<Vec<i64>>()), (2..9).collect::<Vec<i64>>()))
+            ),
+//            Request truncation amount greater than sentence 1
+            (
+                (16, &TruncationStrategy::OnlyFirstLeft, 2),
+                Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
+            ),
+//            Head and tail truncation amount greater than sentence 1
+            (
+                (16, &TruncationStrategy::HeadTail { head: 3 }, 2),
+                Err(TokenizerError::TruncationError("First sequence too short for first only truncation".to_owned()))
+            ),
+        ];
+
+        for (parameters, expected_outputs) in &test_tuples {
+            let test_results = truncate_sequences(test_token_ids.clone(), Some(test_pair_token_ids.clone()), parameters.0, parameters.1, parameters.2);
+            match test_results {
+                Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncate_sentence_pair_second_only_left() {
+//        Given
+        let test_token_ids: Vec<i64> = (0..15).collect();
+        let test_pair_token_ids: Vec<i64> = (42..51).collect();
+        let test_tuples: [TruncationTestCase;
+            4] = [
+//            Baseline
+            (
+                (5, &TruncationStrategy::OnlySecondLeft, 0),
+                Ok(((0..15).collect::<Vec<i64>>(), Some((47..51).collect::<Vec<i64>>()), (42..47).collect::<Vec<i64>>()))
+            ),
+//            With stride = 2
+            (
+                (5, &TruncationStrategy::OnlySecondLeft, 2),
+                Ok(((0..15).collect::<Vec<i64>>(), Some((47..51).collect::<Vec<i64>>()), (42..49).collect::<Vec<i64>>()))
+            ),
+//            Truncate entire sentence 2
+            (
+                (9, &TruncationStrategy::OnlySecondLeft, 2),
+                Ok(((0..15).collect::<Vec<i64>>(), Some((51..51).collect::<Vec<i64>>()), (42..51).collect::<Vec<i64>>()))
+            ),
+//            Request truncation amount greater than sentence 2
+            (
+                (10, &TruncationStrategy::OnlySecondLeft, 2),
+                Err(TokenizerError::TruncationError("Second sequence too short for second only truncation".to_owned()))
+            ),
+        ];
+
+        for (parameters, expected_outputs) in &test_tuples {
+            let test_results = truncate_sequences(test_token_ids.clone(), Some(test_pair_token_ids.clone()), parameters.0, parameters.1, parameters.2);
+            match test_results {
+                Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
+                Err(e) => assert_eq!(e, *expected_outputs.as_ref().err().unwrap())
             }
         }
     }
@@ -1217,7 +1581,7 @@ mod tests {
             (("l".to_owned(), "l".to_owned()), 5),
         ].iter().cloned().collect();
 
-        BpePairVocab { values }
+        BpePairVocab::from_values(values)
     }
 
     #[test]
@@ -1295,4 +1659,125 @@ mod tests {
             assert_eq!(ctrl_bpe(input.clone(), &bpe_pairs), *expected_output);
         }
     }
-}
\ No newline at end of file
+
+    /// Reference implementation of the BPE merges, applying `group_common_pairs` until no ranked pair remains.
+    fn reference_merges(sub_tokens: Vec<String>, bpe_ranks: &BpePairVocab) -> Vec<String> {
+        let mut output = (sub_tokens, false);
+        while !output.1 {
+            output = group_common_pairs(output.0, bpe_ranks);
+        }
+        output.0
+    }
+
+    fn reference_end_of_word_merges(token: &str, bpe_ranks: &BpePairVocab) -> Vec<String> {
+        let mut sub_tokens = token.chars().map(|v| v.to_string()).collect::<Vec<String>>();
+        if let Some(last) = sub_tokens.last_mut() {
+            last.push_str("</w>");
+        }
+        reference_merges(sub_tokens, bpe_ranks)
+    }
+
+    /// Xorshift pseudo-random generator, seeded for reproducible tests.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    /// Generates merges of random pairs of characters and previously merged symbols, with shuffled ranks.
+    fn generate_random_merges(rng: &mut XorShift, alphabet: &[&str], num_merges: usize) -> BpePairVocab {
+        let mut symbols: Vec<String> = alphabet.iter().map(|v| v.to_string()).collect();
+        symbols.extend(alphabet.iter().map(|v| format!("{}</w>", v)));
+        let mut ranks: Vec<i64> = (0..num_merges as i64).collect();
+        for index in (1..ranks.len()).rev() {
+            ranks.swap(index, rng.below(index + 1));
+        }
+        let mut values = HashMap::new();
+        for rank in ranks {
+            let byte_1 = symbols[rng.below(symbols.len())].clone();
+            let byte_2 = symbols[rng.below(symbols.len())].clone();
+            if !byte_1.ends_with("</w>") && !values.contains_key(&(byte_1.clone(), byte_2.clone())) {
+                symbols.push(format!("{}{}", byte_1, byte_2));
+                values.insert((byte_1, byte_2), rank);
+            }
+        }
+        BpePairVocab::from_values(values)
+    }
+
+    #[test]
+    fn test_bpe_variants() {
+//        Given
+        let bpe_pairs = generate_bpe_pair_vocab();
+
+//        When & Then
+        assert_eq!(bpe("hellllo", &bpe_pairs), vec!("h", "ell", "ll", "o"));
+        assert_eq!(bpe("", &bpe_pairs), Vec::<String>::new());
+        assert_eq!(openai_gpt_bpe("hellllo", &bpe_pairs), vec!("h", "ell", "ll", "o</w>"));
+        assert_eq!(openai_gpt_bpe("", &bpe_pairs), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_bpe_merges_all_occurrences_before_new_pairs() {
+//        Given
+        let values: HashMap<(String, String), i64> = [
+            (("ab".to_owned(), "a".to_owned()), 0),
+            (("a".to_owned(), "b".to_owned()), 1),
+            (("ab".to_owned(), "ab".to_owned()), 2),
+        ].iter().cloned().collect();
+        let bpe_pairs = BpePairVocab::from_values(values);
+
+//        When & Then
+        assert_eq!(bpe("abab", &bpe_pairs), vec!("abab"));
+        assert_eq!(bpe("aaa", &bpe_pairs), vec!("a", "a", "a"));
+    }
+
+    #[test]
+    fn test_bpe_random_equivalence() {
+//        Given
+        let mut rng = XorShift(0x2545_F491_4F6C_DD1D);
+        let alphabet = ["a", "b", "c", "d", "é", "中"];
+
+//        When & Then
+        for _ in 0..100 {
+            let num_merges = 1 + rng.below(80);
+            let bpe_pairs = generate_random_merges(&mut rng, &alphabet, num_merges);
+            for _ in 0..100 {
+                let length = rng.below(24);
+                let word: String = (0..length).map(|_| alphabet[rng.below(alphabet.len())]).collect();
+                let characters = word.chars().map(|v| v.to_string()).collect();
+
+                assert_eq!(bpe(&word, &bpe_pairs), reference_merges(characters, &bpe_pairs), "bpe({})", word);
+                assert_eq!(openai_gpt_bpe(&word, &bpe_pairs), reference_end_of_word_merges(&word, &bpe_pairs), "openai_gpt_bpe({})", word);
+                let expected_ctrl_output = match reference_end_of_word_merges(&word, &bpe_pairs).join("@@ ") {
+                    joined if joined.is_empty() => vec!(joined),
+                    joined => joined[..joined.len() - 4].split(' ').map(|v| v.to_owned()).collect()
+                };
+                assert_eq!(ctrl_bpe(&word, &bpe_pairs), expected_ctrl_output, "ctrl_bpe({})", word);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_gpt2_words() {
+//        Given
+        let test_tuples = [
+            ("", vec!()),
+            ("hello world", vec!("hello", " world")),
+            ("I'm here  now!", vec!("I", "'m", " here", " ", " now", "!")),
+            ("  the earth 42", vec!(" ", " the", " earth", " 42")),
+            ("wait...  ", vec!("wait", "...", "  ")),
+            ("Ég  中文", vec!("Ég", " ", " 中文")),
+        ];
+
+//        When & Then
+        for (text, expected) in test_tuples.iter() {
+            let words: Vec<&str> = split_gpt2_words(text).into_iter().map(|(start, end)| &text[start..end]).collect();
+            assert_eq!(&words, expected, "split_gpt2_words({:?})", text);
+        }
+    }
+}
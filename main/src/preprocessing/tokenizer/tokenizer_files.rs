@@ -0,0 +1,101 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use serde_json::{json, Value};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::vocab::base_vocab::{open_file, create_file, SpecialTokenMap, Vocab};
+use crate::preprocessing::tokenizer::special_token_matcher::AddedToken;
+
+pub const VOCAB_TXT_FILE: &str = "vocab.txt";
+pub const VOCAB_JSON_FILE: &str = "vocab.json";
+pub const MERGES_FILE: &str = "merges.txt";
+pub const SENTENCE_PIECE_MODEL_FILE: &str = "spiece.model";
+pub const TOKENIZER_CONFIG_FILE: &str = "tokenizer_config.json";
+pub const SPECIAL_TOKENS_MAP_FILE: &str = "special_tokens_map.json";
+pub const ADDED_TOKENS_FILE: &str = "added_tokens.json";
+
+pub(crate) fn create_directory(directory: &str) -> Result<(), TokenizerError> {
+    fs::create_dir_all(directory).map_err(|e| TokenizerError::IOError(format!("could not create {}: {}", directory, e)))
+}
+
+pub(crate) fn file_path(directory: &str, file_name: &str) -> String {
+    Path::new(directory).join(file_name).to_string_lossy().into_owned()
+}
+
+/// Writes the configuration of a tokenizer. `strip_accents` is only set for the tokenizers where accent stripping is optional.
+pub(crate) fn write_tokenizer_config(directory: &str, lower_case: bool, strip_accents: Option<bool>) -> Result<(), TokenizerError> {
+    let f = create_file(&file_path(directory, TOKENIZER_CONFIG_FILE))?;
+    let mut bw = BufWriter::new(f);
+    let config = match strip_accents {
+        Some(strip_accents) => json!({ "lower_case": lower_case, "strip_accents": strip_accents }),
+        None => json!({ "lower_case": lower_case })
+    };
+    serde_json::to_writer_pretty(&mut bw, &config)?;
+    bw.flush()?;
+    Ok(())
+}
+
+/// Reads the configuration of a saved tokenizer and returns its lower casing and accent stripping settings.
+pub(crate) fn read_tokenizer_config(directory: &str) -> Result<(bool, Option<bool>), TokenizerError> {
+    let f = open_file(&file_path(directory, TOKENIZER_CONFIG_FILE))?;
+    let br = BufReader::new(f);
+    let config: Value = serde_json::from_reader(br)?;
+    let lower_case = config["lower_case"].as_bool()
+        .ok_or_else(|| TokenizerError::VocabularyParsingError(format!("{} must contain a boolean lower_case", TOKENIZER_CONFIG_FILE)))?;
+    Ok((lower_case, config["strip_accents"].as_bool()))
+}
+
+/// Reads the special token map of a saved tokenizer. Directories saved without one get the default map of the vocabulary.
+pub(crate) fn read_special_token_map<T: Vocab>(directory: &str) -> Result<SpecialTokenMap, TokenizerError> {
+    let path = file_path(directory, SPECIAL_TOKENS_MAP_FILE);
+    if Path::new(&path).exists() {
+        SpecialTokenMap::from_file(&path)
+    } else {
+        Ok(T::default_special_token_map())
+    }
+}
+
+/// Writes the tokens added to a tokenizer after its creation, as a list of `content` and `special` entries in the order they were added.
+pub(crate) fn write_added_tokens(directory: &str, added_tokens: &[AddedToken]) -> Result<(), TokenizerError> {
+    let f = create_file(&file_path(directory, ADDED_TOKENS_FILE))?;
+    let mut bw = BufWriter::new(f);
+    let added_tokens: Vec<Value> = added_tokens
+        .iter()
+        .map(|added_token| json!({ "content": added_token.content, "special": added_token.special }))
+        .collect();
+    serde_json::to_writer_pretty(&mut bw, &added_tokens)?;
+    bw.flush()?;
+    Ok(())
+}
+
+/// Reads the tokens added to a saved tokenizer. Directories saved without them have no added tokens.
+pub(crate) fn read_added_tokens(directory: &str) -> Result<Vec<AddedToken>, TokenizerError> {
+    let path = file_path(directory, ADDED_TOKENS_FILE);
+    if !Path::new(&path).exists() {
+        return Ok(vec!());
+    }
+    let f = open_file(&path)?;
+    let br = BufReader::new(f);
+    let added_tokens: Value = serde_json::from_reader(br)?;
+    let added_tokens = added_tokens.as_array()
+        .ok_or_else(|| TokenizerError::VocabularyParsingError(format!("{} must contain an array", ADDED_TOKENS_FILE)))?;
+    added_tokens
+        .iter()
+        .map(|added_token| match (added_token["content"].as_str(), added_token["special"].as_bool()) {
+            (Some(content), Some(special)) => Ok(AddedToken { content: content.to_owned(), special }),
+            _ => Err(TokenizerError::VocabularyParsingError(
+                format!("each entry of {} must contain a string content and a boolean special", ADDED_TOKENS_FILE)))
+        })
+        .collect()
+}
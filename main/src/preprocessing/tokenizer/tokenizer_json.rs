@@ -0,0 +1,485 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::sync::Arc;
+use serde_json::Value;
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::vocab::base_vocab::{Vocab, open_file};
+use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
+use crate::{BertTokenizer, Gpt2Tokenizer, RobertaTokenizer, BertVocab, Gpt2Vocab, RobertaVocab};
+
+/// Tokenizer built from a HuggingFace `tokenizer.json` file, with the variant matching the serialized pipeline.
+///
+/// Only the pipelines matching the tokenizers of this crate are supported: a WordPiece model with the BERT
+/// normalizer, pre-tokenizer and post-processor, and a byte-level BPE model with the GPT-2 or RoBERTa post-processor.
+/// Any other component or option is rejected with a `TokenizerError::UnsupportedComponent`.
+/// The top-level `truncation` and `padding` settings are ignored: they are passed to each `encode` call instead.
+pub enum PretrainedTokenizer {
+    Bert(BertTokenizer),
+    Gpt2(Gpt2Tokenizer),
+    Roberta(RobertaTokenizer),
+}
+
+impl PretrainedTokenizer {
+    pub fn from_tokenizer_json(path: &str) -> Result<PretrainedTokenizer, TokenizerError> {
+        let tokenizer_json = read_tokenizer_json(path)?;
+        match component_type(&tokenizer_json, "model")? {
+            Some("WordPiece") => Ok(PretrainedTokenizer::Bert(bert_tokenizer_from_json(&tokenizer_json)?)),
+            Some("BPE") => match component_type(&tokenizer_json, "post_processor")? {
+                Some("RobertaProcessing") => Ok(PretrainedTokenizer::Roberta(roberta_tokenizer_from_json(&tokenizer_json)?)),
+                _ => Ok(PretrainedTokenizer::Gpt2(gpt2_tokenizer_from_json(&tokenizer_json)?))
+            },
+            Some(model_type) => Err(unsupported("model", model_type)),
+            None => Err(TokenizerError::VocabularyParsingError("the tokenizer.json file does not contain a model".to_owned()))
+        }
+    }
+}
+
+pub(crate) fn read_tokenizer_json(path: &str) -> Result<Value, TokenizerError> {
+    let f = open_file(path)?;
+    let br = BufReader::new(f);
+    Ok(serde_json::from_reader(br)?)
+}
+
+pub(crate) fn bert_tokenizer_from_json(tokenizer_json: &Value) -> Result<BertTokenizer, TokenizerError> {
+    let model = &tokenizer_json["model"];
+    expect_type(tokenizer_json, "model", &["WordPiece"])?;
+    expect_option(model, "model", "unk_token", &Value::from(BertVocab::default_special_token_map().unk_token))?;
+    expect_option(model, "model", "continuing_subword_prefix", &Value::from("##"))?;
+    expect_option(model, "model", "max_input_chars_per_word", &Value::from(100))?;
+
+    expect_type(tokenizer_json, "normalizer", &["BertNormalizer"])?;
+    let normalizer = &tokenizer_json["normalizer"];
+    expect_option(normalizer, "normalizer", "clean_text", &Value::Bool(true))?;
+    expect_option(normalizer, "normalizer", "handle_chinese_chars", &Value::Bool(true))?;
+    let lower_case = get_bool(normalizer, "normalizer", "lowercase", true)?;
+//        Accents are always stripped by the base tokenizer, an unset value defaults to the lower casing setting
+    if !get_bool(normalizer, "normalizer", "strip_accents", lower_case)? {
+        return Err(unsupported("normalizer", "BertNormalizer without accent stripping"));
+    }
+
+    expect_type(tokenizer_json, "pre_tokenizer", &["BertPreTokenizer"])?;
+    match component_type(tokenizer_json, "post_processor")? {
+        Some("BertProcessing") => {
+            let post_processor = &tokenizer_json["post_processor"];
+            expect_special_token(post_processor, "cls", "[CLS]")?;
+            expect_special_token(post_processor, "sep", "[SEP]")?;
+        }
+        Some("TemplateProcessing") => expect_template(&tokenizer_json["post_processor"],
+                                                      "[CLS]:0 $A:0 [SEP]:0",
+                                                      "[CLS]:0 $A:0 [SEP]:0 $B:1 [SEP]:1")?,
+        Some(post_processor_type) => return Err(unsupported("post_processor", post_processor_type)),
+        None => return Err(unsupported("post_processor", "null"))
+    }
+    expect_optional_type(tokenizer_json, "decoder", &["WordPiece"])?;
+
+    let vocab = BertVocab::from_values(read_vocab(model)?)?;
+    let added_tokens = read_added_tokens(tokenizer_json, &vocab)?;
+    let mut tokenizer = BertTokenizer::from_existing_vocab(Arc::new(vocab), lower_case);
+    register_added_tokens(&added_tokens, |token, special| {
+        if special { tokenizer.add_special_tokens(&[token]) } else { tokenizer.add_tokens(&[token]) }
+    })?;
+    Ok(tokenizer)
+}
+
+pub(crate) fn gpt2_tokenizer_from_json(tokenizer_json: &Value) -> Result<Gpt2Tokenizer, TokenizerError> {
+    let lower_case = check_byte_level_bpe(tokenizer_json)?;
+    expect_optional_type(tokenizer_json, "post_processor", &["ByteLevel"])?;
+
+    let vocab = Gpt2Vocab::from_values(read_vocab(&tokenizer_json["model"])?)?;
+    let merges = read_merges(&tokenizer_json["model"])?;
+    let added_tokens = read_added_tokens(tokenizer_json, &vocab)?;
+    let mut tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), lower_case);
+    register_added_tokens(&added_tokens, |token, special| {
+        if special { tokenizer.add_special_tokens(&[token]) } else { tokenizer.add_tokens(&[token]) }
+    })?;
+    Ok(tokenizer)
+}
+
+pub(crate) fn roberta_tokenizer_from_json(tokenizer_json: &Value) -> Result<RobertaTokenizer, TokenizerError> {
+    let lower_case = check_byte_level_bpe(tokenizer_json)?;
+    expect_type(tokenizer_json, "post_processor", &["RobertaProcessing"])?;
+    let post_processor = &tokenizer_json["post_processor"];
+    expect_special_token(post_processor, "cls", "<s>")?;
+    expect_special_token(post_processor, "sep", "</s>")?;
+
+    let vocab = RobertaVocab::from_values(read_vocab(&tokenizer_json["model"])?)?;
+    let merges = read_merges(&tokenizer_json["model"])?;
+    let added_tokens = read_added_tokens(tokenizer_json, &vocab)?;
+    let mut tokenizer = RobertaTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), lower_case);
+    register_added_tokens(&added_tokens, |token, special| {
+        if special { tokenizer.add_special_tokens(&[token]) } else { tokenizer.add_tokens(&[token]) }
+    })?;
+    Ok(tokenizer)
+}
+
+/// Validates the model, normalizer, pre-tokenizer and decoder shared by the byte-level BPE tokenizers
+/// and returns the lower casing setting.
+fn check_byte_level_bpe(tokenizer_json: &Value) -> Result<bool, TokenizerError> {
+    let model = &tokenizer_json["model"];
+    expect_type(tokenizer_json, "model", &["BPE"])?;
+    expect_option(model, "model", "dropout", &Value::Null)?;
+    expect_option(model, "model", "unk_token", &Value::Null)?;
+    for option in &["continuing_subword_prefix", "end_of_word_suffix"] {
+        if !matches!(model.get(*option), None | Some(Value::Null)) && model[*option] != "" {
+            return Err(unsupported("model", &format!("BPE with {} {}", option, model[*option])));
+        }
+    }
+
+    let lower_case = match component_type(tokenizer_json, "normalizer")? {
+        None => false,
+        Some("Lowercase") => true,
+        Some(normalizer_type) => return Err(unsupported("normalizer", normalizer_type))
+    };
+
+    expect_type(tokenizer_json, "pre_tokenizer", &["ByteLevel"])?;
+    let pre_tokenizer = &tokenizer_json["pre_tokenizer"];
+    expect_option(pre_tokenizer, "pre_tokenizer", "add_prefix_space", &Value::Bool(false))?;
+    expect_option(pre_tokenizer, "pre_tokenizer", "use_regex", &Value::Bool(true))?;
+    expect_optional_type(tokenizer_json, "decoder", &["ByteLevel"])?;
+    Ok(lower_case)
+}
+
+fn read_vocab(model: &Value) -> Result<HashMap<String, i64>, TokenizerError> {
+    let values = model["vocab"].as_object()
+        .ok_or_else(|| TokenizerError::VocabularyParsingError("the model vocab must be an object".to_owned()))?;
+    values
+        .iter()
+        .map(|(token, id)| match id.as_i64() {
+            Some(id) => Ok((token.clone(), id)),
+            None => Err(TokenizerError::VocabularyParsingError(format!("invalid id {} for token {}", id, token)))
+        })
+        .collect()
+}
+
+fn read_merges(model: &Value) -> Result<BpePairVocab, TokenizerError> {
+    let merges = model["merges"].as_array()
+        .ok_or_else(|| TokenizerError::VocabularyParsingError("the model merges must be an array".to_owned()))?;
+    let mut values = HashMap::new();
+    for (index, merge) in merges.iter().enumerate() {
+        let pair = match merge {
+            Value::String(merge) => merge.split_once(' ').map(|(byte_1, byte_2)| (byte_1.to_owned(), byte_2.to_owned())),
+            Value::Array(pair) if pair.len() == 2 => match (pair[0].as_str(), pair[1].as_str()) {
+                (Some(byte_1), Some(byte_2)) => Some((byte_1.to_owned(), byte_2.to_owned())),
+                _ => None
+            },
+            _ => None
+        };
+        match pair {
+            Some(pair) => values.insert(pair, index as i64),
+            None => return Err(TokenizerError::VocabularyParsingError(format!("invalid merge {}", merge)))
+        };
+    }
+    Ok(BpePairVocab::from_values(values))
+}
+
+struct JsonAddedToken {
+    content: String,
+    id: i64,
+    special: bool,
+}
+
+/// Reads the added tokens, skipping the ones that already are special values of the vocabulary with the same id.
+fn read_added_tokens<T: Vocab>(tokenizer_json: &Value, vocab: &T) -> Result<Vec<JsonAddedToken>, TokenizerError> {
+    let added_tokens = match &tokenizer_json["added_tokens"] {
+        Value::Null => return Ok(vec!()),
+        Value::Array(added_tokens) => added_tokens,
+        _ => return Err(TokenizerError::VocabularyParsingError("added_tokens must be an array".to_owned()))
+    };
+    let mut json_added_tokens = vec!();
+    for added_token in added_tokens {
+        let content = added_token["content"].as_str();
+        let id = added_token["id"].as_i64();
+        let special = added_token["special"].as_bool().unwrap_or(false);
+        match (content, id) {
+            (Some(content), Some(id)) if !content.is_empty() => if vocab.special_values().get(content) != Some(&id) {
+                json_added_tokens.push(JsonAddedToken { content: content.to_owned(), id, special });
+            },
+            _ => return Err(TokenizerError::VocabularyParsingError(format!("invalid added token {}", added_token)))
+        }
+    }
+    Ok(json_added_tokens)
+}
+
+/// Registers the added tokens in their order with `add_token`, which returns the ids assigned by the tokenizer.
+/// Fails if a token does not get the id of the `tokenizer.json` file.
+fn register_added_tokens<F>(added_tokens: &[JsonAddedToken], mut add_token: F) -> Result<(), TokenizerError>
+    where F: FnMut(&str, bool) -> Vec<i64> {
+    for added_token in added_tokens {
+        if add_token(&added_token.content, added_token.special) != [added_token.id] {
+            return Err(unsupported("added_tokens", &format!("{} (id {})", added_token.content, added_token.id)));
+        }
+    }
+    Ok(())
+}
+
+fn unsupported(component: &str, value: &str) -> TokenizerError {
+    TokenizerError::UnsupportedComponent(format!("{} {}", component, value))
+}
+
+/// Returns the type of a component, or None if the component is null or missing.
+fn component_type<'a>(tokenizer_json: &'a Value, component: &str) -> Result<Option<&'a str>, TokenizerError> {
+    match &tokenizer_json[component] {
+        Value::Null => Ok(None),
+        value => match value["type"].as_str() {
+            Some(component_type) => Ok(Some(component_type)),
+//            The type of the model is optional in older serializations
+            None if component == "model" && value.get("merges").is_some() => Ok(Some("BPE")),
+            None if component == "model" && value.get("continuing_subword_prefix").is_some() => Ok(Some("WordPiece")),
+            None => Err(TokenizerError::VocabularyParsingError(format!("the {} has no type", component)))
+        }
+    }
+}
+
+fn expect_type(tokenizer_json: &Value, component: &str, expected_types: &[&str]) -> Result<(), TokenizerError> {
+    match component_type(tokenizer_json, component)? {
+        Some(component_type) if expected_types.contains(&component_type) => Ok(()),
+        Some(component_type) => Err(unsupported(component, component_type)),
+        None => Err(unsupported(component, "null"))
+    }
+}
+
+fn expect_optional_type(tokenizer_json: &Value, component: &str, expected_types: &[&str]) -> Result<(), TokenizerError> {
+    match component_type(tokenizer_json, component)? {
+        Some(component_type) if !expected_types.contains(&component_type) => Err(unsupported(component, component_type)),
+        _ => Ok(())
+    }
+}
+
+/// Checks that an option of a component is either missing or set to the only value supported.
+fn expect_option(value: &Value, component: &str, option: &str, expected: &Value) -> Result<(), TokenizerError> {
+    match value.get(option) {
+        Some(actual) if actual != expected => Err(unsupported(component, &format!("with {} {}", option, actual))),
+        _ => Ok(())
+    }
+}
+
+fn get_bool(value: &Value, component: &str, option: &str, default: bool) -> Result<bool, TokenizerError> {
+    match value.get(option) {
+        None | Some(Value::Null) => Ok(default),
+        Some(Value::Bool(option_value)) => Ok(*option_value),
+        Some(actual) => Err(TokenizerError::VocabularyParsingError(format!("invalid {} {} for the {}", option, actual, component)))
+    }
+}
+
+/// Post-processors serialize their special tokens as `[token, id]` pairs.
+fn expect_special_token(post_processor: &Value, key: &str, expected: &str) -> Result<(), TokenizerError> {
+    match post_processor[key][0].as_str() {
+        Some(token) if token == expected => Ok(()),
+        _ => Err(unsupported("post_processor", &format!("with {} {}", key, post_processor[key])))
+    }
+}
+
+/// Compares the single and pair templates, written as space separated `piece:type_id` items.
+fn expect_template(post_processor: &Value, single: &str, pair: &str) -> Result<(), TokenizerError> {
+    for (key, expected) in &[("single", single), ("pair", pair)] {
+        let pieces = post_processor[*key].as_array()
+            .ok_or_else(|| TokenizerError::VocabularyParsingError(format!("invalid {} template", key)))?;
+        let template = pieces
+            .iter()
+            .map(|piece| match (&piece["SpecialToken"], &piece["Sequence"]) {
+                (Value::Object(token), _) => format!("{}:{}", token["id"].as_str().unwrap_or_default(), token["type_id"]),
+                (_, Value::Object(sequence)) => format!("${}:{}", sequence["id"].as_str().unwrap_or_default(), sequence["type_id"]),
+                _ => piece.to_string()
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        if template != *expected {
+            return Err(unsupported("post_processor", &format!("TemplateProcessing with {} template {}", key, template)));
+        }
+    }
+    Ok(())
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use serde_json::json;
+    use tempfile::NamedTempFile;
+    use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, TruncationStrategy, PaddingStrategy, PaddingSide};
+
+    fn write_tokenizer_json(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", content).unwrap();
+        file
+    }
+
+    const BERT_TOKENIZER_JSON: &str = r###"{
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": [
+            {"id": 0, "content": "[PAD]", "single_word": false, "lstrip": false, "rstrip": false, "normalized": false, "special": true},
+            {"id": 2, "content": "[UNK]", "single_word": false, "lstrip": false, "rstrip": false, "normalized": false, "special": true}
+        ],
+        "normalizer": {"type": "BertNormalizer", "clean_text": true, "handle_chinese_chars": true, "strip_accents": null, "lowercase": true},
+        "pre_tokenizer": {"type": "BertPreTokenizer"},
+        "post_processor": {
+            "type": "TemplateProcessing",
+            "single": [{"SpecialToken": {"id": "[CLS]", "type_id": 0}}, {"Sequence": {"id": "A", "type_id": 0}}, {"SpecialToken": {"id": "[SEP]", "type_id": 0}}],
+            "pair": [{"SpecialToken": {"id": "[CLS]", "type_id": 0}}, {"Sequence": {"id": "A", "type_id": 0}}, {"SpecialToken": {"id": "[SEP]", "type_id": 0}},
+                     {"Sequence": {"id": "B", "type_id": 1}}, {"SpecialToken": {"id": "[SEP]", "type_id": 1}}],
+            "special_tokens": {}
+        },
+        "decoder": {"type": "WordPiece", "prefix": "##", "cleanup": true},
+        "model": {
+            "type": "WordPiece", "unk_token": "[UNK]", "continuing_subword_prefix": "##", "max_input_chars_per_word": 100,
+            "vocab": {"[PAD]": 0, "[MASK]": 1, "[UNK]": 2, "[CLS]": 3, "[SEP]": 4, "hello": 5, "!": 7, "wo": 8, "##rld": 9}
+        }
+    }"###;
+
+    const BPE_TOKENIZER_JSON: &str = r###"{
+        "version": "1.0",
+        "added_tokens": [{"id": 6, "content": "<|endoftext|>", "special": true}],
+        "normalizer": null,
+        "pre_tokenizer": {"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true, "use_regex": true},
+        "post_processor": {"type": "ByteLevel", "add_prefix_space": true, "trim_offsets": false, "use_regex": true},
+        "decoder": {"type": "ByteLevel", "add_prefix_space": true, "trim_offsets": true, "use_regex": true},
+        "model": {
+            "type": "BPE", "dropout": null, "unk_token": null, "continuing_subword_prefix": "", "end_of_word_suffix": "", "fuse_unk": false,
+            "vocab": {"t": 0, "h": 1, "a@@": 2, "n": 3, "the": 4, "Ġ": 5, "<|endoftext|>": 6, "o@@": 7, "Ġear": 8, "th": 9},
+            "merges": ["Ġ t", "Ġ n", "e e", "Ġt he", "h e", "t h", "t he", ["Ġ", "e"], ["Ġe", "a"], "Ġea r"]
+        }
+    }"###;
+
+    const ROBERTA_TOKENIZER_JSON: &str = r###"{
+        "version": "1.0",
+        "added_tokens": [{"id": 8, "content": "<s>", "special": true}, {"id": 9, "content": "</s>", "special": true}],
+        "normalizer": null,
+        "pre_tokenizer": {"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true},
+        "post_processor": {"type": "RobertaProcessing", "sep": ["</s>", 9], "cls": ["<s>", 8], "trim_offsets": true, "add_prefix_space": false},
+        "decoder": {"type": "ByteLevel"},
+        "model": {
+            "type": "BPE", "dropout": null, "unk_token": null, "continuing_subword_prefix": null, "end_of_word_suffix": null,
+            "vocab": {"t": 0, "h": 1, "a@@": 2, "n": 3, "the": 4, "Ġ": 5, "<unk>": 6, "o@@": 7, "<s>": 8, "</s>": 9,
+                      "<pad>": 10, "<mask>": 11, "Ġear": 12, "th": 13},
+            "merges": ["Ġ t", "Ġ n", "e e", "Ġt he", "h e", "t h", "t he", "Ġ e", "Ġe a", "Ġea r"]
+        }
+    }"###;
+
+    #[test]
+    fn test_bert_from_tokenizer_json() -> Result<(), TokenizerError> {
+//        Given
+        let file = write_tokenizer_json(BERT_TOKENIZER_JSON);
+
+//        When
+        let tokenizer = BertTokenizer::from_tokenizer_json(file.path().to_str().unwrap())?;
+        let encoded = tokenizer.encode("Hello World!", None, 128, &TruncationStrategy::LongestFirst, 0,
+                                       &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(tokenizer.tokenize("Hello World!"), vec!("hello", "wo", "##rld", "!"));
+        assert_eq!(encoded.token_ids, vec!(3, 5, 8, 9, 7, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bert_from_tokenizer_json_with_added_tokens() -> Result<(), TokenizerError> {
+//        Given
+        let mut tokenizer_json: Value = serde_json::from_str(BERT_TOKENIZER_JSON)?;
+        tokenizer_json["added_tokens"] = json!([
+            {"id": 0, "content": "[PAD]", "special": true},
+            {"id": 10, "content": "[NEW]", "special": true},
+            {"id": 11, "content": "earth", "special": false}
+        ]);
+        let file = write_tokenizer_json(&tokenizer_json.to_string());
+
+//        When
+        let tokenizer = BertTokenizer::from_tokenizer_json(file.path().to_str().unwrap())?;
+        let encoded = tokenizer.encode("Hello [NEW] earth", None, 128, &TruncationStrategy::LongestFirst, 0,
+                                       &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(tokenizer.tokenize("Hello [NEW] earth"), vec!("hello", "[NEW]", "earth"));
+        assert_eq!(encoded.token_ids, vec!(3, 5, 10, 11, 4));
+        assert_eq!(encoded.special_tokens_mask, vec!(1, 0, 1, 0, 1));
+        assert_eq!(tokenizer.vocab().special_values.get("[NEW]"), Some(&10));
+        assert_eq!(tokenizer.vocab().special_values.get("earth"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gpt2_from_tokenizer_json() -> Result<(), TokenizerError> {
+//        Given
+        let file = write_tokenizer_json(BPE_TOKENIZER_JSON);
+
+//        When
+        let tokenizer = Gpt2Tokenizer::from_tokenizer_json(file.path().to_str().unwrap())?;
+
+//        Then
+        assert_eq!(tokenizer.tokenize("the earth"), vec!("the", "Ġear", "th"));
+        assert_eq!(tokenizer.convert_tokens_to_ids(&tokenizer.tokenize("the earth")), vec!(4, 8, 9));
+        Ok(())
+    }
+
+    #[test]
+    fn test_roberta_from_tokenizer_json() -> Result<(), TokenizerError> {
+//        Given
+        let file = write_tokenizer_json(ROBERTA_TOKENIZER_JSON);
+
+//        When
+        let tokenizer = RobertaTokenizer::from_tokenizer_json(file.path().to_str().unwrap())?;
+        let encoded = tokenizer.encode("the earth", None, 128, &TruncationStrategy::LongestFirst, 0,
+                                       &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(encoded.token_ids, vec!(8, 4, 12, 13, 9));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pretrained_tokenizer_model_detection() -> Result<(), TokenizerError> {
+//        Given
+        let bert_file = write_tokenizer_json(BERT_TOKENIZER_JSON);
+        let gpt2_file = write_tokenizer_json(BPE_TOKENIZER_JSON);
+        let roberta_file = write_tokenizer_json(ROBERTA_TOKENIZER_JSON);
+
+//        When & Then
+        assert!(matches!(PretrainedTokenizer::from_tokenizer_json(bert_file.path().to_str().unwrap())?, PretrainedTokenizer::Bert(_)));
+        assert!(matches!(PretrainedTokenizer::from_tokenizer_json(gpt2_file.path().to_str().unwrap())?, PretrainedTokenizer::Gpt2(_)));
+        assert!(matches!(PretrainedTokenizer::from_tokenizer_json(roberta_file.path().to_str().unwrap())?, PretrainedTokenizer::Roberta(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_components() {
+//        Given
+        let test_tuples = [
+            (BERT_TOKENIZER_JSON.replace("\"BertPreTokenizer\"", "\"Whitespace\""),
+             TokenizerError::UnsupportedComponent("pre_tokenizer Whitespace".to_owned())),
+            (BERT_TOKENIZER_JSON.replace("\"strip_accents\": null, \"lowercase\": true", "\"strip_accents\": null, \"lowercase\": false"),
+             TokenizerError::UnsupportedComponent("normalizer BertNormalizer without accent stripping".to_owned())),
+            (BERT_TOKENIZER_JSON.replace("\"id\": 0, \"content\": \"[PAD]\"", "\"id\": 12, \"content\": \"[NEW]\""),
+             TokenizerError::UnsupportedComponent("added_tokens [NEW] (id 12)".to_owned())),
+            (BERT_TOKENIZER_JSON.replace("\"id\": 0, \"content\": \"[PAD]\"", "\"id\": 3, \"content\": \"[PAD]\""),
+             TokenizerError::UnsupportedComponent("added_tokens [PAD] (id 3)".to_owned())),
+            (BERT_TOKENIZER_JSON.replace("\"type\": \"WordPiece\", \"unk_token\"", "\"type\": \"Unigram\", \"unk_token\""),
+             TokenizerError::UnsupportedComponent("model Unigram".to_owned())),
+            (BPE_TOKENIZER_JSON.replace("\"normalizer\": null", "\"normalizer\": {\"type\": \"NFC\"}"),
+             TokenizerError::UnsupportedComponent("normalizer NFC".to_owned())),
+            (BPE_TOKENIZER_JSON.replace("\"dropout\": null", "\"dropout\": 0.1"),
+             TokenizerError::UnsupportedComponent("model with dropout 0.1".to_owned())),
+        ];
+
+//        When & Then
+        for (content, expected_error) in test_tuples.iter() {
+            let file = write_tokenizer_json(content);
+            let result = PretrainedTokenizer::from_tokenizer_json(file.path().to_str().unwrap());
+            assert_eq!(result.err(), Some(expected_error.clone()));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright 2018 Facebook AI Research and the HuggingFace Inc. team.
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use crate::preprocessing::vocab::base_vocab::{Vocab, role_value};
+use crate::preprocessing::vocab::xlm_roberta_vocab::XLMRobertaVocab;
+use crate::preprocessing::vocab::sentence_piece_model::{SentencePieceModel, SPIECE_UNDERLINE};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_files::{SENTENCE_PIECE_MODEL_FILE, SPECIAL_TOKENS_MAP_FILE, create_directory, file_path, write_tokenizer_config, read_tokenizer_config, read_special_token_map, write_added_tokens, read_added_tokens};
+use crate::preprocessing::tokenizer::base_tokenizer::{Tokenizer, MultiThreadedTokenizer, Token};
+use crate::preprocessing::tokenizer::tokenization_utils::{lowercase_with_offsets};
+use crate::preprocessing::tokenizer::special_token_matcher::{SpecialTokenMatcher, register_added_tokens, restore_added_tokens};
+use crate::preprocessing::tokenizer::template_processor::TemplateProcessor;
+
+/// XLM-RoBERTa tokenizer: SentencePiece segmentation with the ids shifted to the fairseq vocabulary
+pub struct XLMRobertaTokenizer {
+    model: Arc<SentencePieceModel>,
+    vocab: Arc<XLMRobertaVocab>,
+    special_token_matcher: SpecialTokenMatcher,
+    post_processor: TemplateProcessor,
+    lower_case: bool,
+}
+
+impl XLMRobertaTokenizer {
+    pub fn from_file(path: &str, lower_case: bool) -> Result<XLMRobertaTokenizer, TokenizerError> {
+        let model = SentencePieceModel::from_file(path)?;
+        let vocab = XLMRobertaVocab::from_sentence_piece_model(&model)?;
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(&vocab);
+        let post_processor = TemplateProcessor::roberta(vocab.token_to_id(role_value(vocab.cls_value(), "cls")), vocab.token_to_id(role_value(vocab.sep_value(), "sep")), 0);
+        Ok(XLMRobertaTokenizer { model: Arc::new(model), vocab: Arc::new(vocab), special_token_matcher, post_processor, lower_case })
+    }
+
+    pub fn from_existing_vocab_and_model(vocab: Arc<XLMRobertaVocab>, model: Arc<SentencePieceModel>, lower_case: bool) -> XLMRobertaTokenizer {
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(vocab.as_ref());
+        let post_processor = TemplateProcessor::roberta(vocab.token_to_id(role_value(vocab.cls_value(), "cls")), vocab.token_to_id(role_value(vocab.sep_value(), "sep")), 0);
+        XLMRobertaTokenizer { model, vocab, special_token_matcher, post_processor, lower_case }
+    }
+
+    /// Writes the SentencePiece model, the special token map, the added tokens and a configuration holding the normalization settings to `directory`, created if needed.
+    pub fn save(&self, directory: &str) -> Result<(), TokenizerError> {
+        create_directory(directory)?;
+        self.model.to_file(&file_path(directory, SENTENCE_PIECE_MODEL_FILE))?;
+        self.vocab.special_token_map().to_file(&file_path(directory, SPECIAL_TOKENS_MAP_FILE))?;
+        write_added_tokens(directory, self.special_token_matcher.added_tokens())?;
+        write_tokenizer_config(directory, self.lower_case, None)
+    }
+
+    /// Loads a tokenizer written by `save`.
+    pub fn from_directory(directory: &str) -> Result<XLMRobertaTokenizer, TokenizerError> {
+        let (lower_case, _) = read_tokenizer_config(directory)?;
+        let model = SentencePieceModel::from_file(&file_path(directory, SENTENCE_PIECE_MODEL_FILE))?;
+        let vocab = XLMRobertaVocab::from_sentence_piece_model_with_special_token_map(&model, read_special_token_map::<XLMRobertaVocab>(directory)?)?;
+        let mut tokenizer = XLMRobertaTokenizer::from_existing_vocab_and_model(Arc::new(vocab), Arc::new(model), lower_case);
+        restore_added_tokens(&mut tokenizer.vocab, &mut tokenizer.special_token_matcher, &read_added_tokens(directory)?);
+        Ok(tokenizer)
+    }
+
+    /// Adds tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer. Returns the id of each token.
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, false)
+    }
+
+    /// Adds special tokens to the vocabulary, after the existing tokens. They are never split by the tokenizer, are flagged in the
+    /// special tokens mask and are skipped when decoding with `skip_special_tokens`. Returns the id of each token.
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        register_added_tokens(&mut self.vocab, &mut self.special_token_matcher, tokens, true)
+    }
+}
+
+impl Tokenizer<XLMRobertaVocab> for XLMRobertaTokenizer {
+    fn vocab(&self) -> &XLMRobertaVocab {
+        self.vocab.as_ref()
+    }
+
+    fn tokenize_to_tokens(&self, initial_token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(initial_token).into_iter().flatten().collect()
+    }
+
+    fn tokenize_to_words(&self, initial_token: &Token) -> Vec<Vec<Token>> {
+        let mut words: Vec<Vec<Token>> = vec!();
+        for text in self.special_token_matcher.split_with_offsets(initial_token) {
+            if self.special_token_matcher.contains(&text.text) {
+                words.push(vec!(text));
+            } else {
+                let text = if self.lower_case { lowercase_with_offsets(&text) } else { text };
+                words.extend(self.model.tokenize_to_words(&text));
+            }
+        }
+        words
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
+        tokens.join("").replace(SPIECE_UNDERLINE, " ").trim().to_owned()
+    }
+
+    /// Same layout as RoBERTa, without segment ids as the XLM-RoBERTa models have a single token type
+    fn build_input_with_special_tokens(&self, tokens_1: Vec<i64>, tokens_2: Option<Vec<i64>>) -> (Vec<i64>, Vec<i8>, Vec<i8>) {
+        self.post_processor.build_input_with_special_tokens(tokens_1, tokens_2)
+    }
+}
+
+impl MultiThreadedTokenizer<XLMRobertaVocab> for XLMRobertaTokenizer {}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::base_tokenizer::{TruncationStrategy, TokenizedInput, Offset, PaddingStrategy, PaddingSide};
+    use crate::preprocessing::vocab::sentence_piece_model::{SentencePiece, SentencePieceType, SentencePieceModelType};
+
+    fn generate_test_model() -> SentencePieceModel {
+        let pieces = [
+            ("<unk>", 0.0, SentencePieceType::Unknown),
+            ("<s>", 0.0, SentencePieceType::Control),
+            ("</s>", 0.0, SentencePieceType::Control),
+            ("▁", -2.0, SentencePieceType::Normal),
+            ("▁the", -3.0, SentencePieceType::Normal),
+            ("▁ear", -4.0, SentencePieceType::Normal),
+            ("th", -5.0, SentencePieceType::Normal),
+            ("▁earth", -6.0, SentencePieceType::Normal),
+            ("e", -7.0, SentencePieceType::Normal),
+            ("▁cafe", -8.0, SentencePieceType::Normal),
+        ];
+        let pieces = pieces
+            .iter()
+            .map(|&(piece, score, piece_type)| SentencePiece { piece: piece.to_owned(), score, piece_type })
+            .collect();
+        SentencePieceModel::new(pieces, SentencePieceModelType::Unigram)
+    }
+
+    #[test]
+    fn test_xlmroberta_tokenizer() {
+//        Given
+        let model = generate_test_model();
+        let vocab = Arc::new(XLMRobertaVocab::from_sentence_piece_model(&model).unwrap());
+        let tokenizer: XLMRobertaTokenizer = XLMRobertaTokenizer::from_existing_vocab_and_model(vocab, Arc::new(model), false);
+        let test_tuples = [
+            (
+                "the earth",
+                vec!("▁the", "▁earth")
+            ),
+            (
+                "中 <mask>",
+                vec!("▁", "中", "<mask>")
+            )
+        ];
+        let source_texts: Vec<&str> = test_tuples.iter().map(|v| v.0).collect();
+        let expected_results: Vec<Vec<&str>> = test_tuples.iter().map(|v| v.1.clone()).collect();
+
+//        When & Then
+        for (source_text, expected_result) in test_tuples.iter() {
+            assert_eq!(tokenizer.tokenize(source_text), *expected_result);
+        }
+
+        assert_eq!(Tokenizer::tokenize_list(&tokenizer, source_texts.clone()), expected_results);
+        assert_eq!(MultiThreadedTokenizer::tokenize_list(&tokenizer, source_texts.clone()), expected_results);
+    }
+
+    #[test]
+    fn test_encode() {
+//        Given
+        let model = generate_test_model();
+        let vocab = Arc::new(XLMRobertaVocab::from_sentence_piece_model(&model).unwrap());
+        let tokenizer: XLMRobertaTokenizer = XLMRobertaTokenizer::from_existing_vocab_and_model(vocab, Arc::new(model), false);
+        let truncation_strategy = TruncationStrategy::LongestFirst;
+        let expected_result = TokenizedInput {
+            token_ids: vec!(0, 5, 8, 2, 2, 8, 2),
+            segment_ids: vec!(0, 0, 0, 0, 0, 0, 0),
+            special_tokens_mask: vec!(1, 0, 0, 1, 1, 0, 1),
+            overflowing_tokens: vec!(),
+            num_truncated_tokens: 0,
+            token_offsets: vec!(None, Some(Offset { begin: 0, end: 3 }), Some(Offset { begin: 3, end: 9 }), None, None, Some(Offset { begin: 0, end: 5 }), None),
+            attention_mask: vec!(1, 1, 1, 1, 1, 1, 1),
+            word_ids: vec!(None, Some(0), Some(1), None, None, Some(0), None),
+            sequence_ids: vec!(None, Some(0), Some(0), None, None, Some(1), None),
+        };
+
+//        When
+        let encoded = tokenizer.encode("the earth", Some("earth"), 128, &truncation_strategy, 0, &PaddingStrategy::DoNotPad, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(encoded, expected_result);
+        assert_eq!(tokenizer.decode(encoded.token_ids, true, false), "the earth earth");
+    }
+
+    #[test]
+    fn test_unknown_and_mask_ids() {
+//        Given
+        let model = generate_test_model();
+        let vocab = Arc::new(XLMRobertaVocab::from_sentence_piece_model(&model).unwrap());
+        let tokenizer: XLMRobertaTokenizer = XLMRobertaTokenizer::from_existing_vocab_and_model(vocab, Arc::new(model), false);
+
+//        When & Then
+        assert_eq!(tokenizer.convert_tokens_to_ids(&tokenizer.tokenize("中 <mask>")), vec!(4, 3, 11));
+        assert_eq!(tokenizer.pad_id(), 1);
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright 2018 The Open AI Team Authors
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::base_tokenizer::Token;
+use crate::preprocessing::tokenizer::special_token_matcher::SpecialTokenMatcher;
+use crate::preprocessing::tokenizer::constants::BYTES_TO_UNICODE;
+use crate::preprocessing::tokenizer::tokenization_utils::{lowercase_with_offsets, split_gpt2_words};
+use crate::preprocessing::trainer::merge_statistics::MergeStatistics;
+use crate::preprocessing::vocab::base_vocab::{Vocab, swap_key_values, open_file};
+use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
+use crate::Gpt2Vocab;
+
+/// Trains a byte-level `Gpt2Vocab` and the matching `BpePairVocab` merges from a text corpus.
+///
+/// The corpus is pre-tokenized as by the `Gpt2Tokenizer` (optional lower casing, GPT-2 regular expression) and each word is mapped
+/// byte by byte to unicode characters. The vocabulary is initialized with the special tokens and the 256 byte characters, and grown
+/// by repeatedly merging the most frequent pair of adjacent sub-words until the target size is reached or no pair occurs at least
+/// `min_frequency` times. The special tokens and byte characters are always kept, even if they exceed the target size.
+///
+/// The vocabulary and merges written with `to_file` can be loaded by `Gpt2Tokenizer::from_file`, and by `RobertaTokenizer::from_file`
+/// if the RoBERTa special tokens were added with `with_special_tokens`.
+pub struct BpeTrainer {
+    vocab_size: usize,
+    min_frequency: u64,
+    lower_case: bool,
+    special_tokens: Vec<String>,
+}
+
+impl BpeTrainer {
+    /// Creates a trainer for a vocabulary of `vocab_size` tokens, with `<|endoftext|>` as only special token.
+    /// All pairs are candidates for merging.
+    pub fn new(vocab_size: usize, lower_case: bool) -> BpeTrainer {
+        BpeTrainer {
+            vocab_size,
+            min_frequency: 1,
+            lower_case,
+            special_tokens: vec!(Gpt2Vocab::default_special_token_map().unk_token),
+        }
+    }
+
+    /// Sets the minimum number of occurrences of a pair of sub-words in the corpus for it to be merged.
+    pub fn with_min_frequency(mut self, min_frequency: u64) -> BpeTrainer {
+        self.min_frequency = min_frequency;
+        self
+    }
+
+    /// Sets the special tokens, placed at the start of the vocabulary in the given order, e.g. `["<s>", "<pad>", "</s>", "<unk>", "<mask>"]`
+    /// for RoBERTa. `<|endoftext|>`, required by the `Gpt2Vocab`, is appended if missing.
+    pub fn with_special_tokens(mut self, special_tokens: &[&str]) -> BpeTrainer {
+        self.special_tokens = vec!();
+        for special_token in special_tokens {
+            if !self.special_tokens.iter().any(|token| token == special_token) {
+                self.special_tokens.push(special_token.to_string());
+            }
+        }
+        let unknown_token = Gpt2Vocab::default_special_token_map().unk_token;
+        if !self.special_tokens.contains(&unknown_token) {
+            self.special_tokens.push(unknown_token);
+        }
+        self
+    }
+
+    /// Trains a vocabulary and merges from an iterator over the texts of a corpus.
+    pub fn train<I, S>(&self, texts: I) -> Result<(Gpt2Vocab, BpePairVocab), TokenizerError>
+        where I: IntoIterator<Item=S>,
+              S: AsRef<str> {
+        let special_vocab = self.special_tokens_vocab();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(&special_vocab);
+        let mut word_counts = HashMap::new();
+        for text in texts {
+            self.count_words(text.as_ref(), &special_vocab, &special_token_matcher, &mut word_counts);
+        }
+        self.train_from_word_counts(word_counts)
+    }
+
+    /// Trains a vocabulary and merges from text files, read line by line.
+    pub fn train_from_files(&self, paths: &[&str]) -> Result<(Gpt2Vocab, BpePairVocab), TokenizerError> {
+        let special_vocab = self.special_tokens_vocab();
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(&special_vocab);
+        let mut word_counts = HashMap::new();
+        for path in paths {
+            let f = open_file(path)?;
+            for line in BufReader::new(f).lines() {
+                self.count_words(&line?, &special_vocab, &special_token_matcher, &mut word_counts);
+            }
+        }
+        self.train_from_word_counts(word_counts)
+    }
+
+    /// Returns a vocabulary made of the special tokens only, all of them registered as special values.
+    fn special_tokens_vocab(&self) -> Gpt2Vocab {
+        let values: HashMap<String, i64> = self.special_tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| (token.clone(), index as i64))
+            .collect();
+        let indices = swap_key_values(&values);
+        Gpt2Vocab {
+            values: values.clone(),
+            indices: indices.clone(),
+            special_token_map: Gpt2Vocab::default_special_token_map(),
+            special_values: values,
+            special_indices: indices,
+        }
+    }
+
+    fn count_words(&self, text: &str, special_vocab: &Gpt2Vocab, special_token_matcher: &SpecialTokenMatcher,
+                   word_counts: &mut HashMap<String, u64>) {
+        for word in self.pre_tokenize(text, special_vocab, special_token_matcher) {
+            *word_counts.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    /// Splits a text into byte-level words as the `Gpt2Tokenizer` does before BPE tokenization. Special tokens are skipped.
+    fn pre_tokenize(&self, text: &str, special_vocab: &Gpt2Vocab, special_token_matcher: &SpecialTokenMatcher) -> Vec<String> {
+        let mut words = vec!();
+        for token in special_token_matcher.split_with_offsets(&Token::new(text)) {
+            if special_vocab.special_values.contains_key(&token.text) {
+                continue;
+            }
+            let token = if self.lower_case { lowercase_with_offsets(&token) } else { token };
+
+            for (start, end) in split_gpt2_words(&token.text) {
+                words.push(token.text.as_bytes()[start..end].iter().map(|v| BYTES_TO_UNICODE.get(v).unwrap()).collect());
+            }
+        }
+        words
+    }
+
+    fn train_from_word_counts(&self, word_counts: HashMap<String, u64>) -> Result<(Gpt2Vocab, BpePairVocab), TokenizerError> {
+        let mut values: HashMap<String, i64> = self.special_tokens_vocab().values;
+        for byte in 0..=255u8 {
+            let symbol = BYTES_TO_UNICODE[&byte].to_string();
+            if !values.contains_key(&symbol) {
+                values.insert(symbol, values.len() as i64);
+            }
+        }
+
+        let words: Vec<(Vec<String>, u64)> = word_counts
+            .into_iter()
+            .map(|(word, count)| (word.chars().map(|character| character.to_string()).collect(), count))
+            .collect();
+
+        let mut merges = HashMap::new();
+        let mut statistics = MergeStatistics::new(words);
+        while values.len() < self.vocab_size {
+            let (left, right) = match statistics.most_frequent_pair(self.min_frequency) {
+                Some(pair) => pair,
+                None => break
+            };
+            let merged = format!("{}{}", left, right);
+            statistics.merge(&left, &right, &merged);
+            merges.insert((left, right), merges.len() as i64);
+            if !values.contains_key(&merged) {
+                values.insert(merged, values.len() as i64);
+            }
+        }
+
+        Ok((Gpt2Vocab::from_values(values)?, BpePairVocab::from_values(merges)))
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::io::Write;
+    use crate::{Gpt2Tokenizer, RobertaTokenizer, Tokenizer, TruncationStrategy, PaddingStrategy, PaddingSide};
+
+    #[test]
+    fn test_special_tokens_and_alphabet() -> Result<(), TokenizerError> {
+//        Given
+        let trainer = BpeTrainer::new(0, false);
+
+//        When
+        let (vocab, merges) = trainer.train(vec!("Hello world!"))?;
+
+//        Then
+        assert_eq!(vocab.values.len(), 257);
+        assert_eq!(vocab.values["<|endoftext|>"], 0);
+        assert_eq!(vocab.values["Ġ"], 1 + 32);
+        assert_eq!(vocab.values["!"], 1 + 33);
+        assert!(merges.values().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merges() -> Result<(), TokenizerError> {
+//        Given
+        let corpus = vec!("low lower lowest", "low newer wider", "the lowest");
+        let trainer = BpeTrainer::new(267, true);
+
+//        When
+        let (vocab, merges) = trainer.train(corpus)?;
+
+//        Then
+        assert_eq!(vocab.values.len(), 267);
+        assert_eq!(merges.values().len(), 10);
+        assert_eq!(merges.values().values().cloned().collect::<HashSet<i64>>(), (0..10).collect());
+        for (left, right) in merges.values().keys() {
+            assert!(vocab.values.contains_key(&format!("{}{}", left, right)));
+        }
+        assert!(vocab.values.contains_key("Ġlow"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_frequency() -> Result<(), TokenizerError> {
+//        Given
+        let corpus = vec!("low lower lowest");
+        let trainer = BpeTrainer::new(1000, true).with_min_frequency(3);
+
+//        When
+        let (vocab, merges) = trainer.train(corpus)?;
+
+//        Then
+        let merged_pairs: Vec<&(String, String)> = merges.ranked_merges().collect();
+        assert_eq!(merged_pairs, vec!(&("l".to_owned(), "o".to_owned()), &("lo".to_owned(), "w".to_owned())));
+        assert_eq!(vocab.values.len(), 259);
+        Ok(())
+    }
+
+    #[test]
+    fn test_train_gpt2_tokenizer() -> Result<(), TokenizerError> {
+//        Given
+        let mut corpus_file = tempfile::NamedTempFile::new()?;
+        write!(corpus_file, "The tokenizer splits words into pieces.\nWords are split <|endoftext|> into byte pieces!\n")?;
+        let corpus_path = corpus_file.into_temp_path();
+        let trainer = BpeTrainer::new(300, false);
+        let vocab_path = tempfile::NamedTempFile::new()?.into_temp_path();
+        let merges_path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        let (vocab, merges) = trainer.train_from_files(&[corpus_path.to_str().unwrap()])?;
+        vocab.to_file(vocab_path.to_str().unwrap())?;
+        merges.to_file(merges_path.to_str().unwrap())?;
+        let tokenizer = Gpt2Tokenizer::from_file(vocab_path.to_str().unwrap(), merges_path.to_str().unwrap(), false)?;
+        let text = "Words split into pieces, héhé!";
+        let tokens = tokenizer.tokenize(text);
+
+//        Then
+        assert!(!tokens.contains(&"<|endoftext|>".to_owned()));
+        assert!(tokens.contains(&"Ġpieces".to_owned()));
+        assert_eq!(tokenizer.decode(tokenizer.convert_tokens_to_ids(&tokens), false, false), text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_train_roberta_tokenizer() -> Result<(), TokenizerError> {
+//        Given
+        let corpus = vec!("<s> The tokenizer splits words into pieces.", "Words are split into byte pieces!");
+        let trainer = BpeTrainer::new(300, false).with_special_tokens(&["<s>", "<pad>", "</s>", "<unk>", "<mask>"]);
+        let vocab_path = tempfile::NamedTempFile::new()?.into_temp_path();
+        let merges_path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        let (vocab, merges) = trainer.train(corpus)?;
+        vocab.to_file(vocab_path.to_str().unwrap())?;
+        merges.to_file(merges_path.to_str().unwrap())?;
+        let tokenizer = RobertaTokenizer::from_file(vocab_path.to_str().unwrap(), merges_path.to_str().unwrap(), false)?;
+        let encoded = tokenizer.encode("Words <mask>", None, 10, &TruncationStrategy::LongestFirst, 0,
+                                       &PaddingStrategy::MaxLength, &PaddingSide::Right);
+
+//        Then
+        assert_eq!(vocab.values["<s>"], 0);
+        assert_eq!(vocab.values["<mask>"], 4);
+        assert_eq!(vocab.values["<|endoftext|>"], 5);
+        let length = encoded.attention_mask.iter().filter(|&&mask| mask == 1).count();
+        assert_eq!(encoded.token_ids.len(), 10);
+        assert_eq!(encoded.token_ids[0], 0);
+        assert_eq!(&encoded.token_ids[length - 2..length], &[4, 2]);
+        assert!(encoded.token_ids[length..].iter().all(|&id| id == 1));
+        assert_eq!(tokenizer.decode(encoded.token_ids, true, false), "Words");
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet, BinaryHeap};
+use std::cmp::Reverse;
+use std::hash::Hash;
+
+type PairQueueEntry = (u64, Reverse<(String, String)>);
+
+/// Words of a corpus split into sub-words, with the counts of the sub-words and of the pairs of adjacent sub-words,
+/// updated as pairs get merged.
+pub(crate) struct MergeStatistics {
+    words: Vec<(Vec<String>, u64)>,
+    token_counts: HashMap<String, u64>,
+    pair_counts: HashMap<(String, String), u64>,
+    /// Indices of the words containing each pair. Entries may be stale once a word has been merged.
+    pair_words: HashMap<(String, String), HashSet<usize>>,
+    /// Pairs ordered by number of occurrences, then by lexicographic order. An entry is pushed each time the count of a pair
+    /// changes, entries whose count differs from the current count of the pair being stale and skipped.
+    pair_queue: BinaryHeap<PairQueueEntry>,
+}
+
+impl MergeStatistics {
+    /// Creates the statistics from the sub-words of each distinct word and its number of occurrences.
+    pub(crate) fn new(words: Vec<(Vec<String>, u64)>) -> MergeStatistics {
+        let mut statistics = MergeStatistics {
+            words: vec!(),
+            token_counts: HashMap::new(),
+            pair_counts: HashMap::new(),
+            pair_words: HashMap::new(),
+            pair_queue: BinaryHeap::new(),
+        };
+        for (index, (symbols, count)) in words.iter().enumerate() {
+            statistics.add_word(index, symbols, *count);
+        }
+        statistics.words = words;
+        statistics.pair_queue = statistics.pair_counts
+            .iter()
+            .map(|(pair, &count)| (count, Reverse(pair.clone())))
+            .collect();
+        statistics
+    }
+
+    /// Returns the pair with the highest score among the pairs occurring at least `min_frequency` times. The score is computed from
+    /// the number of occurrences of the pair and of its left and right sub-words. Ties are broken by the number of occurrences,
+    /// then by the lexicographic order of the pairs.
+    pub(crate) fn best_pair<F>(&self, min_frequency: u64, score: F) -> Option<(String, String)>
+        where F: Fn(u64, u64, u64) -> f64 {
+        let mut best: Option<(&(String, String), f64, u64)> = None;
+        for (pair, &count) in self.pair_counts.iter().filter(|(_, &count)| count >= min_frequency) {
+            let pair_score = score(count, self.token_counts[&pair.0], self.token_counts[&pair.1]);
+            let is_better = match best {
+                None => true,
+                Some((best_pair, best_score, best_count)) => pair_score > best_score
+                    || (pair_score == best_score && (count > best_count || (count == best_count && pair < best_pair)))
+            };
+            if is_better {
+                best = Some((pair, pair_score, count));
+            }
+        }
+        best.map(|(pair, _, _)| pair.clone())
+    }
+
+    /// Returns the pair with the most occurrences among the pairs occurring at least `min_frequency` times, ties being broken by the
+    /// lexicographic order of the pairs. Same result as `best_pair` scored by the number of occurrences, without scanning all pairs.
+    pub(crate) fn most_frequent_pair(&mut self, min_frequency: u64) -> Option<(String, String)> {
+        while let Some((count, Reverse(pair))) = self.pair_queue.peek() {
+            if self.pair_counts.get(pair) == Some(count) {
+                return if *count >= min_frequency { Some(pair.clone()) } else { None };
+            }
+            self.pair_queue.pop();
+        }
+        None
+    }
+
+    /// Replaces the occurrences of the pair `(left, right)` by the `merged` sub-word in all words.
+    pub(crate) fn merge(&mut self, left: &str, right: &str, merged: &str) {
+        let word_indices = self.pair_words.remove(&(left.to_owned(), right.to_owned())).unwrap_or_default();
+        let mut updated_pairs = HashSet::new();
+        for index in word_indices {
+            let count = self.words[index].1;
+            let merged_symbols = merge_pair(&self.words[index].0, left, right, merged);
+            if merged_symbols.len() < self.words[index].0.len() {
+                let symbols = std::mem::replace(&mut self.words[index].0, merged_symbols);
+                self.remove_word(&symbols, count);
+                self.add_word(index, &self.words[index].0.clone(), count);
+                for pair in symbols.windows(2).chain(self.words[index].0.windows(2)) {
+                    updated_pairs.insert((pair[0].clone(), pair[1].clone()));
+                }
+            }
+        }
+        for pair in updated_pairs {
+            if let Some(&count) = self.pair_counts.get(&pair) {
+                self.pair_queue.push((count, Reverse(pair)));
+            }
+        }
+    }
+
+    fn add_word(&mut self, index: usize, symbols: &[String], count: u64) {
+        for symbol in symbols {
+            *self.token_counts.entry(symbol.clone()).or_insert(0) += count;
+        }
+        for pair in symbols.windows(2) {
+            let pair = (pair[0].clone(), pair[1].clone());
+            *self.pair_counts.entry(pair.clone()).or_insert(0) += count;
+            self.pair_words.entry(pair).or_default().insert(index);
+        }
+    }
+
+    fn remove_word(&mut self, symbols: &[String], count: u64) {
+        for symbol in symbols {
+            decrement(&mut self.token_counts, symbol.clone(), count);
+        }
+        for pair in symbols.windows(2) {
+            decrement(&mut self.pair_counts, (pair[0].clone(), pair[1].clone()), count);
+        }
+    }
+}
+
+/// Replaces the non-overlapping occurrences of the pair `(left, right)` in the symbols, from left to right.
+fn merge_pair(symbols: &[String], left: &str, right: &str, merged: &str) -> Vec<String> {
+    let mut output = Vec::with_capacity(symbols.len());
+    let mut position = 0;
+    while position < symbols.len() {
+        if position + 1 < symbols.len() && symbols[position] == left && symbols[position + 1] == right {
+            output.push(merged.to_owned());
+            position += 2;
+        } else {
+            output.push(symbols[position].clone());
+            position += 1;
+        }
+    }
+    output
+}
+
+fn decrement<K: Hash + Eq>(counts: &mut HashMap<K, u64>, key: K, count: u64) {
+    if let Some(value) = counts.get_mut(&key) {
+        *value -= count;
+        if *value == 0 {
+            counts.remove(&key);
+        }
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_words(words: &[(&str, u64)]) -> Vec<(Vec<String>, u64)> {
+        words
+            .iter()
+            .map(|&(word, count)| (word.chars().map(|character| character.to_string()).collect(), count))
+            .collect()
+    }
+
+    #[test]
+    fn test_most_frequent_pair() {
+//        Given
+        let mut statistics = MergeStatistics::new(split_words(&[("lower", 2), ("lowest", 3), ("newer", 6), ("wider", 3), ("aaaa", 2)]));
+        let mut expected_statistics = MergeStatistics::new(split_words(&[("lower", 2), ("lowest", 3), ("newer", 6), ("wider", 3), ("aaaa", 2)]));
+
+//        When & Then
+        assert_eq!(statistics.most_frequent_pair(1), Some(("e".to_owned(), "r".to_owned())));
+        for _ in 0..10 {
+            let pair = statistics.most_frequent_pair(2);
+            assert_eq!(pair, expected_statistics.best_pair(2, |pair_count, _, _| pair_count as f64));
+            let (left, right) = match pair {
+                Some(pair) => pair,
+                None => break
+            };
+            let merged = format!("{}{}", left, right);
+            statistics.merge(&left, &right, &merged);
+            expected_statistics.merge(&left, &right, &merged);
+        }
+        assert_eq!(statistics.most_frequent_pair(100), None);
+    }
+}
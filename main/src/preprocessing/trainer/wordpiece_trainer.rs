@@ -0,0 +1,304 @@
+// Copyright 2018 The Google AI Language Team Authors
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufRead, BufReader};
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::base_tokenizer::Token;
+use crate::preprocessing::tokenizer::special_token_matcher::SpecialTokenMatcher;
+use crate::preprocessing::tokenizer::tokenization_utils::{clean_text_with_offsets, tokenize_cjk_chars_with_offsets, whitespace_tokenize_with_offsets,
+                                                          lowercase_with_offsets, strip_accents_with_offsets, split_on_punct_with_offsets};
+use crate::preprocessing::vocab::base_vocab::{Vocab, open_file};
+use crate::preprocessing::trainer::merge_statistics::MergeStatistics;
+use crate::BertVocab;
+
+/// Prefix of the sub-words continuing a word, as expected by the WordPiece tokenization of the `BertTokenizer`.
+pub const CONTINUING_SUBWORD_PREFIX: &str = "##";
+
+/// Words longer than this (in characters) are mapped to the unknown token by the `BertTokenizer` and are not used for training.
+const MAX_WORD_LENGTH: usize = 100;
+
+/// Trains a `BertVocab` from a text corpus.
+///
+/// The corpus is pre-tokenized as by the `BertTokenizer` (text cleaning, CJK characters and punctuation splitting, optional lower casing
+/// and accent stripping). The vocabulary is initialized with the special tokens and all the characters of the corpus, and grown by
+/// repeatedly merging the pair of adjacent sub-words with the highest likelihood score `count(ab) / (count(a) * count(b))` until the target
+/// size is reached or no pair occurs at least `min_frequency` times. The characters of the corpus are always kept, even if they
+/// exceed the target size.
+pub struct WordPieceTrainer {
+    vocab_size: usize,
+    min_frequency: u64,
+    lower_case: bool,
+    strip_accents: bool,
+}
+
+impl WordPieceTrainer {
+    /// Creates a trainer for a vocabulary of `vocab_size` tokens. Accents are stripped, and all pairs are candidates for merging.
+    pub fn new(vocab_size: usize, lower_case: bool) -> WordPieceTrainer {
+        WordPieceTrainer { vocab_size, min_frequency: 1, lower_case, strip_accents: true }
+    }
+
+    /// Sets the minimum number of occurrences of a pair of sub-words in the corpus for it to be merged.
+    pub fn with_min_frequency(mut self, min_frequency: u64) -> WordPieceTrainer {
+        self.min_frequency = min_frequency;
+        self
+    }
+
+    /// Sets whether accents are stripped. The `BertTokenizer` always strips accents.
+    pub fn with_strip_accents(mut self, strip_accents: bool) -> WordPieceTrainer {
+        self.strip_accents = strip_accents;
+        self
+    }
+
+    /// Trains a vocabulary from an iterator over the texts of a corpus.
+    pub fn train<I, S>(&self, texts: I) -> Result<BertVocab, TokenizerError>
+        where I: IntoIterator<Item=S>,
+              S: AsRef<str> {
+        let special_vocab = special_tokens_vocab()?;
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(&special_vocab);
+        let mut word_counts = HashMap::new();
+        for text in texts {
+            self.count_words(text.as_ref(), &special_vocab, &special_token_matcher, &mut word_counts);
+        }
+        self.train_from_word_counts(word_counts)
+    }
+
+    /// Trains a vocabulary from text files, read line by line.
+    pub fn train_from_files(&self, paths: &[&str]) -> Result<BertVocab, TokenizerError> {
+        let special_vocab = special_tokens_vocab()?;
+        let special_token_matcher = SpecialTokenMatcher::from_vocab(&special_vocab);
+        let mut word_counts = HashMap::new();
+        for path in paths {
+            let f = open_file(path)?;
+            for line in BufReader::new(f).lines() {
+                self.count_words(&line?, &special_vocab, &special_token_matcher, &mut word_counts);
+            }
+        }
+        self.train_from_word_counts(word_counts)
+    }
+
+    fn count_words(&self, text: &str, special_vocab: &BertVocab, special_token_matcher: &SpecialTokenMatcher,
+                   word_counts: &mut HashMap<String, u64>) {
+        for word in self.pre_tokenize(text, special_vocab, special_token_matcher) {
+            *word_counts.entry(word.text).or_insert(0) += 1;
+        }
+    }
+
+    /// Splits a text into words as the `BertTokenizer` does before WordPiece tokenization. Special tokens are skipped.
+    fn pre_tokenize(&self, text: &str, special_vocab: &BertVocab, special_token_matcher: &SpecialTokenMatcher) -> Vec<Token> {
+        special_token_matcher.split_with_offsets(&Token::new(text))
+            .into_iter()
+            .filter(|token| !special_vocab.special_values().contains_key(&token.text))
+            .flat_map(|token| {
+                let cleaned_token = tokenize_cjk_chars_with_offsets(&clean_text_with_offsets(&token, true));
+                whitespace_tokenize_with_offsets(&cleaned_token)
+            })
+            .map(|mut token| {
+                if self.lower_case {
+                    token = lowercase_with_offsets(&token);
+                }
+                if self.strip_accents {
+                    token = strip_accents_with_offsets(&token);
+                }
+                token
+            })
+            .flat_map(|token| split_on_punct_with_offsets(&token, special_vocab))
+            .flat_map(|token| whitespace_tokenize_with_offsets(&token))
+            .collect()
+    }
+
+    fn train_from_word_counts(&self, word_counts: HashMap<String, u64>) -> Result<BertVocab, TokenizerError> {
+        let mut values: HashMap<String, i64> = special_tokens_vocab()?.values;
+
+        let words: Vec<(Vec<String>, u64)> = word_counts
+            .into_iter()
+            .filter(|(word, _)| word.chars().count() <= MAX_WORD_LENGTH)
+            .map(|(word, count)| (split_characters(&word), count))
+            .collect();
+
+        let alphabet: BTreeSet<&String> = words.iter().flat_map(|(symbols, _)| symbols.iter()).collect();
+        for symbol in alphabet {
+            if !values.contains_key(symbol) {
+                values.insert(symbol.clone(), values.len() as i64);
+            }
+        }
+
+        let mut statistics = MergeStatistics::new(words);
+        while values.len() < self.vocab_size {
+            let (left, right) = match statistics.best_pair(self.min_frequency, likelihood_score) {
+                Some(pair) => pair,
+                None => break
+            };
+            let merged = format!("{}{}", left, &right[CONTINUING_SUBWORD_PREFIX.len()..]);
+            statistics.merge(&left, &right, &merged);
+            if !values.contains_key(&merged) {
+                values.insert(merged, values.len() as i64);
+            }
+        }
+
+        BertVocab::from_values(values)
+    }
+}
+
+/// Returns a vocabulary made of the special tokens of BERT only.
+fn special_tokens_vocab() -> Result<BertVocab, TokenizerError> {
+    let special_token_map = BertVocab::default_special_token_map();
+    let special_tokens = [special_token_map.pad_token.as_deref(), Some(special_token_map.unk_token.as_str()),
+        special_token_map.cls_token.as_deref(), special_token_map.sep_token.as_deref(), special_token_map.mask_token.as_deref()];
+    let values = special_tokens
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, token)| (token.to_string(), index as i64))
+        .collect();
+    BertVocab::from_values(values)
+}
+
+/// Scores a pair of sub-words by the likelihood of the pair relative to the likelihoods of its sub-words.
+fn likelihood_score(pair_count: u64, left_count: u64, right_count: u64) -> f64 {
+    pair_count as f64 / (left_count as f64 * right_count as f64)
+}
+
+/// Splits a word into characters, all but the first one carrying the continuing sub-word prefix.
+fn split_characters(word: &str) -> Vec<String> {
+    word.chars()
+        .enumerate()
+        .map(|(index, character)| if index == 0 {
+            character.to_string()
+        } else {
+            format!("{}{}", CONTINUING_SUBWORD_PREFIX, character)
+        })
+        .collect()
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::Arc;
+    use crate::{BertTokenizer, Tokenizer};
+
+    #[test]
+    fn test_special_tokens_and_alphabet() -> Result<(), TokenizerError> {
+//        Given
+        let trainer = WordPieceTrainer::new(0, true);
+
+//        When
+        let vocab = trainer.train(vec!("Hello, World!"))?;
+
+//        Then
+        assert_eq!(vocab.values["[PAD]"], 0);
+        assert_eq!(vocab.values["[UNK]"], 1);
+        assert_eq!(vocab.values["[CLS]"], 2);
+        assert_eq!(vocab.values["[SEP]"], 3);
+        assert_eq!(vocab.values["[MASK]"], 4);
+        assert_eq!(vocab.special_values.len(), 5);
+        for token in &["h", "##e", "##l", "##o", "w", "##r", "##d", ",", "!"] {
+            assert!(vocab.values.contains_key(*token), "missing {}", token);
+        }
+        assert!(!vocab.values.contains_key("H"));
+        assert!(!vocab.values.contains_key("##,"));
+        assert_eq!(vocab.values.len(), 14);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merges() -> Result<(), TokenizerError> {
+//        Given
+        let corpus = vec!("hug hug hug hug hugs pug pug pun bun", "hugs bun");
+        let trainer = WordPieceTrainer::new(20, true);
+
+//        When
+        let vocab = trainer.train(corpus)?;
+
+//        Then
+        assert_eq!(vocab.values.len(), 20);
+        assert!(vocab.values.contains_key("hug"));
+        let ids: BTreeSet<i64> = vocab.values.values().cloned().collect();
+        assert_eq!(ids, (0..20).collect());
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_frequency() -> Result<(), TokenizerError> {
+//        Given
+        let corpus = vec!("hug hug hug pug");
+        let trainer = WordPieceTrainer::new(100, true).with_min_frequency(5);
+
+//        When
+        let vocab = trainer.train(corpus)?;
+
+//        Then
+        let mut tokens: Vec<&String> = vocab.values.keys().filter(|token| !vocab.special_values.contains_key(*token)).collect();
+        tokens.sort();
+        assert_eq!(tokens, vec!("##g", "##u", "h", "p"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lower_case_and_accents() -> Result<(), TokenizerError> {
+//        Given
+        let corpus = vec!("Café [SEP] 中文");
+
+//        When
+        let stripped_vocab = WordPieceTrainer::new(0, true).train(corpus.clone())?;
+        let accented_vocab = WordPieceTrainer::new(0, false).with_strip_accents(false).train(corpus)?;
+
+//        Then
+        assert!(stripped_vocab.values.contains_key("c"));
+        assert!(stripped_vocab.values.contains_key("##e"));
+        assert!(!stripped_vocab.values.contains_key("##é"));
+        assert!(stripped_vocab.values.contains_key("中"));
+        assert!(stripped_vocab.values.contains_key("文"));
+        assert!(!stripped_vocab.values.contains_key("##文"));
+        assert!(accented_vocab.values.contains_key("C"));
+        assert!(accented_vocab.values.contains_key("##é"));
+        assert!(!accented_vocab.values.contains_key("S"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_train_from_files() -> Result<(), TokenizerError> {
+//        Given
+        let mut corpus_file = tempfile::NamedTempFile::new()?;
+        write!(corpus_file, "The tokenizer splits words.\nWords are split into word pieces!\n")?;
+        let path = corpus_file.into_temp_path();
+        let trainer = WordPieceTrainer::new(40, true).with_min_frequency(2);
+
+//        When
+        let vocab = trainer.train_from_files(&[path.to_str().unwrap()])?;
+        let tokenizer = BertTokenizer::from_existing_vocab(Arc::new(vocab), true);
+        let tokens = tokenizer.tokenize("[CLS] Words split the pieces.");
+
+//        Then
+        assert!(!tokens.contains(&"[UNK]".to_owned()));
+        assert_eq!(tokens[0], "[CLS]");
+        assert_eq!(tokens.concat().replace(CONTINUING_SUBWORD_PREFIX, ""), "[CLS]wordssplitthepieces.");
+        drop(path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_train_from_missing_file() {
+//        Given
+        let trainer = WordPieceTrainer::new(10, true);
+
+//        When
+        let vocab = trainer.train_from_files(&["not/a/corpus.txt"]);
+
+//        Then
+        assert!(matches!(vocab, Err(TokenizerError::IOError(_))));
+    }
+}
@@ -10,12 +10,12 @@
 // limitations under the License.
 
 
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap};
 use std::fs::File;
-use std::io::{BufReader, BufRead};
-use std::error::Error;
-use std::process;
+use std::io::{BufReader, BufRead, BufWriter, Write};
 use std::hash::Hash;
+use serde_json::{Map, Value};
+use crate::preprocessing::error::TokenizerError;
 
 pub fn swap_key_values<T: Clone, U: Hash + Eq + Copy>(input_hashmap: &HashMap<T, U>) -> HashMap<U, T> {
     input_hashmap
@@ -24,9 +24,200 @@ pub fn swap_key_values<T: Clone, U: Hash + Eq + Copy>(input_hashmap: &HashMap<T,
         .collect()
 }
 
+pub(crate) fn open_file(path: &str) -> Result<File, TokenizerError> {
+    File::open(path).map_err(|e| TokenizerError::IOError(format!("could not open {}: {}", path, e)))
+}
+
+pub(crate) fn create_file(path: &str) -> Result<File, TokenizerError> {
+    File::create(path).map_err(|e| TokenizerError::IOError(format!("could not create {}: {}", path, e)))
+}
+
+/// Writes the vocabulary as a JSON object mapping tokens to ids, the format read by the BPE vocabularies.
+pub(crate) fn write_json_vocab_file(path: &str, values: &HashMap<String, i64>) -> Result<(), TokenizerError> {
+    let f = create_file(path)?;
+    let mut bw = BufWriter::new(f);
+    let sorted_values: BTreeMap<&String, &i64> = values.iter().collect();
+    serde_json::to_writer(&mut bw, &sorted_values)?;
+    bw.flush()?;
+    Ok(())
+}
+
+/// Tokens playing a role in the inputs of a model. Only the unknown token is required: the tokenizers using other roles
+/// (e.g. the classification and separator tokens of BERT) check that they are set when the vocabulary is created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecialTokenMap {
+    pub unk_token: String,
+    pub pad_token: Option<String>,
+    pub bos_token: Option<String>,
+    pub sep_token: Option<String>,
+    pub cls_token: Option<String>,
+    pub eos_token: Option<String>,
+    pub mask_token: Option<String>,
+    /// Special tokens without a role, such as the T5 sentinel tokens
+    pub additional_special_tokens: Vec<String>,
+}
+
+impl SpecialTokenMap {
+    /// Creates a map with an unknown token only.
+    pub fn new(unk_token: &str) -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: unk_token.to_owned(),
+            pad_token: None,
+            bos_token: None,
+            sep_token: None,
+            cls_token: None,
+            eos_token: None,
+            mask_token: None,
+            additional_special_tokens: vec!(),
+        }
+    }
+
+    /// Reads a HuggingFace `special_tokens_map.json` file, the tokens being given either as strings
+    /// or as objects with a `content` field.
+    pub fn from_file(path: &str) -> Result<SpecialTokenMap, TokenizerError> {
+        let f = open_file(path)?;
+        let br = BufReader::new(f);
+        let json: Value = serde_json::from_reader(br)?;
+        let unk_token = special_token_from_json(&json, "unk_token")?
+            .ok_or_else(|| TokenizerError::VocabularyParsingError(format!("{} does not define an unk_token", path)))?;
+        let additional_special_tokens = match &json["additional_special_tokens"] {
+            Value::Null => vec!(),
+            Value::Array(tokens) => tokens
+                .iter()
+                .map(|token| special_token_content(token, "additional_special_tokens"))
+                .collect::<Result<Vec<String>, TokenizerError>>()?,
+            value => return Err(TokenizerError::VocabularyParsingError(format!("invalid additional_special_tokens {}", value)))
+        };
+        Ok(SpecialTokenMap {
+            unk_token,
+            pad_token: special_token_from_json(&json, "pad_token")?,
+            bos_token: special_token_from_json(&json, "bos_token")?,
+            sep_token: special_token_from_json(&json, "sep_token")?,
+            cls_token: special_token_from_json(&json, "cls_token")?,
+            eos_token: special_token_from_json(&json, "eos_token")?,
+            mask_token: special_token_from_json(&json, "mask_token")?,
+            additional_special_tokens,
+        })
+    }
+
+    /// Writes the map as a `special_tokens_map.json` file, the format read by `from_file`. Unset roles are omitted.
+    pub fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        let f = create_file(path)?;
+        let mut bw = BufWriter::new(f);
+        let mut json = Map::new();
+        json.insert("unk_token".to_owned(), Value::String(self.unk_token.clone()));
+        let roles = [("pad_token", &self.pad_token), ("bos_token", &self.bos_token), ("sep_token", &self.sep_token),
+            ("cls_token", &self.cls_token), ("eos_token", &self.eos_token), ("mask_token", &self.mask_token)];
+        for (key, token) in roles.iter() {
+            if let Some(token) = token {
+                json.insert((*key).to_owned(), Value::String(token.clone()));
+            }
+        }
+        if !self.additional_special_tokens.is_empty() {
+            json.insert("additional_special_tokens".to_owned(), Value::from(self.additional_special_tokens.clone()));
+        }
+        serde_json::to_writer_pretty(&mut bw, &Value::Object(json))?;
+        bw.flush()?;
+        Ok(())
+    }
+
+    /// Returns the tokens of the map, in the order of the fields.
+    pub fn tokens(&self) -> Vec<&str> {
+        let role_tokens = [&self.pad_token, &self.bos_token, &self.sep_token, &self.cls_token, &self.eos_token, &self.mask_token];
+        let mut tokens = vec!(self.unk_token.as_str());
+        tokens.extend(role_tokens.iter().filter_map(|token| token.as_deref()));
+        tokens.extend(self.additional_special_tokens.iter().map(String::as_str));
+        tokens
+    }
+
+    /// Registers all the tokens of the map as special values, failing if one of them is not in the vocabulary.
+    pub(crate) fn register_special_values(&self, values: &HashMap<String, i64>, special_values: &mut HashMap<String, i64>)
+                                          -> Result<(), TokenizerError> {
+        for token in self.tokens() {
+            match values.get(token) {
+                Some(index) => special_values.insert(token.to_owned(), *index),
+                None => return Err(TokenizerError::MissingSpecialToken(token.to_owned()))
+            };
+        }
+        Ok(())
+    }
+}
+
+fn special_token_from_json(json: &Value, key: &str) -> Result<Option<String>, TokenizerError> {
+    match &json[key] {
+        Value::Null => Ok(None),
+        token => special_token_content(token, key).map(Some)
+    }
+}
+
+fn special_token_content(token: &Value, key: &str) -> Result<String, TokenizerError> {
+    match token {
+        Value::String(content) => Ok(content.clone()),
+        Value::Object(_) => match token["content"].as_str() {
+            Some(content) => Ok(content.to_owned()),
+            None => Err(TokenizerError::VocabularyParsingError(format!("invalid {} {}", key, token)))
+        },
+        _ => Err(TokenizerError::VocabularyParsingError(format!("invalid {} {}", key, token)))
+    }
+}
+
+/// Fails if a role used by the tokenizer of a vocabulary has no token.
+pub(crate) fn expect_role(token: &Option<String>, role: &str) -> Result<(), TokenizerError> {
+    match token {
+        Some(_) => Ok(()),
+        None => Err(TokenizerError::VocabularyParsingError(format!("the special token map must define a {} token", role)))
+    }
+}
+
+/// Returns the token of a role, panicking if it is not set. Only used by the tokenizers for the roles their vocabulary checks
+/// with `expect_role` when created.
+pub(crate) fn role_value<'a>(token: Option<&'a str>, role: &str) -> &'a str {
+    match token {
+        Some(token) => token,
+        None => panic!("the special token map of the vocabulary has no {} token", role)
+    }
+}
+
 
 pub trait Vocab {
-    fn unknown_value() -> &'static str;
+    /// Special tokens used when the vocabulary is created without an explicit special token map.
+    fn default_special_token_map() -> SpecialTokenMap;
+
+    fn special_token_map(&self) -> &SpecialTokenMap;
+
+    fn unknown_value(&self) -> &str {
+        self.special_token_map().unk_token.as_str()
+    }
+
+    /// Token used to pad encoded inputs. Defaults to the unknown value for vocabularies without a padding token.
+    fn pad_value(&self) -> &str {
+        match &self.special_token_map().pad_token {
+            Some(pad_token) => pad_token.as_str(),
+            None => self.unknown_value()
+        }
+    }
+
+    /// Token of the beginning of sequence role, `None` if the special token map of the vocabulary does not set it. Same for the
+    /// other roles below.
+    fn bos_value(&self) -> Option<&str> {
+        self.special_token_map().bos_token.as_deref()
+    }
+
+    fn eos_value(&self) -> Option<&str> {
+        self.special_token_map().eos_token.as_deref()
+    }
+
+    fn sep_value(&self) -> Option<&str> {
+        self.special_token_map().sep_token.as_deref()
+    }
+
+    fn cls_value(&self) -> Option<&str> {
+        self.special_token_map().cls_token.as_deref()
+    }
+
+    fn mask_value(&self) -> Option<&str> {
+        self.special_token_map().mask_token.as_deref()
+    }
 
     fn values(&self) -> &HashMap<String, i64>;
 
@@ -36,33 +227,65 @@ pub trait Vocab {
 
     fn special_indices(&self) -> &HashMap<i64, String>;
 
-    fn from_file(path: &str) -> Self;
+    fn from_file(path: &str) -> Result<Self, TokenizerError> where Self: Sized {
+        Self::from_file_with_special_token_map(path, Self::default_special_token_map())
+    }
+
+    /// Reads the vocabulary as `from_file` does, with the given special tokens instead of the default ones.
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<Self, TokenizerError> where Self: Sized;
 
-    fn read_vocab_file(path: &str) -> HashMap<String, i64> {
-        let f = File::open(path).expect("Could not open vocabulary file.");
+    /// Builds the vocabulary from a token to id mapping, registering the special values of the vocabulary
+    fn from_values(values: HashMap<String, i64>) -> Result<Self, TokenizerError> where Self: Sized {
+        Self::from_values_with_special_token_map(values, Self::default_special_token_map())
+    }
+
+    /// Builds the vocabulary from a token to id mapping, registering the tokens of the special token map as special values.
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap)
+                                          -> Result<Self, TokenizerError> where Self: Sized;
+
+    /// Writes the vocabulary to a file in the format read by `from_file`
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError>;
+
+    fn read_vocab_file(path: &str) -> Result<HashMap<String, i64>, TokenizerError> {
+        let f = open_file(path)?;
         let br = BufReader::new(f);
         let mut data = HashMap::new();
         let mut index = 0;
 
         for line in br.lines() {
-            data.insert(line.unwrap().trim().to_owned(), index);
+            data.insert(line?.trim().to_owned(), index);
             index += 1;
         };
-        data
+        Ok(data)
+    }
+
+    /// Writes one token per line, the line number being the token id. The ids must be contiguous and start at 0.
+    fn write_vocab_file(path: &str, indices: &HashMap<i64, String>) -> Result<(), TokenizerError> {
+        let f = create_file(path)?;
+        let mut bw = BufWriter::new(f);
+        for index in 0..indices.len() as i64 {
+            match indices.get(&index) {
+                Some(token) => writeln!(bw, "{}", token)?,
+                None => return Err(TokenizerError::VocabularyParsingError(
+                    format!("id {} is missing, a line-based vocabulary requires contiguous ids", index)))
+            }
+        }
+        bw.flush()?;
+        Ok(())
     }
 
     fn _token_to_id(&self,
                     token: &str,
                     values: &HashMap<String, i64>,
                     special_values: &HashMap<String, i64>,
-                    unknown_value: &str) -> Result<i64, Box<dyn Error>> {
+                    unknown_value: &str) -> Result<i64, TokenizerError> {
         match special_values.get(token) {
             Some(index) => Ok(*index),
             None => match values.get(token) {
                 Some(index) => Ok(*index),
                 None => match values.get(unknown_value) {
                     Some(index) => Ok(*index),
-                    None => Err("Could not decode token".into())
+                    None => Err(TokenizerError::MissingSpecialToken(unknown_value.to_owned()))
                 }
             }
         }
@@ -72,7 +295,7 @@ pub trait Vocab {
                     id: &i64,
                     indices: &HashMap<i64, String>,
                     special_indices: &HashMap<i64, String>,
-                    unknown_value: &str) -> Result<String, Box<dyn Error>> {
+                    unknown_value: &str) -> Result<String, TokenizerError> {
         match special_indices.get(id) {
             Some(token) => Ok(token.clone()),
             None => match indices.get(id) {
@@ -82,36 +305,99 @@ pub trait Vocab {
         }
     }
 
-    fn _register_as_special_value(token: &str,
-                                  values: &HashMap<String, i64>,
-                                  special_values: &mut HashMap<String, i64>) {
-        let token_id = match values.get(token) {
-            Some(index) => *index,
-            None => panic!("The special value {} could not be found in the vocabulary", token)
-        };
-        special_values.insert(String::from(token), token_id);
+    /// Adds the tokens missing from `values` with ids following the largest id of the vocabulary,
+    /// and returns the ids of all the tokens. Empty tokens are ignored.
+    fn _add_tokens(tokens: &[&str],
+                   values: &mut HashMap<String, i64>,
+                   indices: &mut HashMap<i64, String>) -> Vec<i64> {
+        let mut next_id = indices.keys().max().map_or(0, |id| id + 1);
+        let mut token_ids = Vec::with_capacity(tokens.len());
+        for token in tokens.iter().filter(|token| !token.is_empty()) {
+            let token_id = match values.get(*token) {
+                Some(index) => *index,
+                None => {
+                    let token_id = next_id;
+                    next_id += 1;
+                    values.insert(token.to_string(), token_id);
+                    indices.insert(token_id, token.to_string());
+                    token_id
+                }
+            };
+            token_ids.push(token_id);
+        }
+        token_ids
     }
 
-    fn token_to_id(&self, token: &str) -> i64;
+    fn _add_special_tokens(tokens: &[&str],
+                           values: &mut HashMap<String, i64>,
+                           indices: &mut HashMap<i64, String>,
+                           special_values: &mut HashMap<String, i64>,
+                           special_indices: &mut HashMap<i64, String>) -> Vec<i64> {
+        let token_ids = Self::_add_tokens(tokens, values, indices);
+        for (token, &token_id) in tokens.iter().filter(|token| !token.is_empty()).zip(token_ids.iter()) {
+            special_values.insert(token.to_string(), token_id);
+            special_indices.insert(token_id, token.to_string());
+        }
+        token_ids
+    }
 
-    fn id_to_token(&self, id: &i64) -> String;
+    /// Adds tokens to the vocabulary, after the existing tokens. Returns the id of each token, tokens already in the vocabulary
+    /// keeping their id. Empty tokens are ignored.
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64>;
+
+    /// Adds tokens to the vocabulary as `add_tokens` does and registers them as special values,
+    /// e.g. domain markers or separators introduced for fine-tuning.
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64>;
+
+    /// Converts a token to its id, falling back to the id of the unknown value for
+    /// out-of-vocabulary tokens. Fails if the unknown value itself is not in the vocabulary.
+    fn try_token_to_id(&self, token: &str) -> Result<i64, TokenizerError> {
+        self._token_to_id(token, self.values(), self.special_values(), self.unknown_value())
+    }
+
+    /// Infallible version of `try_token_to_id`. Vocabularies created with `from_file` always
+    /// contain the unknown value: this only panics for vocabularies built by hand without it.
+    fn token_to_id(&self, token: &str) -> i64 {
+        match self.try_token_to_id(token) {
+            Ok(index) => index,
+            Err(err) => panic!("{}", err)
+        }
+    }
+
+    fn id_to_token(&self, id: &i64) -> String {
+        match self._id_to_token(id, self.indices(), self.special_indices(), self.unknown_value()) {
+            Ok(token) => token,
+            Err(err) => panic!("{}", err)
+        }
+    }
 
     fn convert_tokens_to_ids(&self, tokens: Vec<&str>) -> Vec<i64> {
         tokens.iter().map(|v| self.token_to_id(v)).collect()
     }
+
+    fn try_convert_tokens_to_ids(&self, tokens: Vec<&str>) -> Result<Vec<i64>, TokenizerError> {
+        tokens.iter().map(|v| self.try_token_to_id(v)).collect()
+    }
 }
 
 
+#[derive(Clone)]
 pub struct BaseVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
 }
 
 impl Vocab for BaseVocab {
-    fn unknown_value() -> &'static str { "[UNK]" }
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap::new("[UNK]")
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
 
     fn values(&self) -> &HashMap<String, i64> {
         &self.values
@@ -129,36 +415,31 @@ impl Vocab for BaseVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> BaseVocab {
-        let values = BaseVocab::read_vocab_file(path);
-        let mut special_values = HashMap::new();
-        let unknown_value = BaseVocab::unknown_value();
-        BaseVocab::_register_as_special_value(unknown_value, &values, &mut special_values);
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_tokens(tokens, &mut self.values, &mut self.indices)
+    }
 
-        let indices = swap_key_values(&values);
-        let special_indices = swap_key_values(&special_values);
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_special_tokens(tokens, &mut self.values, &mut self.indices, &mut self.special_values, &mut self.special_indices)
+    }
 
-        BaseVocab { values, indices, unknown_value, special_values, special_indices }
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        BaseVocab::write_vocab_file(path, &self.indices)
     }
 
-    fn token_to_id(&self, token: &str) -> i64 {
-        match self._token_to_id(token, &self.values, &self.special_values, &self.unknown_value) {
-            Ok(index) => index,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<BaseVocab, TokenizerError> {
+        let values = BaseVocab::read_vocab_file(path)?;
+        BaseVocab::from_values_with_special_token_map(values, special_token_map)
     }
 
-    fn id_to_token(&self, id: &i64) -> String {
-        match self._id_to_token(&id, &self.indices, &self.special_indices, &self.unknown_value) {
-            Ok(token) => token,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap) -> Result<BaseVocab, TokenizerError> {
+        let mut special_values = HashMap::new();
+        special_token_map.register_special_values(&values, &mut special_values)?;
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+
+        Ok(BaseVocab { values, indices, special_token_map, special_values, special_indices })
     }
 }
 
@@ -168,7 +449,6 @@ impl Vocab for BaseVocab {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io;
     use std::io::Write;
 
     #[test]
@@ -178,26 +458,51 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = BaseVocab::unknown_value();
+        let special_token_map = BaseVocab::default_special_token_map();
 
 //        When
         let base_vocab = BaseVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
         };
 
 //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
-        assert_eq!(base_vocab.unknown_value, BaseVocab::unknown_value());
+        assert_eq!(base_vocab.unknown_value(), "[UNK]");
         assert_eq!(base_vocab.values, *base_vocab.values());
         assert_eq!(base_vocab.special_values, *base_vocab.special_values());
     }
 
     #[test]
-    fn test_create_object_from_file() -> Result<(), io::Error> {
+    fn test_add_tokens() -> Result<(), TokenizerError> {
+//        Given
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("[UNK]".to_owned(), 1),
+            ("world".to_owned(), 3),
+        ].iter().cloned().collect();
+        let mut base_vocab = BaseVocab::from_values(values)?;
+
+//        When
+        let token_ids = base_vocab.add_tokens(&["<domain>", "hello", "", "<domain>"]);
+        let special_token_ids = base_vocab.add_special_tokens(&["<|sep|>", "world"]);
+
+//        Then
+        assert_eq!(token_ids, vec!(4, 0, 4));
+        assert_eq!(special_token_ids, vec!(5, 3));
+        assert_eq!(base_vocab.token_to_id("<domain>"), 4);
+        assert_eq!(base_vocab.id_to_token(&5), "<|sep|>");
+        assert!(!base_vocab.special_values.contains_key("<domain>"));
+        assert_eq!(base_vocab.special_values.get("<|sep|>"), Some(&5));
+        assert_eq!(base_vocab.special_indices.get(&3).map(String::as_str), Some("world"));
+        assert_eq!(base_vocab.values.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_object_from_file() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "hello \n world \n [UNK] \n !")?;
@@ -214,10 +519,10 @@ mod tests {
         ].iter().cloned().collect();
 
 //        When
-        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
+        assert_eq!(base_vocab.unknown_value(), "[UNK]");
         assert_eq!(base_vocab.values, target_values);
         assert_eq!(base_vocab.special_values, special_values);
         drop(path);
@@ -225,24 +530,26 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn test_create_object_from_file_without_unknown_token() {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new().unwrap();
         write!(vocab_file, "hello \n world \n !").unwrap();
         let path = vocab_file.into_temp_path();
 
-//        When & Then
-        let _base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap());
+//        When
+        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap());
+
+//        Then
+        assert_eq!(base_vocab.err(), Some(TokenizerError::MissingSpecialToken("[UNK]".to_owned())));
     }
 
     #[test]
-    fn test_encode_tokens() -> Result<(), io::Error> {
+    fn test_encode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "hello \n world \n [UNK] \n !")?;
         let path = vocab_file.into_temp_path();
-        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(base_vocab.token_to_id("hello"), 0);
@@ -256,12 +563,12 @@ mod tests {
     }
 
     #[test]
-    fn test_decode_tokens() -> Result<(), io::Error> {
+    fn test_decode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "hello \n world \n [UNK] \n !")?;
         let path = vocab_file.into_temp_path();
-        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(base_vocab.id_to_token(&(0 as i64)), "hello");
@@ -272,4 +579,82 @@ mod tests {
         drop(path);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_read_special_token_map() -> Result<(), TokenizerError> {
+//        Given
+        let mut special_tokens_file = tempfile::NamedTempFile::new()?;
+        write!(special_tokens_file, "{{\"unk_token\": \"<unk>\", \"pad_token\": {{\"content\": \"<pad>\", \"lstrip\": false}}, \
+                                      \"cls_token\": \"<cls>\", \"additional_special_tokens\": [\"<extra>\"]}}")?;
+        let path = special_tokens_file.into_temp_path();
+
+//        When
+        let special_token_map = SpecialTokenMap::from_file(path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(special_token_map, SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            cls_token: Some("<cls>".to_owned()),
+            additional_special_tokens: vec!("<extra>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        });
+        assert_eq!(special_token_map.tokens(), vec!("<unk>", "<pad>", "<cls>", "<extra>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_special_token_map() -> Result<(), TokenizerError> {
+//        Given
+        let special_token_map = SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            sep_token: Some("</s>".to_owned()),
+            additional_special_tokens: vec!("<extra>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        };
+        let path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        special_token_map.to_file(path.to_str().unwrap())?;
+        let reloaded_special_token_map = SpecialTokenMap::from_file(path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(reloaded_special_token_map, special_token_map);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_special_token_map_without_unknown_token() -> Result<(), TokenizerError> {
+//        Given
+        let mut special_tokens_file = tempfile::NamedTempFile::new()?;
+        write!(special_tokens_file, "{{\"pad_token\": \"<pad>\"}}")?;
+        let path = special_tokens_file.into_temp_path();
+
+//        When
+        let special_token_map = SpecialTokenMap::from_file(path.to_str().unwrap());
+
+//        Then
+        assert!(matches!(special_token_map, Err(TokenizerError::VocabularyParsingError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_object_with_special_token_map() -> Result<(), TokenizerError> {
+//        Given
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("<unk>".to_owned(), 1),
+            ("<pad>".to_owned(), 2)
+        ].iter().cloned().collect();
+        let special_token_map = SpecialTokenMap { pad_token: Some("<pad>".to_owned()), ..SpecialTokenMap::new("<unk>") };
+
+//        When
+        let base_vocab = BaseVocab::from_values_with_special_token_map(values, special_token_map)?;
+
+//        Then
+        assert_eq!(base_vocab.unknown_value(), "<unk>");
+        assert_eq!(base_vocab.pad_value(), "<pad>");
+        assert_eq!(base_vocab.token_to_id("oov_value"), 1);
+        assert_eq!(base_vocab.special_values.len(), 2);
+        Ok(())
+    }
+}
@@ -12,26 +12,32 @@
 // limitations under the License.
 
 use std::collections::HashMap;
-use crate::preprocessing::vocab::base_vocab::{Vocab, swap_key_values};
-use std::process;
+use crate::preprocessing::vocab::base_vocab::{Vocab, SpecialTokenMap, expect_role, swap_key_values};
+use crate::preprocessing::error::TokenizerError;
 
+#[derive(Clone)]
 pub struct BertVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
 }
 
-impl BertVocab {
-    pub(crate) fn pad_value() -> &'static str { "[PAD]" }
-    pub(crate) fn sep_value() -> &'static str { "[SEP]" }
-    pub(crate) fn cls_value() -> &'static str { "[CLS]" }
-    pub(crate) fn mask_value() -> &'static str { "[MASK]" }
-}
-
 impl Vocab for BertVocab {
-    fn unknown_value() -> &'static str { "[UNK]" }
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            pad_token: Some("[PAD]".to_owned()),
+            sep_token: Some("[SEP]".to_owned()),
+            cls_token: Some("[CLS]".to_owned()),
+            mask_token: Some("[MASK]".to_owned()),
+            ..SpecialTokenMap::new("[UNK]")
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
 
     fn values(&self) -> &HashMap<String, i64> {
         &self.values
@@ -43,49 +49,33 @@ impl Vocab for BertVocab {
 
     fn special_indices(&self) -> &HashMap<i64, String> {&self.special_indices}
 
-    fn from_file(path: &str) -> BertVocab {
-        let values = BertVocab::read_vocab_file(path);
-        let mut special_values = HashMap::new();
-
-        let unknown_value = BertVocab::unknown_value();
-        BertVocab::_register_as_special_value(unknown_value, &values, &mut special_values);
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_tokens(tokens, &mut self.values, &mut self.indices)
+    }
 
-        let pad_value = BertVocab::pad_value();
-        BertVocab::_register_as_special_value(pad_value, &values, &mut special_values);
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_special_tokens(tokens, &mut self.values, &mut self.indices, &mut self.special_values, &mut self.special_indices)
+    }
 
-        let sep_value = BertVocab::sep_value();
-        BertVocab::_register_as_special_value(sep_value, &values, &mut special_values);
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        BertVocab::write_vocab_file(path, &self.indices)
+    }
 
-        let cls_value = BertVocab::cls_value();
-        BertVocab::_register_as_special_value(cls_value, &values, &mut special_values);
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<BertVocab, TokenizerError> {
+        let values = BertVocab::read_vocab_file(path)?;
+        BertVocab::from_values_with_special_token_map(values, special_token_map)
+    }
 
-        let mask_value = BertVocab::mask_value();
-        BertVocab::_register_as_special_value(mask_value, &values, &mut special_values);
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap) -> Result<BertVocab, TokenizerError> {
+        expect_role(&special_token_map.cls_token, "cls")?;
+        expect_role(&special_token_map.sep_token, "sep")?;
+        let mut special_values = HashMap::new();
+        special_token_map.register_special_values(&values, &mut special_values)?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        BertVocab { values, indices, unknown_value, special_values, special_indices }
-    }
-
-    fn token_to_id(&self, token: &str) -> i64 {
-        match self._token_to_id(token, &self.values, &self.special_values, &self.unknown_value) {
-            Ok(index) => index,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
-    }
-
-    fn id_to_token(&self, id: &i64) -> String {
-        match self._id_to_token(&id, &self.indices, &self.special_indices, &self.unknown_value) {
-            Ok(token) => token,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
+        Ok(BertVocab { values, indices, special_token_map, special_values, special_indices })
     }
 }
 
@@ -95,7 +85,6 @@ impl Vocab for BertVocab {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io;
     use std::io::Write;
 
     #[test]
@@ -105,30 +94,31 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = BertVocab::unknown_value();
+        let special_token_map = BertVocab::default_special_token_map();
 
 //        When
         let base_vocab = BertVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices
         };
 
 //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
-        assert_eq!(base_vocab.unknown_value, BertVocab::unknown_value());
-        assert_eq!(BertVocab::pad_value(), "[PAD]");
-        assert_eq!(BertVocab::sep_value(), "[SEP]");
-        assert_eq!(BertVocab::cls_value(), "[CLS]");
-        assert_eq!(BertVocab::mask_value(), "[MASK]");
+        assert_eq!(base_vocab.unknown_value(), "[UNK]");
+        assert_eq!(base_vocab.pad_value(), "[PAD]");
+        assert_eq!(base_vocab.sep_value(), Some("[SEP]"));
+        assert_eq!(base_vocab.cls_value(), Some("[CLS]"));
+        assert_eq!(base_vocab.mask_value(), Some("[MASK]"));
+        assert_eq!(base_vocab.bos_value(), None);
+        assert_eq!(base_vocab.eos_value(), None);
         assert_eq!(base_vocab.values, *base_vocab.values());
         assert_eq!(base_vocab.special_values, *base_vocab.special_values());
     }
 
     #[test]
-    fn test_create_object_from_file() -> Result<(), io::Error> {
+    fn test_create_object_from_file() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "hello \n world \n [UNK] \n ! \n [CLS] \n [SEP] \n [MASK] \n [PAD]")?;
@@ -153,10 +143,10 @@ mod tests {
         ].iter().cloned().collect();
 
 //        When
-        let base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
+        assert_eq!(base_vocab.unknown_value(), "[UNK]");
         assert_eq!(base_vocab.values, target_values);
         assert_eq!(base_vocab.special_values, special_values);
         drop(path);
@@ -164,24 +154,68 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    fn test_save_object_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let mut vocab_file = tempfile::NamedTempFile::new()?;
+        write!(vocab_file, "hello \n world \n [UNK] \n ! \n [CLS] \n [SEP] \n [MASK] \n [PAD]")?;
+        let path = vocab_file.into_temp_path();
+        let bert_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap())?;
+        let saved_path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        bert_vocab.to_file(saved_path.to_str().unwrap())?;
+        let reloaded_vocab = BertVocab::from_file(saved_path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(std::fs::read_to_string(&saved_path)?, "hello\nworld\n[UNK]\n!\n[CLS]\n[SEP]\n[MASK]\n[PAD]\n");
+        assert_eq!(reloaded_vocab.values, bert_vocab.values);
+        assert_eq!(reloaded_vocab.special_values, bert_vocab.special_values);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_object_with_missing_ids_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let values: HashMap<String, i64> = [
+            ("[UNK]".to_owned(), 0),
+            ("[CLS]".to_owned(), 1),
+            ("[SEP]".to_owned(), 2),
+            ("[MASK]".to_owned(), 3),
+            ("[PAD]".to_owned(), 5)
+        ].iter().cloned().collect();
+        let bert_vocab = BertVocab::from_values(values)?;
+        let saved_path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        let result = bert_vocab.to_file(saved_path.to_str().unwrap());
+
+//        Then
+        assert_eq!(result.err(), Some(TokenizerError::VocabularyParsingError(
+            "id 4 is missing, a line-based vocabulary requires contiguous ids".to_owned())));
+        Ok(())
+    }
+
+    #[test]
     fn test_create_object_from_file_without_unknown_token() {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new().unwrap();
         write!(vocab_file, "hello \n world \n [UNK] \n ! \n [CLS]").unwrap();
         let path = vocab_file.into_temp_path();
 
-//        When & Then
-        let _base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap());
+//        When
+        let base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap());
+
+//        Then
+        assert_eq!(base_vocab.err(), Some(TokenizerError::MissingSpecialToken("[PAD]".to_owned())));
     }
 
     #[test]
-    fn test_encode_tokens() -> Result<(), io::Error> {
+    fn test_encode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "hello \n world \n [UNK] \n ! \n [CLS] \n [SEP] \n [MASK] \n [PAD]")?;
         let path = vocab_file.into_temp_path();
-        let base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(base_vocab.token_to_id("hello"), 0);
@@ -199,12 +233,12 @@ mod tests {
     }
 
     #[test]
-    fn test_decode_tokens() -> Result<(), io::Error> {
+    fn test_decode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "hello \n world \n [UNK] \n ! \n [CLS] \n [SEP] \n [MASK] \n [PAD]")?;
         let path = vocab_file.into_temp_path();
-        let bert_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let bert_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(bert_vocab.id_to_token(&(0 as i64)), "hello");
@@ -219,4 +253,47 @@ mod tests {
         drop(path);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_create_object_with_special_token_map() -> Result<(), TokenizerError> {
+//        Given
+        let mut vocab_file = tempfile::NamedTempFile::new()?;
+        write!(vocab_file, "hello \n world \n <unk> \n ! \n <s> \n </s> \n <mask> \n <pad>")?;
+        let path = vocab_file.into_temp_path();
+        let special_token_map = SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            sep_token: Some("</s>".to_owned()),
+            cls_token: Some("<s>".to_owned()),
+            mask_token: Some("<mask>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        };
+
+//        When
+        let bert_vocab = BertVocab::from_file_with_special_token_map(path.to_str().unwrap(), special_token_map)?;
+
+//        Then
+        assert_eq!(bert_vocab.unknown_value(), "<unk>");
+        assert_eq!(bert_vocab.cls_value(), Some("<s>"));
+        assert_eq!(bert_vocab.sep_value(), Some("</s>"));
+        assert_eq!(bert_vocab.token_to_id("oov_value"), 2);
+        assert_eq!(bert_vocab.special_values.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_object_without_separator_role() {
+//        Given
+        let values: HashMap<String, i64> = [
+            ("[UNK]".to_owned(), 0),
+            ("[CLS]".to_owned(), 1)
+        ].iter().cloned().collect();
+        let special_token_map = SpecialTokenMap { cls_token: Some("[CLS]".to_owned()), ..SpecialTokenMap::new("[UNK]") };
+
+//        When
+        let bert_vocab = BertVocab::from_values_with_special_token_map(values, special_token_map);
+
+//        Then
+        assert_eq!(bert_vocab.err(), Some(TokenizerError::VocabularyParsingError(
+            "the special token map must define a sep token".to_owned())));
+    }
+}
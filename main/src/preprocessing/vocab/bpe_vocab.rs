@@ -11,10 +11,10 @@
 // limitations under the License.
 
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, BufRead};
-use std::ptr;
-use std::mem::ManuallyDrop;
+use std::io::{BufReader, BufRead, BufWriter, Write};
+use crate::preprocessing::vocab::base_vocab::{open_file, create_file};
+use crate::preprocessing::error::TokenizerError;
+use itertools::Itertools;
 
 #[derive(Eq, PartialEq, Hash, Clone, Debug)]
 pub struct BpePairRef<'a> {
@@ -22,36 +22,89 @@ pub struct BpePairRef<'a> {
     pub byte_2: &'a String,
 }
 
+/// Ranked BPE merges. Construct with `from_file` or `from_values`, which also index the merges for the merge engine of the BPE tokenizers.
 pub struct BpePairVocab {
-    pub values: HashMap<(String, String), i64>
+    values: HashMap<(String, String), i64>,
+    /// Integer ids of the symbols appearing in the merges, either as one of the merged symbols or as a merge result.
+    symbol_ids: HashMap<String, u32>,
+    /// Rank and id of the resulting symbol for each merged pair of symbol ids.
+    merges: HashMap<(u32, u32), (i64, u32)>,
 }
 
 impl BpePairVocab {
-    pub fn from_file(path: &str) -> BpePairVocab {
-        let f = File::open(path).expect("Could not open vocabulary file.");
+    pub fn from_values(values: HashMap<(String, String), i64>) -> BpePairVocab {
+        let mut symbol_ids: HashMap<String, u32> = HashMap::new();
+        let mut merges = HashMap::with_capacity(values.len());
+        for ((byte_1, byte_2), &rank) in values.iter() {
+            let id_1 = BpePairVocab::intern(&mut symbol_ids, byte_1.clone());
+            let id_2 = BpePairVocab::intern(&mut symbol_ids, byte_2.clone());
+            let merged_id = BpePairVocab::intern(&mut symbol_ids, format!("{}{}", byte_1, byte_2));
+            merges.insert((id_1, id_2), (rank, merged_id));
+        }
+        BpePairVocab { values, symbol_ids, merges }
+    }
+
+    fn intern(symbol_ids: &mut HashMap<String, u32>, symbol: String) -> u32 {
+        let next_id = symbol_ids.len() as u32;
+        *symbol_ids.entry(symbol).or_insert(next_id)
+    }
+
+    pub fn from_file(path: &str) -> Result<BpePairVocab, TokenizerError> {
+        let f = open_file(path)?;
         let br = BufReader::new(f);
         let mut data = HashMap::new();
         let mut index = 0;
         for line in br.lines().skip(1) {
-            let tuple: Vec<String> = line.unwrap().trim().split(' ').map(|v| v.to_owned()).collect();
+            let tuple: Vec<String> = line?.trim().split(' ').map(|v| v.to_owned()).collect();
             if tuple.len() > 1 {
                 data.insert((tuple[0].clone(), tuple[1].clone()), index);
                 index += 1;
             }
         };
 
-        BpePairVocab { values: data }
+        Ok(BpePairVocab::from_values(data))
     }
 
-    pub fn byte_pair_to_id(&self, byte_pair: &BpePairRef) -> Option<&i64> {
-        unsafe {
-            let byte_1 = byte_pair.byte_1;
-            let byte_2 = byte_pair.byte_2;
-            let k = (ptr::read(byte_1), ptr::read(byte_2));
-            let k = ManuallyDrop::new(k);
-            let v = self.values.get(&k);
-            v
+    /// Writes the merges ordered by rank after a `#version` header, the format read by `from_file`.
+    pub fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        let f = create_file(path)?;
+        let mut bw = BufWriter::new(f);
+        writeln!(bw, "#version: 0.2")?;
+        for (byte_1, byte_2) in self.ranked_merges() {
+            writeln!(bw, "{} {}", byte_1, byte_2)?;
         }
+        bw.flush()?;
+        Ok(())
+    }
+
+    /// Returns the rank of each merged pair. The merges are read-only, as the merge engine indexes them on creation.
+    pub fn values(&self) -> &HashMap<(String, String), i64> {
+        &self.values
+    }
+
+    /// Iterates over the merged pairs, ordered by rank.
+    pub fn ranked_merges(&self) -> impl Iterator<Item=&(String, String)> {
+        self.values
+            .iter()
+            .sorted_by_key(|(_, rank)| **rank)
+            .map(|(pair, _)| pair)
+    }
+
+    /// Returns the id of a symbol, `None` for symbols that are not part of any merge.
+    pub(crate) fn symbol_id(&self, symbol: &str) -> Option<u32> {
+        self.symbol_ids.get(symbol).cloned()
+    }
+
+    /// Returns the rank of the merge of two symbols and the id of the resulting symbol, `None` if the pair is not merged.
+    pub(crate) fn merge(&self, symbol_1: u32, symbol_2: u32) -> Option<(i64, u32)> {
+        self.merges.get(&(symbol_1, symbol_2)).cloned()
+    }
+
+    /// Returns the rank of a pair, looked up through the symbol ids without copying the symbols.
+    pub fn byte_pair_to_id(&self, byte_pair: &BpePairRef) -> Option<&i64> {
+        let id_1 = self.symbol_ids.get(byte_pair.byte_1.as_str())?;
+        let id_2 = self.symbol_ids.get(byte_pair.byte_2.as_str())?;
+        self.merges.get(&(*id_1, *id_2)).map(|(rank, _)| rank)
     }
 }
 
@@ -61,8 +114,6 @@ impl BpePairVocab {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io;
-    use std::io::Write;
 
     #[test]
     fn test_create_pair_vocab() {
@@ -70,16 +121,36 @@ mod tests {
         let values: HashMap<(String, String), i64> = HashMap::new();
 
 //        When
-        let pair_vocab = BpePairVocab {
-            values: values.clone(),
-        };
+        let pair_vocab = BpePairVocab::from_values(values.clone());
 
 //        Then
         assert_eq!(pair_vocab.values, values);
     }
 
     #[test]
-    fn test_create_pair_vocab_from_file() -> Result<(), io::Error> {
+    fn test_merge_symbol_ids() {
+//        Given
+        let values: HashMap<(String, String), i64> = [
+            (("t".to_owned(), "h".to_owned()), 0),
+            (("th".to_owned(), "e</w>".to_owned()), 1),
+        ].iter().cloned().collect();
+        let pair_vocab = BpePairVocab::from_values(values);
+        let t = pair_vocab.symbol_id("t").unwrap();
+        let h = pair_vocab.symbol_id("h").unwrap();
+        let th = pair_vocab.symbol_id("th").unwrap();
+        let e_eow = pair_vocab.symbol_id("e</w>").unwrap();
+        let the_eow = pair_vocab.symbol_id("the</w>").unwrap();
+
+//        When & Then
+        assert_eq!(pair_vocab.symbol_id("x"), None);
+        assert_eq!(pair_vocab.merge(t, h), Some((0, th)));
+        assert_eq!(pair_vocab.merge(th, e_eow), Some((1, the_eow)));
+        assert_eq!(pair_vocab.merge(h, t), None);
+        assert_eq!(pair_vocab.merge(t, e_eow), None);
+    }
+
+    #[test]
+    fn test_create_pair_vocab_from_file() -> Result<(), TokenizerError> {
 //        Given
         let mut merges_file = tempfile::NamedTempFile::new()?;
         write!(merges_file, "#version: 0.1\n t h\na n\ni n\nth e</w>")?;
@@ -92,7 +163,7 @@ mod tests {
         ].iter().cloned().collect();
 
 //        When
-        let pair_vocab = BpePairVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let pair_vocab = BpePairVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        Then
         assert_eq!(pair_vocab.values, target_values);
@@ -101,12 +172,31 @@ mod tests {
     }
 
     #[test]
-    fn test_encode_byte_pairs() -> Result<(), io::Error> {
+    fn test_save_pair_vocab_to_file() -> Result<(), TokenizerError> {
 //        Given
         let mut merges_file = tempfile::NamedTempFile::new()?;
         write!(merges_file, "#version: 0.1\n t h\na n\ni n\nth e</w>")?;
         let path = merges_file.into_temp_path();
-        let pair_vocab = BpePairVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let pair_vocab = BpePairVocab::from_file(path.to_path_buf().to_str().unwrap())?;
+        let saved_path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        pair_vocab.to_file(saved_path.to_str().unwrap())?;
+        let reloaded_pair_vocab = BpePairVocab::from_file(saved_path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(std::fs::read_to_string(&saved_path)?, "#version: 0.2\nt h\na n\ni n\nth e</w>\n");
+        assert_eq!(reloaded_pair_vocab.values, pair_vocab.values);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_byte_pairs() -> Result<(), TokenizerError> {
+//        Given
+        let mut merges_file = tempfile::NamedTempFile::new()?;
+        write!(merges_file, "#version: 0.1\n t h\na n\ni n\nth e</w>")?;
+        let path = merges_file.into_temp_path();
+        let pair_vocab = BpePairVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        Given
         let t = String::from("t");
@@ -137,6 +227,10 @@ mod tests {
             (
                 (a.clone(), e_eow.clone()),
                 None
+            ),
+            (
+                (String::from("x"), t.clone()),
+                None
             )
         ];
 
@@ -12,21 +12,31 @@
 // limitations under the License.
 
 use std::collections::HashMap;
-use crate::preprocessing::vocab::base_vocab::{Vocab, swap_key_values};
-use std::process;
-use std::fs::File;
+use crate::preprocessing::vocab::base_vocab::{Vocab, SpecialTokenMap, swap_key_values, open_file, write_json_vocab_file};
+use crate::preprocessing::error::TokenizerError;
 use std::io::BufReader;
 
+#[derive(Clone)]
 pub struct Gpt2Vocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
 }
 
 impl Vocab for Gpt2Vocab {
-    fn unknown_value() -> &'static str { "<|endoftext|>" }
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            bos_token: Some("<|endoftext|>".to_owned()),
+            eos_token: Some("<|endoftext|>".to_owned()),
+            ..SpecialTokenMap::new("<|endoftext|>")
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
 
     fn values(&self) -> &HashMap<String, i64> {
         &self.values
@@ -40,38 +50,33 @@ impl Vocab for Gpt2Vocab {
 
     fn special_indices(&self) -> &HashMap<i64, String> { &self.special_indices }
 
-    fn from_file(path: &str) -> Gpt2Vocab {
-        let f = File::open(path).expect("Could not open vocabulary file.");
-        let br = BufReader::new(f);
-        let values: HashMap<String, i64> = serde_json::from_reader(br).expect("could not parse vocabulary");
-        let mut special_values = HashMap::new();
-        let unknown_value = Gpt2Vocab::unknown_value();
-        Gpt2Vocab::_register_as_special_value(unknown_value, &values, &mut special_values);
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_tokens(tokens, &mut self.values, &mut self.indices)
+    }
 
-        let indices = swap_key_values(&values);
-        let special_indices = swap_key_values(&special_values);
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_special_tokens(tokens, &mut self.values, &mut self.indices, &mut self.special_values, &mut self.special_indices)
+    }
 
-        Gpt2Vocab { values, indices, unknown_value, special_values, special_indices }
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        write_json_vocab_file(path, &self.values)
     }
 
-    fn token_to_id(&self, token: &str) -> i64 {
-        match self._token_to_id(token, &self.values, &self.special_values, &self.unknown_value) {
-            Ok(index) => index,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<Gpt2Vocab, TokenizerError> {
+        let f = open_file(path)?;
+        let br = BufReader::new(f);
+        let values: HashMap<String, i64> = serde_json::from_reader(br)?;
+        Gpt2Vocab::from_values_with_special_token_map(values, special_token_map)
     }
 
-    fn id_to_token(&self, id: &i64) -> String {
-        match self._id_to_token(&id, &self.indices, &self.special_indices, &self.unknown_value) {
-            Ok(token) => token,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap) -> Result<Gpt2Vocab, TokenizerError> {
+        let mut special_values = HashMap::new();
+        special_token_map.register_special_values(&values, &mut special_values)?;
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+
+        Ok(Gpt2Vocab { values, indices, special_token_map, special_values, special_indices })
     }
 }
 
@@ -82,7 +87,6 @@ impl Vocab for Gpt2Vocab {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io;
     use std::io::Write;
 
     #[test]
@@ -92,26 +96,25 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = Gpt2Vocab::unknown_value();
+        let special_token_map = Gpt2Vocab::default_special_token_map();
 
 //        When
         let gpt2_vocab = Gpt2Vocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_indices,
             special_values,
         };
 
 //        Then
-        assert_eq!(gpt2_vocab.unknown_value, "<|endoftext|>");
-        assert_eq!(gpt2_vocab.unknown_value, Gpt2Vocab::unknown_value());
+        assert_eq!(gpt2_vocab.unknown_value(), "<|endoftext|>");
         assert_eq!(gpt2_vocab.values, *gpt2_vocab.values());
         assert_eq!(gpt2_vocab.special_values, *gpt2_vocab.special_values());
     }
 
     #[test]
-    fn test_create_object_from_file() -> Result<(), io::Error> {
+    fn test_create_object_from_file() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<|endoftext|>\": 2,\n \"!\": 3\n}}")?;
@@ -128,10 +131,10 @@ mod tests {
         ].iter().cloned().collect();
 
 //        When
-        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap());
+        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        Then
-        assert_eq!(gpt2_vocab.unknown_value, "<|endoftext|>");
+        assert_eq!(gpt2_vocab.unknown_value(), "<|endoftext|>");
         assert_eq!(gpt2_vocab.values, target_values);
         assert_eq!(gpt2_vocab.special_values, special_values);
         drop(path);
@@ -139,24 +142,45 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    fn test_save_object_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let mut vocab_file = tempfile::NamedTempFile::new()?;
+        write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<|endoftext|>\": 2,\n \"!\": 3\n}}")?;
+        let path = vocab_file.into_temp_path();
+        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap())?;
+        let saved_path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        gpt2_vocab.to_file(saved_path.to_str().unwrap())?;
+        let reloaded_vocab = Gpt2Vocab::from_file(saved_path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(reloaded_vocab.values, gpt2_vocab.values);
+        assert_eq!(reloaded_vocab.special_values, gpt2_vocab.special_values);
+        Ok(())
+    }
+
+    #[test]
     fn test_create_object_from_file_without_unknown_token() {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new().unwrap();
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"!\": 3\n}}").unwrap();
         let path = vocab_file.into_temp_path();
 
-//        When & Then
-        let _ctrl_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap());
+//        When
+        let ctrl_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap());
+
+//        Then
+        assert_eq!(ctrl_vocab.err(), Some(TokenizerError::MissingSpecialToken("<|endoftext|>".to_owned())));
     }
 
     #[test]
-    fn test_encode_tokens() -> Result<(), io::Error> {
+    fn test_encode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<|endoftext|>\": 2,\n \"!\": 3\n}}")?;
         let path = vocab_file.into_temp_path();
-        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap());
+        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(gpt2_vocab.token_to_id("hello"), 1);
@@ -170,12 +194,12 @@ mod tests {
     }
 
     #[test]
-    fn test_decode_tokens() -> Result<(), io::Error> {
+    fn test_decode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<|endoftext|>\": 2,\n \"!\": 3\n}}")?;
         let path = vocab_file.into_temp_path();
-        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap());
+        let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(gpt2_vocab.id_to_token(&(1 as i64)), "hello");
@@ -14,4 +14,9 @@ pub mod bert_vocab;
 pub mod openai_gpt_vocab;
 pub mod gpt2_vocab;
 pub mod roberta_vocab;
-pub mod bpe_vocab;
\ No newline at end of file
+pub mod bpe_vocab;
+pub mod sentence_piece_model;
+pub mod albert_vocab;
+pub mod xlnet_vocab;
+pub mod t5_vocab;
+pub mod xlm_roberta_vocab;
@@ -12,30 +12,35 @@
 // limitations under the License.
 
 use std::collections::HashMap;
-use crate::preprocessing::vocab::base_vocab::{Vocab, swap_key_values};
-use std::process;
-use std::fs::File;
+use crate::preprocessing::vocab::base_vocab::{Vocab, SpecialTokenMap, expect_role, swap_key_values, open_file, write_json_vocab_file};
+use crate::preprocessing::error::TokenizerError;
 use std::io::BufReader;
 
+#[derive(Clone)]
 pub struct RobertaVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
 }
 
-impl RobertaVocab {
-    pub(crate) fn pad_value() -> &'static str { "<pad>" }
-    pub(crate) fn bos_value() -> &'static str { "<s>" }
-    pub(crate) fn eos_value() -> &'static str { "</s>" }
-    pub(crate) fn sep_value() -> &'static str { "</s>" }
-    pub(crate) fn cls_value() -> &'static str { "<s>" }
-    pub(crate) fn mask_value() -> &'static str { "<mask>" }
-}
-
 impl Vocab for RobertaVocab {
-    fn unknown_value() -> &'static str { "<unk>" }
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            bos_token: Some("<s>".to_owned()),
+            sep_token: Some("</s>".to_owned()),
+            cls_token: Some("<s>".to_owned()),
+            eos_token: Some("</s>".to_owned()),
+            mask_token: Some("<mask>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
 
     fn values(&self) -> &HashMap<String, i64> {
         &self.values
@@ -49,56 +54,35 @@ impl Vocab for RobertaVocab {
 
     fn special_indices(&self) -> &HashMap<i64, String> { &self.special_indices }
 
-    fn from_file(path: &str) -> RobertaVocab {
-        let f = File::open(path).expect("Could not open vocabulary file.");
-        let br = BufReader::new(f);
-        let values: HashMap<String, i64> = serde_json::from_reader(br).expect("could not parse vocabulary");
-        let mut special_values = HashMap::new();
-        let unknown_value = RobertaVocab::unknown_value();
-        RobertaVocab::_register_as_special_value(unknown_value, &values, &mut special_values);
-
-        let pad_value = RobertaVocab::pad_value();
-        RobertaVocab::_register_as_special_value(pad_value, &values, &mut special_values);
-
-        let sep_value = RobertaVocab::sep_value();
-        RobertaVocab::_register_as_special_value(sep_value, &values, &mut special_values);
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_tokens(tokens, &mut self.values, &mut self.indices)
+    }
 
-        let cls_value = RobertaVocab::cls_value();
-        RobertaVocab::_register_as_special_value(cls_value, &values, &mut special_values);
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_special_tokens(tokens, &mut self.values, &mut self.indices, &mut self.special_values, &mut self.special_indices)
+    }
 
-        let mask_value = RobertaVocab::mask_value();
-        RobertaVocab::_register_as_special_value(mask_value, &values, &mut special_values);
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        write_json_vocab_file(path, &self.values)
+    }
 
-        let bos_value = RobertaVocab::bos_value();
-        RobertaVocab::_register_as_special_value(bos_value, &values, &mut special_values);
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<RobertaVocab, TokenizerError> {
+        let f = open_file(path)?;
+        let br = BufReader::new(f);
+        let values: HashMap<String, i64> = serde_json::from_reader(br)?;
+        RobertaVocab::from_values_with_special_token_map(values, special_token_map)
+    }
 
-        let eos_value = RobertaVocab::eos_value();
-        RobertaVocab::_register_as_special_value(eos_value, &values, &mut special_values);
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap) -> Result<RobertaVocab, TokenizerError> {
+        expect_role(&special_token_map.cls_token, "cls")?;
+        expect_role(&special_token_map.sep_token, "sep")?;
+        let mut special_values = HashMap::new();
+        special_token_map.register_special_values(&values, &mut special_values)?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
 
-        RobertaVocab { values, indices, unknown_value, special_values, special_indices }
-    }
-
-    fn token_to_id(&self, token: &str) -> i64 {
-        match self._token_to_id(token, &self.values, &self.special_values, &self.unknown_value) {
-            Ok(index) => index,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
-    }
-
-    fn id_to_token(&self, id: &i64) -> String {
-        match self._id_to_token(&id, &self.indices, &self.special_indices, &self.unknown_value) {
-            Ok(token) => token,
-            Err(err) => {
-                println!("{}", err);
-                process::exit(1);
-            }
-        }
+        Ok(RobertaVocab { values, indices, special_token_map, special_values, special_indices })
     }
 }
 
@@ -109,7 +93,6 @@ impl Vocab for RobertaVocab {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io;
     use std::io::Write;
 
     #[test]
@@ -119,32 +102,31 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = RobertaVocab::unknown_value();
+        let special_token_map = RobertaVocab::default_special_token_map();
 
 //        When
         let roberta_vocab = RobertaVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_indices,
             special_values,
         };
 
 //        Then
-        assert_eq!(roberta_vocab.unknown_value, "<unk>");
-        assert_eq!(RobertaVocab::pad_value(), "<pad>");
-        assert_eq!(RobertaVocab::sep_value(), "</s>");
-        assert_eq!(RobertaVocab::bos_value(), "<s>");
-        assert_eq!(RobertaVocab::eos_value(), "</s>");
-        assert_eq!(RobertaVocab::cls_value(), "<s>");
-        assert_eq!(RobertaVocab::mask_value(), "<mask>");
-        assert_eq!(roberta_vocab.unknown_value, RobertaVocab::unknown_value());
+        assert_eq!(roberta_vocab.unknown_value(), "<unk>");
+        assert_eq!(roberta_vocab.pad_value(), "<pad>");
+        assert_eq!(roberta_vocab.sep_value(), Some("</s>"));
+        assert_eq!(roberta_vocab.bos_value(), Some("<s>"));
+        assert_eq!(roberta_vocab.eos_value(), Some("</s>"));
+        assert_eq!(roberta_vocab.cls_value(), Some("<s>"));
+        assert_eq!(roberta_vocab.mask_value(), Some("<mask>"));
         assert_eq!(roberta_vocab.values, *roberta_vocab.values());
         assert_eq!(roberta_vocab.special_values, *roberta_vocab.special_values());
     }
 
     #[test]
-    fn test_create_object_from_file() -> Result<(), io::Error> {
+    fn test_create_object_from_file() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<unk>\": 2,\n \"!\": 3\n, \"<pad>\": 4\n, \"<s>\": 5\n, \"</s>\": 6\n, \"<mask>\": 7\n}}")?;
@@ -169,10 +151,10 @@ mod tests {
         ].iter().cloned().collect();
 
 //        When
-        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        Then
-        assert_eq!(roberta_vocab.unknown_value, "<unk>");
+        assert_eq!(roberta_vocab.unknown_value(), "<unk>");
         assert_eq!(roberta_vocab.values, target_values);
         assert_eq!(roberta_vocab.special_values, special_values);
         drop(path);
@@ -180,24 +162,26 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn test_create_object_from_file_without_unknown_token() {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new().unwrap();
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"!\": 3\n}}").unwrap();
         let path = vocab_file.into_temp_path();
 
-//        When & Then
-        let _roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap());
+//        When
+        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap());
+
+//        Then
+        assert_eq!(roberta_vocab.err(), Some(TokenizerError::MissingSpecialToken("<unk>".to_owned())));
     }
 
     #[test]
-    fn test_encode_tokens() -> Result<(), io::Error> {
+    fn test_encode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<unk>\": 2,\n \"!\": 3\n, \"<pad>\": 4\n, \"<s>\": 5\n, \"</s>\": 6\n, \"<mask>\": 7\n}}")?;
         let path = vocab_file.into_temp_path();
-        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(roberta_vocab.token_to_id("hello"), 1);
@@ -214,12 +198,12 @@ mod tests {
     }
 
     #[test]
-    fn test_decode_tokens() -> Result<(), io::Error> {
+    fn test_decode_tokens() -> Result<(), TokenizerError> {
 //        Given
         let mut vocab_file = tempfile::NamedTempFile::new()?;
         write!(vocab_file, "{{\"hello\": 1,\n \"world\": 0,\n \"<unk>\": 2,\n \"!\": 3\n, \"<pad>\": 4\n, \"<s>\": 5\n, \"</s>\": 6\n, \"<mask>\": 7\n}}")?;
         let path = vocab_file.into_temp_path();
-        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
 //        When & Then
         assert_eq!(roberta_vocab.id_to_token(&(1 as i64)), "hello");
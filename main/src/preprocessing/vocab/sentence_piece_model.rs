@@ -0,0 +1,625 @@
+// Copyright 2018 Google AI and Google Brain team.
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::{Read, Write, BufWriter};
+use std::iter::once;
+use std::ops::Range;
+use unicode_normalization::UnicodeNormalization;
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::vocab::base_vocab::{open_file, create_file};
+use crate::preprocessing::tokenizer::base_tokenizer::Token;
+use crate::preprocessing::tokenizer::tokenization_utils::clean_text_with_offsets;
+
+/// Marker replacing whitespaces in SentencePiece pieces
+pub const SPIECE_UNDERLINE: char = '▁';
+
+/// Score penalty of the unknown pieces relative to the lowest scoring piece, as in SentencePiece
+const UNKNOWN_PENALTY: f32 = 10.0;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SentencePieceModelType {
+    Unigram,
+    Bpe,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SentencePieceType {
+    Normal,
+    Unknown,
+    Control,
+    UserDefined,
+    Unused,
+    Byte,
+}
+
+impl SentencePieceType {
+    fn from_proto(value: u64) -> Result<SentencePieceType, TokenizerError> {
+        match value {
+            1 => Ok(SentencePieceType::Normal),
+            2 => Ok(SentencePieceType::Unknown),
+            3 => Ok(SentencePieceType::Control),
+            4 => Ok(SentencePieceType::UserDefined),
+            5 => Ok(SentencePieceType::Unused),
+            6 => Ok(SentencePieceType::Byte),
+            _ => Err(invalid_model(&format!("unknown piece type {}", value)))
+        }
+    }
+
+    fn to_proto(self) -> u64 {
+        match self {
+            SentencePieceType::Normal => 1,
+            SentencePieceType::Unknown => 2,
+            SentencePieceType::Control => 3,
+            SentencePieceType::UserDefined => 4,
+            SentencePieceType::Unused => 5,
+            SentencePieceType::Byte => 6,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SentencePiece {
+    pub piece: String,
+    pub score: f32,
+    pub piece_type: SentencePieceType,
+}
+
+/// SentencePiece model read from a `.model` file: the pieces with their scores, the segmentation algorithm
+/// and the whitespace handling options.
+/// The precompiled normalization rules of the model are approximated by a NFKC normalization.
+pub struct SentencePieceModel {
+    pub pieces: Vec<SentencePiece>,
+    pub model_type: SentencePieceModelType,
+    pub add_dummy_prefix: bool,
+    pub remove_extra_whitespaces: bool,
+    pub nfkc_normalization: bool,
+    piece_indices: HashMap<String, usize>,
+    max_piece_length: usize,
+    unknown_score: f32,
+}
+
+impl SentencePieceModel {
+    pub fn new(pieces: Vec<SentencePiece>, model_type: SentencePieceModelType) -> SentencePieceModel {
+        let piece_indices: HashMap<String, usize> = pieces
+            .iter()
+            .enumerate()
+            .filter(|(_, piece)| matches!(piece.piece_type, SentencePieceType::Normal | SentencePieceType::UserDefined))
+            .map(|(index, piece)| (piece.piece.clone(), index))
+            .collect();
+        let max_piece_length = piece_indices.keys().map(|piece| piece.chars().count()).max().unwrap_or(1);
+        let min_score = pieces.iter().map(|piece| piece.score).fold(0.0, f32::min);
+        SentencePieceModel {
+            pieces,
+            model_type,
+            add_dummy_prefix: true,
+            remove_extra_whitespaces: true,
+            nfkc_normalization: true,
+            piece_indices,
+            max_piece_length,
+            unknown_score: min_score - UNKNOWN_PENALTY,
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<SentencePieceModel, TokenizerError> {
+        let mut f = open_file(path)?;
+        let mut bytes = vec!();
+        f.read_to_end(&mut bytes)?;
+        SentencePieceModel::from_bytes(&bytes)
+    }
+
+    /// Parses a serialized `ModelProto`. Only the fields used for the segmentation are read.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SentencePieceModel, TokenizerError> {
+        let mut pieces = vec!();
+        let mut model_type = SentencePieceModelType::Unigram;
+        let (mut add_dummy_prefix, mut remove_extra_whitespaces, mut nfkc_normalization) = (true, true, true);
+
+        let mut reader = ProtoReader::new(bytes);
+        while !reader.is_empty() {
+            match reader.read_field()? {
+                (1, ProtoValue::Bytes(piece)) => pieces.push(parse_piece(piece)?),
+                (2, ProtoValue::Bytes(trainer_spec)) => {
+                    let mut trainer_spec = ProtoReader::new(trainer_spec);
+                    while !trainer_spec.is_empty() {
+                        if let (3, ProtoValue::Varint(value)) = trainer_spec.read_field()? {
+                            model_type = match value {
+                                1 => SentencePieceModelType::Unigram,
+                                2 => SentencePieceModelType::Bpe,
+                                3 => return Err(TokenizerError::UnsupportedComponent("SentencePiece word model".to_owned())),
+                                4 => return Err(TokenizerError::UnsupportedComponent("SentencePiece char model".to_owned())),
+                                _ => return Err(invalid_model(&format!("unknown model type {}", value)))
+                            };
+                        }
+                    }
+                }
+                (3, ProtoValue::Bytes(normalizer_spec)) => {
+                    let mut normalizer_spec = ProtoReader::new(normalizer_spec);
+                    while !normalizer_spec.is_empty() {
+                        match normalizer_spec.read_field()? {
+                            (1, ProtoValue::Bytes(name)) => nfkc_normalization = name != b"identity",
+                            (3, ProtoValue::Varint(value)) => add_dummy_prefix = value != 0,
+                            (4, ProtoValue::Varint(value)) => remove_extra_whitespaces = value != 0,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut model = SentencePieceModel::new(pieces, model_type);
+        model.add_dummy_prefix = add_dummy_prefix;
+        model.remove_extra_whitespaces = remove_extra_whitespaces;
+        model.nfkc_normalization = nfkc_normalization;
+        Ok(model)
+    }
+
+    /// Writes the model as a `ModelProto` holding the pieces, the model type and the whitespace handling options.
+    pub fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        let f = create_file(path)?;
+        let mut bw = BufWriter::new(f);
+        bw.write_all(&self.to_bytes())?;
+        bw.flush()?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = vec!();
+        for piece in &self.pieces {
+            let mut piece_bytes = vec!();
+            write_bytes_field(&mut piece_bytes, 1, piece.piece.as_bytes());
+            write_key(&mut piece_bytes, 2, 5);
+            piece_bytes.extend_from_slice(&piece.score.to_le_bytes());
+            write_varint_field(&mut piece_bytes, 3, piece.piece_type.to_proto());
+            write_bytes_field(&mut output, 1, &piece_bytes);
+        }
+
+        let mut trainer_spec = vec!();
+        write_varint_field(&mut trainer_spec, 3, match self.model_type {
+            SentencePieceModelType::Unigram => 1,
+            SentencePieceModelType::Bpe => 2
+        });
+        write_bytes_field(&mut output, 2, &trainer_spec);
+
+        let mut normalizer_spec = vec!();
+        write_bytes_field(&mut normalizer_spec, 1, if self.nfkc_normalization { b"nmt_nfkc" } else { b"identity" });
+        write_varint_field(&mut normalizer_spec, 3, self.add_dummy_prefix as u64);
+        write_varint_field(&mut normalizer_spec, 4, self.remove_extra_whitespaces as u64);
+        write_bytes_field(&mut output, 3, &normalizer_spec);
+        output
+    }
+
+    /// Piece to id mapping, the id of a piece being its position in the model
+    pub fn values(&self) -> HashMap<String, i64> {
+        self.pieces
+            .iter()
+            .enumerate()
+            .map(|(index, piece)| (piece.piece.clone(), index as i64))
+            .collect()
+    }
+
+    /// Normalizes the text, replacing whitespaces by `▁`, and splits it into pieces
+    pub fn tokenize_to_tokens(&self, token: &Token) -> Vec<Token> {
+        self.tokenize_to_words(token).into_iter().flatten().collect()
+    }
+
+    /// Normalizes the text and splits it into words starting with `▁`, each holding the pieces it is split into
+    pub fn tokenize_to_words(&self, token: &Token) -> Vec<Vec<Token>> {
+        let normalized_token = self.normalize(token);
+        let mut words: Vec<(usize, usize)> = vec!();
+        let mut word_start = 0;
+        for (position, character) in normalized_token.text.char_indices() {
+            if character == SPIECE_UNDERLINE && position > word_start {
+                words.push((word_start, position));
+                word_start = position;
+            }
+        }
+        if word_start < normalized_token.text.len() {
+            words.push((word_start, normalized_token.text.len()));
+        }
+
+        normalized_token
+            .slices(&words)
+            .iter()
+            .map(|word| match self.model_type {
+                SentencePieceModelType::Unigram => self.unigram(word),
+                SentencePieceModelType::Bpe => self.bpe(word)
+            })
+            .collect()
+    }
+
+    fn normalize(&self, token: &Token) -> Token {
+        let mut text = String::with_capacity(token.text.len());
+        let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+        let cleaned_token = clean_text_with_offsets(token, true);
+        for (character, &position) in cleaned_token.text.chars().zip(cleaned_token.reference_offsets.iter()) {
+            if character == ' ' && self.remove_extra_whitespaces && text.ends_with([SPIECE_UNDERLINE, ' ']) {
+                continue;
+            }
+            if self.nfkc_normalization && !character.is_ascii() {
+                for normalized_character in once(character).nfkc() {
+                    text.push(normalized_character);
+                    reference_offsets.push(position);
+                }
+            } else {
+                text.push(character);
+                reference_offsets.push(position);
+            }
+        }
+        if self.remove_extra_whitespaces {
+            let trimmed_length = text.trim_end_matches(' ').len();
+            reference_offsets.truncate(text[..trimmed_length].chars().count());
+            text.truncate(trimmed_length);
+            let leading_spaces = text.len() - text.trim_start_matches(' ').len();
+            reference_offsets.drain(..leading_spaces);
+            text.drain(..leading_spaces);
+        }
+        if self.add_dummy_prefix && !text.is_empty() {
+//            The dummy prefix is attached to the first character of the text
+            text.insert(0, SPIECE_UNDERLINE);
+            reference_offsets.insert(0, reference_offsets[0]);
+        }
+        Token { text: text.replace(' ', &SPIECE_UNDERLINE.to_string()), reference_offsets }
+    }
+
+    /// Viterbi segmentation maximizing the sum of the piece scores. Characters not covered by any piece
+    /// are unknown pieces, consecutive unknown characters being merged.
+    fn unigram(&self, word: &Token) -> Vec<Token> {
+        let char_positions: Vec<usize> = word.text.char_indices().map(|(position, _)| position).chain(once(word.text.len())).collect();
+        let length = char_positions.len() - 1;
+        let mut best_scores = vec![f32::NEG_INFINITY; length + 1];
+        let mut best_starts = vec![0; length + 1];
+        let mut is_unknown = vec![false; length + 1];
+        best_scores[0] = 0.0;
+
+        for end in 1..=length {
+            for start in end.saturating_sub(self.max_piece_length)..end {
+                if best_scores[start] == f32::NEG_INFINITY {
+                    continue;
+                }
+                let (score, unknown) = match self.piece_indices.get(&word.text[char_positions[start]..char_positions[end]]) {
+                    Some(&index) => (best_scores[start] + self.pieces[index].score, false),
+                    None if end - start == 1 => (best_scores[start] + self.unknown_score, true),
+                    None => continue
+                };
+                if score > best_scores[end] {
+                    best_scores[end] = score;
+                    best_starts[end] = start;
+                    is_unknown[end] = unknown;
+                }
+            }
+        }
+
+        let mut ranges: Vec<(usize, usize)> = vec!();
+        let mut end = length;
+        while end > 0 {
+            let start = best_starts[end];
+            match ranges.last_mut() {
+                Some(last_range) if is_unknown[end] && is_unknown[last_range.1] => last_range.0 = start,
+                _ => ranges.push((start, end))
+            }
+            end = start;
+        }
+        ranges.reverse();
+        let byte_ranges: Vec<(usize, usize)> = ranges
+            .into_iter()
+            .map(|(start, end)| (char_positions[start], char_positions[end]))
+            .collect();
+        word.slices(&byte_ranges)
+    }
+
+    /// Merges the adjacent symbols forming the highest scoring piece until no adjacent pair forms a piece
+    fn bpe(&self, word: &Token) -> Vec<Token> {
+        let char_positions: Vec<usize> = word.text.char_indices().map(|(position, _)| position).chain(once(word.text.len())).collect();
+        let mut symbols: Vec<(usize, usize)> = char_positions.windows(2).map(|window| (window[0], window[1])).collect();
+        loop {
+            let mut best_merge: Option<(usize, f32)> = None;
+            for (index, pair) in symbols.windows(2).enumerate() {
+                if let Some(&piece_index) = self.piece_indices.get(&word.text[pair[0].0..pair[1].1]) {
+                    let score = self.pieces[piece_index].score;
+                    if best_merge.is_none_or(|(_, best_score)| score > best_score) {
+                        best_merge = Some((index, score));
+                    }
+                }
+            }
+            match best_merge {
+                Some((index, _)) => {
+                    symbols[index].1 = symbols[index + 1].1;
+                    symbols.remove(index + 1);
+                }
+                None => break
+            }
+        }
+        word.slices(&symbols)
+    }
+}
+
+/// Writes vocabulary pieces as a SentencePiece model without scores, readable by the `from_file` of the
+/// SentencePiece vocabularies. The special values are written as control pieces.
+pub(crate) fn write_sentence_piece_vocab(path: &str,
+                                         pieces: &[&String],
+                                         special_values: &HashMap<String, i64>,
+                                         unknown_value: &str) -> Result<(), TokenizerError> {
+    let pieces = pieces
+        .iter()
+        .map(|&piece| {
+            let piece_type = if piece == unknown_value {
+                SentencePieceType::Unknown
+            } else if special_values.contains_key(piece) {
+                SentencePieceType::Control
+            } else {
+                SentencePieceType::Normal
+            };
+            SentencePiece { piece: piece.clone(), score: 0.0, piece_type }
+        })
+        .collect();
+    SentencePieceModel::new(pieces, SentencePieceModelType::Unigram).to_file(path)
+}
+
+/// Pieces of the vocabulary for a contiguous range of ids
+pub(crate) fn pieces_for_ids(indices: &HashMap<i64, String>, ids: Range<i64>) -> Result<Vec<&String>, TokenizerError> {
+    ids
+        .map(|id| indices.get(&id).ok_or_else(|| TokenizerError::VocabularyParsingError(
+            format!("id {} is missing, a SentencePiece vocabulary requires contiguous ids", id))))
+        .collect()
+}
+
+fn invalid_model(message: &str) -> TokenizerError {
+    TokenizerError::VocabularyParsingError(format!("invalid SentencePiece model: {}", message))
+}
+
+fn parse_piece(bytes: &[u8]) -> Result<SentencePiece, TokenizerError> {
+    let mut reader = ProtoReader::new(bytes);
+    let mut piece = SentencePiece { piece: String::new(), score: 0.0, piece_type: SentencePieceType::Normal };
+    while !reader.is_empty() {
+        match reader.read_field()? {
+            (1, ProtoValue::Bytes(value)) => piece.piece = String::from_utf8(value.to_vec()).map_err(|e| invalid_model(&e.to_string()))?,
+            (2, ProtoValue::Fixed32(value)) => piece.score = f32::from_bits(value),
+            (3, ProtoValue::Varint(value)) => piece.piece_type = SentencePieceType::from_proto(value)?,
+            _ => {}
+        }
+    }
+    Ok(piece)
+}
+
+enum ProtoValue<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Minimal reader of the protocol buffers wire format
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> ProtoReader<'a> {
+        ProtoReader { data, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn read_varint(&mut self) -> Result<u64, TokenizerError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.position).ok_or_else(|| invalid_model("truncated varint"))?;
+            self.position += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_model("varint too long"))
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], TokenizerError> {
+        if self.data.len() - self.position < length {
+            return Err(invalid_model("truncated field"));
+        }
+        let bytes = &self.data[self.position..self.position + length];
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn read_field(&mut self) -> Result<(u64, ProtoValue<'a>), TokenizerError> {
+        let key = self.read_varint()?;
+        let value = match key & 0x7 {
+            0 => ProtoValue::Varint(self.read_varint()?),
+            1 => {
+//                No 64-bit field is used by the segmentation, the value is skipped
+                self.read_bytes(8)?;
+                ProtoValue::Fixed64
+            }
+            2 => {
+                let length = self.read_varint()? as usize;
+                ProtoValue::Bytes(self.read_bytes(length)?)
+            }
+            5 => {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(self.read_bytes(4)?);
+                ProtoValue::Fixed32(u32::from_le_bytes(bytes))
+            }
+            wire_type => return Err(invalid_model(&format!("unsupported wire type {}", wire_type)))
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_key(output: &mut Vec<u8>, field_number: u64, wire_type: u64) {
+    write_varint(output, (field_number << 3) | wire_type);
+}
+
+fn write_varint_field(output: &mut Vec<u8>, field_number: u64, value: u64) {
+    write_key(output, field_number, 0);
+    write_varint(output, value);
+}
+
+fn write_bytes_field(output: &mut Vec<u8>, field_number: u64, value: &[u8]) {
+    write_key(output, field_number, 2);
+    write_varint(output, value.len() as u64);
+    output.extend_from_slice(value);
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::base_tokenizer::Offset;
+
+    fn generate_test_model(model_type: SentencePieceModelType) -> SentencePieceModel {
+        let pieces = [
+            ("<unk>", 0.0, SentencePieceType::Unknown),
+            ("<s>", 0.0, SentencePieceType::Control),
+            ("</s>", 0.0, SentencePieceType::Control),
+            ("▁", -2.0, SentencePieceType::Normal),
+            ("▁the", -3.0, SentencePieceType::Normal),
+            ("▁ear", -4.0, SentencePieceType::Normal),
+            ("th", -5.0, SentencePieceType::Normal),
+            ("▁earth", -6.0, SentencePieceType::Normal),
+            ("e", -7.0, SentencePieceType::Normal),
+            ("a", -7.5, SentencePieceType::Normal),
+            ("r", -8.0, SentencePieceType::Normal),
+            ("t", -8.5, SentencePieceType::Normal),
+            ("h", -9.0, SentencePieceType::Normal),
+            ("▁e", -9.5, SentencePieceType::Normal),
+            ("ar", -10.0, SentencePieceType::Normal),
+            ("▁ea", -10.5, SentencePieceType::Normal),
+            ("▁th", -11.0, SentencePieceType::Normal),
+        ];
+        let pieces = pieces
+            .iter()
+            .map(|&(piece, score, piece_type)| SentencePiece { piece: piece.to_owned(), score, piece_type })
+            .collect();
+        SentencePieceModel::new(pieces, model_type)
+    }
+
+    #[test]
+    fn test_unigram_segmentation() {
+//        Given
+        let model = generate_test_model(SentencePieceModelType::Unigram);
+        let test_tuples = [
+            ("the earth", vec!("▁the", "▁earth")),
+            ("  the   earth ", vec!("▁the", "▁earth")),
+            ("earthe", vec!("▁earth", "e")),
+            ("the 中国 earth", vec!("▁the", "▁", "中国", "▁earth")),
+            ("ｔｈｅ", vec!("▁the")),
+            ("", vec!()),
+        ];
+
+//        When & Then
+        for (source_text, expected_result) in test_tuples.iter() {
+            let tokens: Vec<String> = model.tokenize_to_tokens(&Token::new(source_text)).into_iter().map(|token| token.text).collect();
+            assert_eq!(tokens, *expected_result);
+        }
+    }
+
+    #[test]
+    fn test_bpe_segmentation() {
+//        Given
+        let model = generate_test_model(SentencePieceModelType::Bpe);
+        let test_tuples = [
+            ("the earth", vec!("▁the", "▁earth")),
+            ("tear", vec!("▁", "t", "e", "ar")),
+        ];
+
+//        When & Then
+        for (source_text, expected_result) in test_tuples.iter() {
+            let tokens: Vec<String> = model.tokenize_to_tokens(&Token::new(source_text)).into_iter().map(|token| token.text).collect();
+            assert_eq!(tokens, *expected_result);
+        }
+    }
+
+    #[test]
+    fn test_segmentation_offsets() {
+//        Given
+        let model = generate_test_model(SentencePieceModelType::Unigram);
+
+//        When
+        let tokens = model.tokenize_to_tokens(&Token::new(" the  earth"));
+
+//        Then
+        assert_eq!(tokens.iter().map(|token| token.offset()).collect::<Vec<Option<Offset>>>(),
+                   vec!(Some(Offset { begin: 1, end: 4 }), Some(Offset { begin: 4, end: 11 })));
+    }
+
+    #[test]
+    fn test_parse_model_proto() -> Result<(), TokenizerError> {
+//        Given
+        let bytes: Vec<u8> = vec!(
+//            pieces: {piece: "<unk>", score: 0.0, type: UNKNOWN}, {piece: "▁a", score: -1.5}
+            0x0a, 0x0e, 0x0a, 0x05, b'<', b'u', b'n', b'k', b'>', 0x15, 0x00, 0x00, 0x00, 0x00, 0x18, 0x02,
+            0x0a, 0x0b, 0x0a, 0x04, 0xe2, 0x96, 0x81, b'a', 0x15, 0x00, 0x00, 0xc0, 0xbf,
+//            trainer_spec: {vocab_size: 2, model_type: BPE}
+            0x12, 0x04, 0x20, 0x02, 0x18, 0x02,
+//            normalizer_spec: {name: "identity", add_dummy_prefix: false}
+            0x1a, 0x0c, 0x0a, 0x08, b'i', b'd', b'e', b'n', b't', b'i', b't', b'y', 0x18, 0x00
+        );
+
+//        When
+        let model = SentencePieceModel::from_bytes(&bytes)?;
+
+//        Then
+        assert_eq!(model.pieces, vec!(
+            SentencePiece { piece: "<unk>".to_owned(), score: 0.0, piece_type: SentencePieceType::Unknown },
+            SentencePiece { piece: "▁a".to_owned(), score: -1.5, piece_type: SentencePieceType::Normal },
+        ));
+        assert_eq!(model.model_type, SentencePieceModelType::Bpe);
+        assert!(!model.add_dummy_prefix);
+        assert!(model.remove_extra_whitespaces);
+        assert!(!model.nfkc_normalization);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_model_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let model = generate_test_model(SentencePieceModelType::Bpe);
+        let path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        model.to_file(path.to_str().unwrap())?;
+        let reloaded_model = SentencePieceModel::from_file(path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(reloaded_model.pieces, model.pieces);
+        assert_eq!(reloaded_model.model_type, model.model_type);
+        assert_eq!(reloaded_model.values(), model.values());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_model_proto() {
+//        Given
+        let bytes: Vec<u8> = vec!(0x0a, 0x0b, 0x0a, 0x05, b'<');
+
+//        When
+        let model = SentencePieceModel::from_bytes(&bytes);
+
+//        Then
+        assert_eq!(model.err(), Some(TokenizerError::VocabularyParsingError("invalid SentencePiece model: truncated field".to_owned())));
+    }
+}
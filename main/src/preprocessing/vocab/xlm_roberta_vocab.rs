@@ -0,0 +1,213 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use crate::preprocessing::vocab::base_vocab::{Vocab, SpecialTokenMap, expect_role, swap_key_values};
+use crate::preprocessing::vocab::sentence_piece_model::{SentencePieceModel, write_sentence_piece_vocab, pieces_for_ids};
+use crate::preprocessing::error::TokenizerError;
+
+/// XLM-RoBERTa vocabulary, built from the SentencePiece model with the fairseq ids
+#[derive(Clone)]
+pub struct XLMRobertaVocab {
+    pub values: HashMap<String, i64>,
+    pub indices: HashMap<i64, String>,
+    pub special_token_map: SpecialTokenMap,
+    pub special_values: HashMap<String, i64>,
+    pub special_indices: HashMap<i64, String>,
+    /// Number of ids of the vocabulary when it was created, tokens added afterwards taking the following ids
+    num_model_ids: i64,
+}
+
+impl XLMRobertaVocab {
+    /// Builds the vocabulary with the fairseq ids used by the XLM-RoBERTa models: `<s>`, `<pad>`, `</s>` and `<unk>`
+    /// come first, the other pieces are shifted by one and `<mask>` is appended last.
+    pub fn from_sentence_piece_model(model: &SentencePieceModel) -> Result<XLMRobertaVocab, TokenizerError> {
+        XLMRobertaVocab::from_sentence_piece_model_with_special_token_map(model, XLMRobertaVocab::default_special_token_map())
+    }
+
+    pub fn from_sentence_piece_model_with_special_token_map(model: &SentencePieceModel, special_token_map: SpecialTokenMap)
+                                                            -> Result<XLMRobertaVocab, TokenizerError> {
+        for (token, role) in [(&special_token_map.bos_token, "bos"), (&special_token_map.pad_token, "pad"),
+                              (&special_token_map.eos_token, "eos"), (&special_token_map.mask_token, "mask")].iter() {
+            expect_role(token, role)?;
+        }
+        let mut values: HashMap<String, i64> = model.values()
+            .into_iter()
+            .map(|(piece, id)| (piece, id + 1))
+            .collect();
+        values.insert(special_token_map.bos_token.clone().unwrap(), 0);
+        values.insert(special_token_map.pad_token.clone().unwrap(), 1);
+        values.insert(special_token_map.eos_token.clone().unwrap(), 2);
+        values.insert(special_token_map.unk_token.clone(), 3);
+        values.insert(special_token_map.mask_token.clone().unwrap(), model.pieces.len() as i64 + 1);
+        XLMRobertaVocab::from_values_with_special_token_map(values, special_token_map)
+    }
+}
+
+impl Vocab for XLMRobertaVocab {
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            bos_token: Some("<s>".to_owned()),
+            sep_token: Some("</s>".to_owned()),
+            cls_token: Some("<s>".to_owned()),
+            eos_token: Some("</s>".to_owned()),
+            mask_token: Some("<mask>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
+
+    fn values(&self) -> &HashMap<String, i64> {
+        &self.values
+    }
+
+    fn indices(&self) -> &HashMap<i64, String> { &self.indices }
+
+    fn special_values(&self) -> &HashMap<String, i64> {
+        &self.special_values
+    }
+
+    fn special_indices(&self) -> &HashMap<i64, String> { &self.special_indices }
+
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_tokens(tokens, &mut self.values, &mut self.indices)
+    }
+
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_special_tokens(tokens, &mut self.values, &mut self.indices, &mut self.special_values, &mut self.special_indices)
+    }
+
+    /// Writes the pieces as a SentencePiece model without scores: use `SentencePieceModel::to_file` to save the segmentation model.
+    /// Tokens added with `add_tokens` or `add_special_tokens` are not written.
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+//        Reverts the fairseq ids: the model starts with `<unk>`, `<s>` and `</s>` and has no `<pad>` and `<mask>` pieces
+        let mut pieces = pieces_for_ids(&self.indices, 3..4)?;
+        pieces.extend(pieces_for_ids(&self.indices, 0..1)?);
+        pieces.extend(pieces_for_ids(&self.indices, 2..3)?);
+        pieces.extend(pieces_for_ids(&self.indices, 4..self.num_model_ids - 1)?);
+        write_sentence_piece_vocab(path, &pieces, &self.special_values, self.unknown_value())
+    }
+
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<XLMRobertaVocab, TokenizerError> {
+        XLMRobertaVocab::from_sentence_piece_model_with_special_token_map(&SentencePieceModel::from_file(path)?, special_token_map)
+    }
+
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap) -> Result<XLMRobertaVocab, TokenizerError> {
+        expect_role(&special_token_map.cls_token, "cls")?;
+        expect_role(&special_token_map.sep_token, "sep")?;
+        let mut special_values = HashMap::new();
+        special_token_map.register_special_values(&values, &mut special_values)?;
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+        let num_model_ids = values.len() as i64;
+
+        Ok(XLMRobertaVocab { values, indices, special_token_map, special_values, special_indices, num_model_ids })
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::vocab::sentence_piece_model::{SentencePiece, SentencePieceType, SentencePieceModelType};
+
+    fn generate_test_model(pieces: &[(&str, SentencePieceType)]) -> SentencePieceModel {
+        let pieces = pieces
+            .iter()
+            .map(|&(piece, piece_type)| SentencePiece { piece: piece.to_owned(), score: -1.0, piece_type })
+            .collect();
+        SentencePieceModel::new(pieces, SentencePieceModelType::Unigram)
+    }
+
+    #[test]
+    fn test_create_object_from_sentence_piece_model() -> Result<(), TokenizerError> {
+//        Given
+        let model = generate_test_model(&[
+            ("<unk>", SentencePieceType::Unknown),
+            ("<s>", SentencePieceType::Control),
+            ("</s>", SentencePieceType::Control),
+            ("▁hello", SentencePieceType::Normal),
+            ("▁world", SentencePieceType::Normal),
+        ]);
+
+//        When
+        let vocab = XLMRobertaVocab::from_sentence_piece_model(&model)?;
+
+//        Then
+        assert_eq!(vocab.token_to_id("<s>"), 0);
+        assert_eq!(vocab.token_to_id("<pad>"), 1);
+        assert_eq!(vocab.token_to_id("</s>"), 2);
+        assert_eq!(vocab.token_to_id("<unk>"), 3);
+        assert_eq!(vocab.token_to_id("▁hello"), 4);
+        assert_eq!(vocab.token_to_id("▁world"), 5);
+        assert_eq!(vocab.token_to_id("<mask>"), 6);
+        assert_eq!(vocab.token_to_id("▁unknown"), vocab.token_to_id("<unk>"));
+        assert!(vocab.special_values.contains_key(vocab.pad_value()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_object_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let model = generate_test_model(&[
+            ("<unk>", SentencePieceType::Unknown),
+            ("<s>", SentencePieceType::Control),
+            ("</s>", SentencePieceType::Control),
+            ("▁hello", SentencePieceType::Normal),
+            ("▁world", SentencePieceType::Normal),
+        ]);
+        let vocab = XLMRobertaVocab::from_sentence_piece_model(&model)?;
+        let path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        vocab.to_file(path.to_str().unwrap())?;
+        let reloaded_vocab = XLMRobertaVocab::from_file(path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(reloaded_vocab.values, vocab.values);
+        assert_eq!(reloaded_vocab.special_values, vocab.special_values);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_object_with_added_tokens_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let model = generate_test_model(&[
+            ("<unk>", SentencePieceType::Unknown),
+            ("<s>", SentencePieceType::Control),
+            ("</s>", SentencePieceType::Control),
+            ("▁hello", SentencePieceType::Normal),
+            ("▁world", SentencePieceType::Normal),
+        ]);
+        let original_vocab = XLMRobertaVocab::from_sentence_piece_model(&model)?;
+        let mut vocab = original_vocab.clone();
+        vocab.add_tokens(&["[Domain]"]);
+        vocab.add_special_tokens(&["<sep2>"]);
+        let path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        vocab.to_file(path.to_str().unwrap())?;
+        let reloaded_vocab = XLMRobertaVocab::from_file(path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(reloaded_vocab.values, original_vocab.values);
+        assert_eq!(reloaded_vocab.special_values, original_vocab.special_values);
+        assert_eq!(reloaded_vocab.token_to_id("<mask>"), 6);
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2019 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use crate::preprocessing::vocab::base_vocab::{Vocab, SpecialTokenMap, expect_role, swap_key_values};
+use crate::preprocessing::vocab::sentence_piece_model::{SentencePieceModel, write_sentence_piece_vocab, pieces_for_ids};
+use crate::preprocessing::error::TokenizerError;
+
+/// XLNet vocabulary, the ids being the positions of the pieces in the SentencePiece model
+#[derive(Clone)]
+pub struct XLNetVocab {
+    pub values: HashMap<String, i64>,
+    pub indices: HashMap<i64, String>,
+    pub special_token_map: SpecialTokenMap,
+    pub special_values: HashMap<String, i64>,
+    pub special_indices: HashMap<i64, String>,
+}
+
+impl XLNetVocab {
+    pub fn from_sentence_piece_model(model: &SentencePieceModel) -> Result<XLNetVocab, TokenizerError> {
+        XLNetVocab::from_sentence_piece_model_with_special_token_map(model, XLNetVocab::default_special_token_map())
+    }
+
+    pub fn from_sentence_piece_model_with_special_token_map(model: &SentencePieceModel, special_token_map: SpecialTokenMap)
+                                                            -> Result<XLNetVocab, TokenizerError> {
+        XLNetVocab::from_values_with_special_token_map(model.values(), special_token_map)
+    }
+}
+
+impl Vocab for XLNetVocab {
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            bos_token: Some("<s>".to_owned()),
+            sep_token: Some("<sep>".to_owned()),
+            cls_token: Some("<cls>".to_owned()),
+            eos_token: Some("</s>".to_owned()),
+            mask_token: Some("<mask>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
+
+    fn values(&self) -> &HashMap<String, i64> {
+        &self.values
+    }
+
+    fn indices(&self) -> &HashMap<i64, String> { &self.indices }
+
+    fn special_values(&self) -> &HashMap<String, i64> {
+        &self.special_values
+    }
+
+    fn special_indices(&self) -> &HashMap<i64, String> { &self.special_indices }
+
+    fn add_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_tokens(tokens, &mut self.values, &mut self.indices)
+    }
+
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> Vec<i64> {
+        Self::_add_special_tokens(tokens, &mut self.values, &mut self.indices, &mut self.special_values, &mut self.special_indices)
+    }
+
+    /// Writes the pieces as a SentencePiece model without scores: use `SentencePieceModel::to_file` to save the segmentation model.
+    fn to_file(&self, path: &str) -> Result<(), TokenizerError> {
+        let pieces = pieces_for_ids(&self.indices, 0..self.indices.len() as i64)?;
+        write_sentence_piece_vocab(path, &pieces, &self.special_values, self.unknown_value())
+    }
+
+    fn from_file_with_special_token_map(path: &str, special_token_map: SpecialTokenMap) -> Result<XLNetVocab, TokenizerError> {
+        XLNetVocab::from_sentence_piece_model_with_special_token_map(&SentencePieceModel::from_file(path)?, special_token_map)
+    }
+
+    fn from_values_with_special_token_map(values: HashMap<String, i64>, special_token_map: SpecialTokenMap) -> Result<XLNetVocab, TokenizerError> {
+        expect_role(&special_token_map.cls_token, "cls")?;
+        expect_role(&special_token_map.sep_token, "sep")?;
+        let mut special_values = HashMap::new();
+        special_token_map.register_special_values(&values, &mut special_values)?;
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+
+        Ok(XLNetVocab { values, indices, special_token_map, special_values, special_indices })
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::vocab::sentence_piece_model::{SentencePiece, SentencePieceType, SentencePieceModelType};
+
+    fn generate_test_model(pieces: &[(&str, SentencePieceType)]) -> SentencePieceModel {
+        let pieces = pieces
+            .iter()
+            .map(|&(piece, piece_type)| SentencePiece { piece: piece.to_owned(), score: -1.0, piece_type })
+            .collect();
+        SentencePieceModel::new(pieces, SentencePieceModelType::Unigram)
+    }
+
+    #[test]
+    fn test_create_object_from_sentence_piece_model() -> Result<(), TokenizerError> {
+//        Given
+        let model = generate_test_model(&[
+            ("<unk>", SentencePieceType::Unknown),
+            ("<s>", SentencePieceType::Control),
+            ("</s>", SentencePieceType::Control),
+            ("<cls>", SentencePieceType::Control),
+            ("<sep>", SentencePieceType::Control),
+            ("<pad>", SentencePieceType::Control),
+            ("<mask>", SentencePieceType::Control),
+            ("▁hello", SentencePieceType::Normal),
+        ]);
+
+//        When
+        let vocab = XLNetVocab::from_sentence_piece_model(&model)?;
+
+//        Then
+        assert_eq!(vocab.token_to_id("<cls>"), 3);
+        assert_eq!(vocab.token_to_id("<sep>"), 4);
+        assert_eq!(vocab.token_to_id("▁hello"), 7);
+        assert_eq!(vocab.token_to_id("▁unknown"), vocab.token_to_id("<unk>"));
+        assert!(vocab.special_values.contains_key(vocab.pad_value()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_object_without_special_token() {
+//        Given
+        let model = generate_test_model(&[
+            ("<unk>", SentencePieceType::Unknown),
+            ("▁hello", SentencePieceType::Normal),
+        ]);
+
+//        When
+        let vocab = XLNetVocab::from_sentence_piece_model(&model);
+
+//        Then
+        assert_eq!(vocab.err(), Some(TokenizerError::MissingSpecialToken("<pad>".to_owned())));
+    }
+
+    #[test]
+    fn test_save_object_to_file() -> Result<(), TokenizerError> {
+//        Given
+        let model = generate_test_model(&[
+            ("<unk>", SentencePieceType::Unknown),
+            ("<s>", SentencePieceType::Control),
+            ("</s>", SentencePieceType::Control),
+            ("<cls>", SentencePieceType::Control),
+            ("<sep>", SentencePieceType::Control),
+            ("<pad>", SentencePieceType::Control),
+            ("<mask>", SentencePieceType::Control),
+            ("▁hello", SentencePieceType::Normal),
+        ]);
+        let vocab = XLNetVocab::from_sentence_piece_model(&model)?;
+        let path = tempfile::NamedTempFile::new()?.into_temp_path();
+
+//        When
+        vocab.to_file(path.to_str().unwrap())?;
+        let reloaded_vocab = XLNetVocab::from_file(path.to_str().unwrap())?;
+
+//        Then
+        assert_eq!(reloaded_vocab.values, vocab.values);
+        assert_eq!(reloaded_vocab.special_values, vocab.special_values);
+        Ok(())
+    }
+}
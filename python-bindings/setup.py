@@ -71,6 +71,7 @@ setup(
     packages=["rust_tokenizers"],
     rust_extensions=[RustExtension("rust_tokenizers.rust_tokenizers", "Cargo.toml", debug=False)],
     setup_requires=setup_requires,
+    install_requires=["numpy"],
     test_requires=test_requires,
     include_package_data=True,
     zip_safe=False,
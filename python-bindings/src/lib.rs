@@ -1,7 +1,13 @@
 use pyo3::{PyResult, PyRawObject, Python};
 use pyo3::prelude::*;
 use pyo3::exceptions;
-use rust_tokenizers::{Tokenizer, Vocab, TruncationStrategy, MultiThreadedTokenizer, BertTokenizer, BertVocab, CtrlTokenizer, OpenAiGptVocab, Gpt2Tokenizer, Gpt2Vocab, RobertaTokenizer, RobertaVocab, OpenAiGptTokenizer};
+use pyo3::types::{PyDict, PyAny};
+use numpy::IntoPyArray;
+use ndarray::Array2;
+use rust_tokenizers::{Tokenizer, Vocab, TruncationStrategy, PaddingStrategy, PaddingSide, MultiThreadedTokenizer, BertTokenizer, BertVocab, CtrlTokenizer, OpenAiGptVocab, Gpt2Tokenizer, Gpt2Vocab, RobertaTokenizer, RobertaVocab, OpenAiGptTokenizer, TokenizedInput, TokenizerError, SpecialTokenMap};
+use rust_tokenizers::preprocessing::vocab::bpe_vocab::BpePairVocab;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
 #[pyclass]
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
@@ -16,11 +22,186 @@ pub struct PyTokenizedInput {
     pub overflowing_tokens: Vec<i64>,
     #[pyo3(get)]
     pub num_truncated_tokens: usize,
+    #[pyo3(get)]
+    pub token_offsets: Vec<Option<(usize, usize)>>,
+    #[pyo3(get)]
+    pub attention_mask: Vec<i8>,
+    #[pyo3(get)]
+    pub word_ids: Vec<Option<usize>>,
+    #[pyo3(get)]
+    pub sequence_ids: Vec<Option<usize>>,
+}
+
+impl From<TokenizedInput> for PyTokenizedInput {
+    fn from(tokenized_input: TokenizedInput) -> Self {
+        PyTokenizedInput {
+            token_ids: tokenized_input.token_ids,
+            segment_ids: tokenized_input.segment_ids,
+            special_tokens_mask: tokenized_input.special_tokens_mask,
+            overflowing_tokens: tokenized_input.overflowing_tokens,
+            num_truncated_tokens: tokenized_input.num_truncated_tokens,
+            token_offsets: tokenized_input.token_offsets
+                .into_iter()
+                .map(|offset| offset.map(|offset| (offset.begin, offset.end)))
+                .collect(),
+            attention_mask: tokenized_input.attention_mask,
+            word_ids: tokenized_input.word_ids,
+            sequence_ids: tokenized_input.sequence_ids,
+        }
+    }
+}
+
+/// Collects the encodings of a batch, raising a `ValueError` identifying the first input that could not be encoded
+fn unwrap_batch(results: Vec<Result<TokenizedInput, TokenizerError>>) -> PyResult<Vec<TokenizedInput>> {
+    results
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| result
+            .map_err(|e| exceptions::ValueError::py_err(format!("Input {} could not be encoded: {}", index, e))))
+        .collect()
 }
 
+fn batch_to_py(results: Vec<Result<TokenizedInput, TokenizerError>>) -> PyResult<Vec<PyTokenizedInput>> {
+    Ok(unwrap_batch(results)?
+        .into_iter()
+        .map(PyTokenizedInput::from)
+        .collect())
+}
+
+/// Stacks the padded encodings of a batch into a dictionary of 2-D NumPy arrays (`token_ids`, `attention_mask`,
+/// `segment_ids` and `special_tokens_mask`). The buffers are built in Rust and handed over to NumPy without copy.
+fn batch_to_numpy(results: Vec<Result<TokenizedInput, TokenizerError>>) -> PyResult<PyObject> {
+    let tokenized_inputs = unwrap_batch(results)?;
+    let num_rows = tokenized_inputs.len();
+    let num_columns = tokenized_inputs.first().map_or(0, |tokenized_input| tokenized_input.token_ids.len());
+    if tokenized_inputs.iter().any(|tokenized_input| tokenized_input.token_ids.len() != num_columns) {
+        return Err(exceptions::ValueError::py_err("Encodings of the batch have different lengths and cannot be stacked. Use the `longest`, `max_length` or `multiple_of` padding strategy"));
+    }
+
+    let mut token_ids = Vec::with_capacity(num_rows * num_columns);
+    let mut attention_mask = Vec::with_capacity(num_rows * num_columns);
+    let mut segment_ids = Vec::with_capacity(num_rows * num_columns);
+    let mut special_tokens_mask = Vec::with_capacity(num_rows * num_columns);
+    for tokenized_input in tokenized_inputs {
+        token_ids.extend(tokenized_input.token_ids);
+        attention_mask.extend(tokenized_input.attention_mask);
+        segment_ids.extend(tokenized_input.segment_ids);
+        special_tokens_mask.extend(tokenized_input.special_tokens_mask);
+    }
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let output = PyDict::new(py);
+    output.set_item("token_ids", to_array(token_ids, num_rows, num_columns)?.into_pyarray(py))?;
+    output.set_item("attention_mask", to_array(attention_mask, num_rows, num_columns)?.into_pyarray(py))?;
+    output.set_item("segment_ids", to_array(segment_ids, num_rows, num_columns)?.into_pyarray(py))?;
+    output.set_item("special_tokens_mask", to_array(special_tokens_mask, num_rows, num_columns)?.into_pyarray(py))?;
+    Ok(output.to_object(py))
+}
+
+fn to_array<T>(values: Vec<T>, num_rows: usize, num_columns: usize) -> PyResult<Array2<T>> {
+    Array2::from_shape_vec((num_rows, num_columns), values)
+        .map_err(|e| exceptions::ValueError::py_err(e.to_string()))
+}
+
+/// Runs a batch operation without holding the GIL, letting other Python threads progress while Rust is busy
+fn without_gil<T: Send, F: Send + FnOnce() -> T>(f: F) -> T {
+    let gil = Python::acquire_gil();
+    gil.python().allow_threads(f)
+}
+
+fn parse_truncation(truncation_strategy: &str, head: usize) -> PyResult<TruncationStrategy> {
+    match truncation_strategy {
+        "longest_first" => Ok(TruncationStrategy::LongestFirst),
+        "only_first" => Ok(TruncationStrategy::OnlyFirst),
+        "only_second" => Ok(TruncationStrategy::OnlySecond),
+        "longest_first_left" => Ok(TruncationStrategy::LongestFirstLeft),
+        "only_first_left" => Ok(TruncationStrategy::OnlyFirstLeft),
+        "only_second_left" => Ok(TruncationStrategy::OnlySecondLeft),
+        "head_tail" => Ok(TruncationStrategy::HeadTail { head }),
+        "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
+        _ => Err(exceptions::ValueError::py_err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second`, `longest_first_left`, `only_first_left`, `only_second_left`, `head_tail` or `do_not_truncate`"))
+    }
+}
+
+fn parse_padding(padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize) -> PyResult<(PaddingStrategy, PaddingSide)> {
+    let padding_strategy = match padding_strategy {
+        "do_not_pad" => PaddingStrategy::DoNotPad,
+        "max_length" => PaddingStrategy::MaxLength,
+        "longest" => PaddingStrategy::Longest,
+        "multiple_of" => PaddingStrategy::MultipleOf(pad_to_multiple_of),
+        _ => return Err(exceptions::ValueError::py_err("Invalid padding strategy provided. Must be one of `do_not_pad`, `max_length`, `longest` or `multiple_of`"))
+    };
+    let padding_side = match padding_side {
+        "right" => PaddingSide::Right,
+        "left" => PaddingSide::Left,
+        _ => return Err(exceptions::ValueError::py_err("Invalid padding side provided. Must be one of `right` or `left`"))
+    };
+    Ok((padding_strategy, padding_side))
+}
+
+/// BPE merges as symbol pairs, ordered by rank
+type BpeMerges = Vec<(String, String)>;
+
+/// Pickled state of the WordPiece tokenizers: vocabulary and lower casing setting
+type WordPieceState = (HashMap<String, i64>, bool);
+
+/// Pickled state of the BPE tokenizers: vocabulary, merges and lower casing setting
+type BpeState = (HashMap<String, i64>, BpeMerges, bool);
+
+/// Reads a vocabulary given either as the path to a vocabulary file or as a dictionary mapping tokens to ids
+fn read_vocab<U: Vocab>(vocab: &PyAny) -> PyResult<U> {
+    let vocab = match vocab.extract::<String>() {
+        Ok(path) => U::from_file(&path),
+        Err(_) => U::from_values(vocab.extract::<HashMap<String, i64>>()?)
+    };
+    vocab.map_err(|e| exceptions::ValueError::py_err(e.to_string()))
+}
+
+/// Reads BPE merges given either as the path to a merges file or as a list of symbol pairs ordered by rank
+fn read_merges(merges: &PyAny) -> PyResult<BpePairVocab> {
+    match merges.extract::<String>() {
+        Ok(path) => BpePairVocab::from_file(&path).map_err(|e| exceptions::ValueError::py_err(e.to_string())),
+        Err(_) => Ok(merges_from_list(merges.extract::<BpeMerges>()?))
+    }
+}
+
+fn merges_from_list(merges: BpeMerges) -> BpePairVocab {
+    BpePairVocab::from_values(merges
+        .into_iter()
+        .enumerate()
+        .map(|(rank, pair)| (pair, rank as i64))
+        .collect())
+}
+
+fn merges_to_list(merges: &BpePairVocab) -> BpeMerges {
+    merges
+        .ranked_merges()
+        .cloned()
+        .collect()
+}
+
+fn values_to_vocab<U: Vocab>(values: HashMap<String, i64>) -> PyResult<Arc<U>> {
+    U::from_values(values)
+        .map(Arc::new)
+        .map_err(|e| exceptions::ValueError::py_err(e.to_string()))
+}
+
+/// Vocabulary made of the default special tokens only. Unpickling constructs the tokenizer from it before
+/// `__setstate__` restores the actual vocabulary, so that the vocabulary is not pickled twice.
+fn placeholder_vocab<U: Vocab>() -> HashMap<String, i64> {
+    U::default_special_token_map()
+        .tokens()
+        .into_iter()
+        .enumerate()
+        .map(|(id, token)| (token.to_owned(), id as i64))
+        .collect()
+}
 
 trait PyTokenizer<T: Tokenizer<U>, U: Vocab> {
-    fn tokenizer(&self) -> &T;
+    /// Returns the current tokenizer. Python classes hold it behind a lock and `__setstate__` replaces it as a whole, so that
+    /// batch operations running without the GIL keep using the tokenizer they started with.
+    fn tokenizer(&self) -> Arc<T>;
 
     fn tokenize(&self, text: &str) -> PyResult<Vec<String>> {
         Ok(self.tokenizer().tokenize(&text))
@@ -30,173 +211,125 @@ trait PyTokenizer<T: Tokenizer<U>, U: Vocab> {
         Ok(self.tokenizer().tokenize_list(text_list))
     }
 
-    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        let truncation_strategy = match truncation_strategy {
-            "longest_first" => Ok(TruncationStrategy::LongestFirst),
-            "only_first" => Ok(TruncationStrategy::OnlyFirst),
-            "only_second" => Ok(TruncationStrategy::OnlySecond),
-            "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
-            _ => Err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second` or `do_not_truncate`")
-        };
-        match truncation_strategy {
-            Ok(truncation_strategy) => {
-                let tokenized_input = self.tokenizer().encode(&text, None, max_len, &truncation_strategy, stride);
-                Ok(PyTokenizedInput {
-                    token_ids: tokenized_input.token_ids,
-                    segment_ids: tokenized_input.segment_ids,
-                    special_tokens_mask: tokenized_input.special_tokens_mask,
-                    overflowing_tokens: tokenized_input.overflowing_tokens,
-                    num_truncated_tokens: tokenized_input.num_truncated_tokens,
-                })
-            }
-            Err(e) => Err(exceptions::ValueError::py_err(e))
-        }
+    fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<String> {
+        Ok(self.tokenizer().decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces))
     }
 
-    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        let truncation_strategy = match truncation_strategy {
-            "longest_first" => Ok(TruncationStrategy::LongestFirst),
-            "only_first" => Ok(TruncationStrategy::OnlyFirst),
-            "only_second" => Ok(TruncationStrategy::OnlySecond),
-            "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
-            _ => Err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second` or `do_not_truncate`")
-        };
-        match truncation_strategy {
-            Ok(truncation_strategy) => {
-                let tokenized_input = self.tokenizer().encode(&text_a, Some(&text_b), max_len, &truncation_strategy, stride);
-                Ok(PyTokenizedInput {
-                    token_ids: tokenized_input.token_ids,
-                    segment_ids: tokenized_input.segment_ids,
-                    special_tokens_mask: tokenized_input.special_tokens_mask,
-                    overflowing_tokens: tokenized_input.overflowing_tokens,
-                    num_truncated_tokens: tokenized_input.num_truncated_tokens,
-                })
-            }
-            Err(e) => Err(exceptions::ValueError::py_err(e))
-        }
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        Ok(self.tokenizer().decode_list(token_ids_list, skip_special_tokens, clean_up_tokenization_spaces))
+    }
+
+    fn convert_tokens_to_ids(&self, tokens: Vec<String>) -> PyResult<Vec<i64>> {
+        self.tokenizer().try_convert_tokens_to_ids(&tokens)
+            .map_err(|e| exceptions::ValueError::py_err(e.to_string()))
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> PyResult<String> {
+        Ok(self.tokenizer().convert_tokens_to_string(tokens))
+    }
+
+    fn token_to_id(&self, token: &str) -> PyResult<i64> {
+        self.tokenizer().vocab().try_token_to_id(token)
+            .map_err(|e| exceptions::ValueError::py_err(e.to_string()))
+    }
+
+    fn id_to_token(&self, id: i64) -> PyResult<String> {
+        Ok(self.tokenizer().vocab().id_to_token(&id))
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        let truncation_strategy = match truncation_strategy {
-            "longest_first" => Ok(TruncationStrategy::LongestFirst),
-            "only_first" => Ok(TruncationStrategy::OnlyFirst),
-            "only_second" => Ok(TruncationStrategy::OnlySecond),
-            "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
-            _ => Err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second` or `do_not_truncate`")
-        };
-        match truncation_strategy {
-            Ok(truncation_strategy) => {
-                let tokenized_inputs = self.tokenizer().encode_list(text_list, max_len, &truncation_strategy, stride);
-                Ok(tokenized_inputs
-                    .into_iter()
-                    .map(|tokenized_input| PyTokenizedInput {
-                        token_ids: tokenized_input.token_ids,
-                        segment_ids: tokenized_input.segment_ids,
-                        special_tokens_mask: tokenized_input.special_tokens_mask,
-                        overflowing_tokens: tokenized_input.overflowing_tokens,
-                        num_truncated_tokens: tokenized_input.num_truncated_tokens,
-                    })
-                    .collect::<Vec<PyTokenizedInput>>())
-            }
-            Err(e) => Err(exceptions::ValueError::py_err(e))
+    fn vocab_size(&self) -> PyResult<usize> {
+        Ok(self.tokenizer().vocab().values().len())
+    }
+
+    fn special_token_map(&self) -> SpecialTokenMap {
+        self.tokenizer().vocab().special_token_map().clone()
+    }
+
+    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        match self.tokenizer().try_encode(&text, None, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side) {
+            Ok(tokenized_input) => Ok(PyTokenizedInput::from(tokenized_input)),
+            Err(e) => Err(exceptions::ValueError::py_err(e.to_string()))
         }
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        let truncation_strategy = match truncation_strategy {
-            "longest_first" => Ok(TruncationStrategy::LongestFirst),
-            "only_first" => Ok(TruncationStrategy::OnlyFirst),
-            "only_second" => Ok(TruncationStrategy::OnlySecond),
-            "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
-            _ => Err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second` or `do_not_truncate`")
-        };
-        match truncation_strategy {
-            Ok(truncation_strategy) => {
-                let tokenized_inputs = self.tokenizer().encode_pair_list(text_list, max_len, &truncation_strategy, stride);
-                Ok(tokenized_inputs
-                    .into_iter()
-                    .map(|tokenized_input| PyTokenizedInput {
-                        token_ids: tokenized_input.token_ids,
-                        segment_ids: tokenized_input.segment_ids,
-                        special_tokens_mask: tokenized_input.special_tokens_mask,
-                        overflowing_tokens: tokenized_input.overflowing_tokens,
-                        num_truncated_tokens: tokenized_input.num_truncated_tokens,
-                    })
-                    .collect::<Vec<PyTokenizedInput>>())
-            }
-            Err(e) => Err(exceptions::ValueError::py_err(e))
+    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        match self.tokenizer().try_encode(&text_a, Some(&text_b), max_len, &truncation_strategy, stride, &padding_strategy, &padding_side) {
+            Ok(tokenized_input) => Ok(PyTokenizedInput::from(tokenized_input)),
+            Err(e) => Err(exceptions::ValueError::py_err(e.to_string()))
         }
     }
+
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        batch_to_py(self.tokenizer().try_encode_list(text_list, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side))
+    }
+
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        batch_to_py(self.tokenizer().try_encode_pair_list(text_list, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side))
+    }
 }
 
 trait PyMultiThreadTokenizer<T: MultiThreadedTokenizer<U>, U: Vocab>
     where Self: PyTokenizer<T, U> {
     fn tokenize_list(&self, text_list: Vec<&str>) -> PyResult<Vec<Vec<String>>> {
-        Ok(MultiThreadedTokenizer::tokenize_list(self.tokenizer(), text_list))
-    }
-
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        let truncation_strategy = match truncation_strategy {
-            "longest_first" => Ok(TruncationStrategy::LongestFirst),
-            "only_first" => Ok(TruncationStrategy::OnlyFirst),
-            "only_second" => Ok(TruncationStrategy::OnlySecond),
-            "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
-            _ => Err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second` or `do_not_truncate`")
-        };
-        match truncation_strategy {
-            Ok(truncation_strategy) => {
-                let tokenized_inputs = MultiThreadedTokenizer::encode_list(self.tokenizer(), text_list, max_len, &truncation_strategy, stride);
-                Ok(tokenized_inputs
-                    .into_iter()
-                    .map(|tokenized_input| PyTokenizedInput {
-                        token_ids: tokenized_input.token_ids,
-                        segment_ids: tokenized_input.segment_ids,
-                        special_tokens_mask: tokenized_input.special_tokens_mask,
-                        overflowing_tokens: tokenized_input.overflowing_tokens,
-                        num_truncated_tokens: tokenized_input.num_truncated_tokens,
-                    })
-                    .collect::<Vec<PyTokenizedInput>>())
-            }
-            Err(e) => Err(exceptions::ValueError::py_err(e))
-        }
+        let tokenizer = self.tokenizer();
+        Ok(without_gil(|| MultiThreadedTokenizer::tokenize_list(tokenizer.as_ref(), text_list)))
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        let truncation_strategy = match truncation_strategy {
-            "longest_first" => Ok(TruncationStrategy::LongestFirst),
-            "only_first" => Ok(TruncationStrategy::OnlyFirst),
-            "only_second" => Ok(TruncationStrategy::OnlySecond),
-            "do_not_truncate" => Ok(TruncationStrategy::DoNotTruncate),
-            _ => Err("Invalid truncation strategy provided. Must be one of `longest_first`, `only_first`, `only_second` or `do_not_truncate`")
-        };
-        match truncation_strategy {
-            Ok(truncation_strategy) => {
-                let tokenized_inputs = MultiThreadedTokenizer::encode_pair_list(self.tokenizer(), text_list, max_len, &truncation_strategy, stride);
-                Ok(tokenized_inputs
-                    .into_iter()
-                    .map(|tokenized_input| PyTokenizedInput {
-                        token_ids: tokenized_input.token_ids,
-                        segment_ids: tokenized_input.segment_ids,
-                        special_tokens_mask: tokenized_input.special_tokens_mask,
-                        overflowing_tokens: tokenized_input.overflowing_tokens,
-                        num_truncated_tokens: tokenized_input.num_truncated_tokens,
-                    })
-                    .collect::<Vec<PyTokenizedInput>>())
-            }
-            Err(e) => Err(exceptions::ValueError::py_err(e))
-        }
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        let tokenizer = self.tokenizer();
+        Ok(without_gil(|| MultiThreadedTokenizer::decode_list(tokenizer.as_ref(), token_ids_list, skip_special_tokens, clean_up_tokenization_spaces)))
+    }
+
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        let tokenizer = self.tokenizer();
+        let results = without_gil(|| MultiThreadedTokenizer::try_encode_list(tokenizer.as_ref(), text_list, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side));
+        batch_to_py(results)
+    }
+
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        let tokenizer = self.tokenizer();
+        let results = without_gil(|| MultiThreadedTokenizer::try_encode_pair_list(tokenizer.as_ref(), text_list, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side));
+        batch_to_py(results)
+    }
+
+    fn encode_list_numpy(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        let tokenizer = self.tokenizer();
+        let results = without_gil(|| MultiThreadedTokenizer::try_encode_list(tokenizer.as_ref(), text_list, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side));
+        batch_to_numpy(results)
+    }
+
+    fn encode_pair_list_numpy(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        let (padding_strategy, padding_side) = parse_padding(padding_strategy, padding_side, pad_to_multiple_of)?;
+        let truncation_strategy = parse_truncation(truncation_strategy, head)?;
+        let tokenizer = self.tokenizer();
+        let results = without_gil(|| MultiThreadedTokenizer::try_encode_pair_list(tokenizer.as_ref(), text_list, max_len, &truncation_strategy, stride, &padding_strategy, &padding_side));
+        batch_to_numpy(results)
     }
 }
 
 
 #[pyclass(module = "rust_tokenizers")]
 struct PyBertTokenizer {
-    tokenizer: BertTokenizer,
+    tokenizer: RwLock<Arc<BertTokenizer>>,
 }
 
 impl PyTokenizer<BertTokenizer, BertVocab> for PyBertTokenizer {
-    fn tokenizer(&self) -> &BertTokenizer {
-        &self.tokenizer
+    fn tokenizer(&self) -> Arc<BertTokenizer> {
+        self.tokenizer.read().unwrap().clone()
     }
 }
 
@@ -205,10 +338,27 @@ impl PyMultiThreadTokenizer<BertTokenizer, BertVocab> for PyBertTokenizer {}
 #[pymethods]
 impl PyBertTokenizer {
     #[new]
-    fn new(obj: &PyRawObject, path: String, do_lower_case: bool) {
-        obj.init(PyBertTokenizer {
-            tokenizer: BertTokenizer::from_file(path.as_str(), do_lower_case),
-        });
+    fn new(obj: &PyRawObject, vocab: &PyAny, do_lower_case: bool) -> PyResult<()> {
+        let vocab = read_vocab::<BertVocab>(vocab)?;
+        let tokenizer = BertTokenizer::from_existing_vocab(Arc::new(vocab), do_lower_case);
+        obj.init(PyBertTokenizer { tokenizer: RwLock::new(Arc::new(tokenizer)) });
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> PyResult<WordPieceState> {
+        Ok((placeholder_vocab::<BertVocab>(), false))
+    }
+
+    fn __getstate__(&self) -> PyResult<WordPieceState> {
+        let tokenizer = self.tokenizer();
+        Ok((Tokenizer::vocab(tokenizer.as_ref()).values().clone(), tokenizer.lower_case()))
+    }
+
+    fn __setstate__(&self, state: WordPieceState) -> PyResult<()> {
+        let (vocab, do_lower_case) = state;
+        let tokenizer = BertTokenizer::from_existing_vocab(values_to_vocab(vocab)?, do_lower_case);
+        *self.tokenizer.write().unwrap() = Arc::new(tokenizer);
+        Ok(())
     }
 
     fn tokenize(&self, text: &str) -> PyResult<Vec<String>> {
@@ -219,41 +369,146 @@ impl PyBertTokenizer {
         <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::tokenize_list(&self, text_list)
     }
 
-    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<BertTokenizer, BertVocab>>::encode(&self, text, max_len, truncation_strategy, stride)
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::encode(&self, text, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list_numpy(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::encode_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list_numpy(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::encode_pair_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<String> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::decode(&self, token_ids, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::decode_list(&self, token_ids_list, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    fn convert_tokens_to_ids(&self, tokens: Vec<String>) -> PyResult<Vec<i64>> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::convert_tokens_to_ids(&self, tokens)
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> PyResult<String> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::convert_tokens_to_string(&self, tokens)
+    }
+
+    fn token_to_id(&self, token: &str) -> PyResult<i64> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::token_to_id(&self, token)
+    }
+
+    fn id_to_token(&self, id: i64) -> PyResult<String> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::id_to_token(&self, id)
+    }
+
+    #[getter]
+    fn vocab_size(&self) -> PyResult<usize> {
+        <Self as PyTokenizer<BertTokenizer, BertVocab>>::vocab_size(&self)
+    }
+
+    #[getter]
+    fn unk_token(&self) -> PyResult<String> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).unk_token.clone())
+    }
+
+    #[getter]
+    fn pad_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).pad_token.clone())
+    }
+
+    #[getter]
+    fn bos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).bos_token.clone())
+    }
+
+    #[getter]
+    fn eos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).eos_token.clone())
     }
 
-    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<BertTokenizer, BertVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride)
+    #[getter]
+    fn sep_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).sep_token.clone())
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[getter]
+    fn cls_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).cls_token.clone())
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyMultiThreadTokenizer<BertTokenizer, BertVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[getter]
+    fn mask_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).mask_token.clone())
+    }
+
+    #[getter]
+    fn additional_special_tokens(&self) -> PyResult<Vec<String>> {
+        Ok(<Self as PyTokenizer<BertTokenizer, BertVocab>>::special_token_map(&self).additional_special_tokens.clone())
     }
 }
 
 #[pyclass(module = "rust_tokenizers")]
 struct PyCtrlTokenizer {
-    tokenizer: CtrlTokenizer,
+    tokenizer: RwLock<Arc<CtrlTokenizer>>,
 }
 
 impl PyTokenizer<CtrlTokenizer, OpenAiGptVocab> for PyCtrlTokenizer {
-    fn tokenizer(&self) -> &CtrlTokenizer {
-        &self.tokenizer
+    fn tokenizer(&self) -> Arc<CtrlTokenizer> {
+        self.tokenizer.read().unwrap().clone()
     }
 }
 
+impl PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab> for PyCtrlTokenizer {}
+
 #[pymethods]
 impl PyCtrlTokenizer {
     #[new]
-    fn new(obj: &PyRawObject, vocab_path: String, merges_path: String, do_lower_case: bool) {
-        obj.init(PyCtrlTokenizer {
-            tokenizer: CtrlTokenizer::from_file(vocab_path.as_str(), merges_path.as_str(), do_lower_case),
-        });
+    fn new(obj: &PyRawObject, vocab: &PyAny, merges: &PyAny, do_lower_case: bool) -> PyResult<()> {
+        let vocab = read_vocab::<OpenAiGptVocab>(vocab)?;
+        let merges = read_merges(merges)?;
+        let tokenizer = CtrlTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), do_lower_case);
+        obj.init(PyCtrlTokenizer { tokenizer: RwLock::new(Arc::new(tokenizer)) });
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> PyResult<BpeState> {
+        Ok((placeholder_vocab::<OpenAiGptVocab>(), vec!(), false))
+    }
+
+    fn __getstate__(&self) -> PyResult<BpeState> {
+        let tokenizer = self.tokenizer();
+        Ok((Tokenizer::vocab(tokenizer.as_ref()).values().clone(), merges_to_list(tokenizer.merges()), tokenizer.lower_case()))
+    }
+
+    fn __setstate__(&self, state: BpeState) -> PyResult<()> {
+        let (vocab, merges, do_lower_case) = state;
+        let tokenizer = CtrlTokenizer::from_existing_vocab_and_merges(values_to_vocab(vocab)?, Arc::new(merges_from_list(merges)), do_lower_case);
+        *self.tokenizer.write().unwrap() = Arc::new(tokenizer);
+        Ok(())
     }
 
     fn tokenize(&self, text: &str) -> PyResult<Vec<String>> {
@@ -261,45 +516,150 @@ impl PyCtrlTokenizer {
     }
 
     fn tokenize_list(&self, text_list: Vec<&str>) -> PyResult<Vec<Vec<String>>> {
-        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::tokenize_list(&self, text_list)
+        <Self as PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab>>::tokenize_list(&self, text_list)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode(&self, text, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list_numpy(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list_numpy(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_pair_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<String> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::decode(&self, token_ids, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        <Self as PyMultiThreadTokenizer<CtrlTokenizer, OpenAiGptVocab>>::decode_list(&self, token_ids_list, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    fn convert_tokens_to_ids(&self, tokens: Vec<String>) -> PyResult<Vec<i64>> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::convert_tokens_to_ids(&self, tokens)
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> PyResult<String> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::convert_tokens_to_string(&self, tokens)
     }
 
-    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode(&self, text, max_len, truncation_strategy, stride)
+    fn token_to_id(&self, token: &str) -> PyResult<i64> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::token_to_id(&self, token)
     }
 
-    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride)
+    fn id_to_token(&self, id: i64) -> PyResult<String> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::id_to_token(&self, id)
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[getter]
+    fn vocab_size(&self) -> PyResult<usize> {
+        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::vocab_size(&self)
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[getter]
+    fn unk_token(&self) -> PyResult<String> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).unk_token.clone())
+    }
+
+    #[getter]
+    fn pad_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).pad_token.clone())
+    }
+
+    #[getter]
+    fn bos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).bos_token.clone())
+    }
+
+    #[getter]
+    fn eos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).eos_token.clone())
+    }
+
+    #[getter]
+    fn sep_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).sep_token.clone())
+    }
+
+    #[getter]
+    fn cls_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).cls_token.clone())
+    }
+
+    #[getter]
+    fn mask_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).mask_token.clone())
+    }
+
+    #[getter]
+    fn additional_special_tokens(&self) -> PyResult<Vec<String>> {
+        Ok(<Self as PyTokenizer<CtrlTokenizer, OpenAiGptVocab>>::special_token_map(&self).additional_special_tokens.clone())
     }
 }
 
 
 #[pyclass(module = "rust_tokenizers")]
 struct PyGpt2Tokenizer {
-    tokenizer: Gpt2Tokenizer,
+    tokenizer: RwLock<Arc<Gpt2Tokenizer>>,
 }
 
 impl PyTokenizer<Gpt2Tokenizer, Gpt2Vocab> for PyGpt2Tokenizer {
-    fn tokenizer(&self) -> &Gpt2Tokenizer {
-        &self.tokenizer
+    fn tokenizer(&self) -> Arc<Gpt2Tokenizer> {
+        self.tokenizer.read().unwrap().clone()
     }
 }
 
+impl PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab> for PyGpt2Tokenizer {}
+
 #[pymethods]
 impl PyGpt2Tokenizer {
     #[new]
-    fn new(obj: &PyRawObject, vocab_path: String, merges_path: String, do_lower_case: bool) {
-        obj.init(PyGpt2Tokenizer {
-            tokenizer: Gpt2Tokenizer::from_file(vocab_path.as_str(), &merges_path.as_str(), do_lower_case),
-        });
+    fn new(obj: &PyRawObject, vocab: &PyAny, merges: &PyAny, do_lower_case: bool) -> PyResult<()> {
+        let vocab = read_vocab::<Gpt2Vocab>(vocab)?;
+        let merges = read_merges(merges)?;
+        let tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), do_lower_case);
+        obj.init(PyGpt2Tokenizer { tokenizer: RwLock::new(Arc::new(tokenizer)) });
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> PyResult<BpeState> {
+        Ok((placeholder_vocab::<Gpt2Vocab>(), vec!(), false))
+    }
+
+    fn __getstate__(&self) -> PyResult<BpeState> {
+        let tokenizer = self.tokenizer();
+        Ok((Tokenizer::vocab(tokenizer.as_ref()).values().clone(), merges_to_list(tokenizer.merges()), tokenizer.lower_case()))
+    }
+
+    fn __setstate__(&self, state: BpeState) -> PyResult<()> {
+        let (vocab, merges, do_lower_case) = state;
+        let tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(values_to_vocab(vocab)?, Arc::new(merges_from_list(merges)), do_lower_case);
+        *self.tokenizer.write().unwrap() = Arc::new(tokenizer);
+        Ok(())
     }
 
     fn tokenize(&self, text: &str) -> PyResult<Vec<String>> {
@@ -307,44 +667,149 @@ impl PyGpt2Tokenizer {
     }
 
     fn tokenize_list(&self, text_list: Vec<&str>) -> PyResult<Vec<Vec<String>>> {
-        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::tokenize_list(&self, text_list)
+        <Self as PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::tokenize_list(&self, text_list)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode(&self, text, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list_numpy(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list_numpy(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_pair_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<String> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::decode(&self, token_ids, skip_special_tokens, clean_up_tokenization_spaces)
     }
 
-    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode(&self, text, max_len, truncation_strategy, stride)
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        <Self as PyMultiThreadTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::decode_list(&self, token_ids_list, skip_special_tokens, clean_up_tokenization_spaces)
     }
 
-    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride)
+    fn convert_tokens_to_ids(&self, tokens: Vec<String>) -> PyResult<Vec<i64>> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::convert_tokens_to_ids(&self, tokens)
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride)
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> PyResult<String> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::convert_tokens_to_string(&self, tokens)
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride)
+    fn token_to_id(&self, token: &str) -> PyResult<i64> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::token_to_id(&self, token)
+    }
+
+    fn id_to_token(&self, id: i64) -> PyResult<String> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::id_to_token(&self, id)
+    }
+
+    #[getter]
+    fn vocab_size(&self) -> PyResult<usize> {
+        <Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::vocab_size(&self)
+    }
+
+    #[getter]
+    fn unk_token(&self) -> PyResult<String> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).unk_token.clone())
+    }
+
+    #[getter]
+    fn pad_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).pad_token.clone())
+    }
+
+    #[getter]
+    fn bos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).bos_token.clone())
+    }
+
+    #[getter]
+    fn eos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).eos_token.clone())
+    }
+
+    #[getter]
+    fn sep_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).sep_token.clone())
+    }
+
+    #[getter]
+    fn cls_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).cls_token.clone())
+    }
+
+    #[getter]
+    fn mask_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).mask_token.clone())
+    }
+
+    #[getter]
+    fn additional_special_tokens(&self) -> PyResult<Vec<String>> {
+        Ok(<Self as PyTokenizer<Gpt2Tokenizer, Gpt2Vocab>>::special_token_map(&self).additional_special_tokens.clone())
     }
 }
 
 #[pyclass(module = "rust_tokenizers")]
 struct PyRobertaTokenizer {
-    tokenizer: RobertaTokenizer,
+    tokenizer: RwLock<Arc<RobertaTokenizer>>,
 }
 
 impl PyTokenizer<RobertaTokenizer, RobertaVocab> for PyRobertaTokenizer {
-    fn tokenizer(&self) -> &RobertaTokenizer {
-        &self.tokenizer
+    fn tokenizer(&self) -> Arc<RobertaTokenizer> {
+        self.tokenizer.read().unwrap().clone()
     }
 }
 
+impl PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab> for PyRobertaTokenizer {}
+
 #[pymethods]
 impl PyRobertaTokenizer {
     #[new]
-    fn new(obj: &PyRawObject, vocab_path: String, merges_path: String, do_lower_case: bool) {
-        obj.init(PyRobertaTokenizer {
-            tokenizer: RobertaTokenizer::from_file(vocab_path.as_str(), &merges_path.as_str(), do_lower_case),
-        });
+    fn new(obj: &PyRawObject, vocab: &PyAny, merges: &PyAny, do_lower_case: bool) -> PyResult<()> {
+        let vocab = read_vocab::<RobertaVocab>(vocab)?;
+        let merges = read_merges(merges)?;
+        let tokenizer = RobertaTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), do_lower_case);
+        obj.init(PyRobertaTokenizer { tokenizer: RwLock::new(Arc::new(tokenizer)) });
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> PyResult<BpeState> {
+        Ok((placeholder_vocab::<RobertaVocab>(), vec!(), false))
+    }
+
+    fn __getstate__(&self) -> PyResult<BpeState> {
+        let tokenizer = self.tokenizer();
+        Ok((Tokenizer::vocab(tokenizer.as_ref()).values().clone(), merges_to_list(tokenizer.merges()), tokenizer.lower_case()))
+    }
+
+    fn __setstate__(&self, state: BpeState) -> PyResult<()> {
+        let (vocab, merges, do_lower_case) = state;
+        let tokenizer = RobertaTokenizer::from_existing_vocab_and_merges(values_to_vocab(vocab)?, Arc::new(merges_from_list(merges)), do_lower_case);
+        *self.tokenizer.write().unwrap() = Arc::new(tokenizer);
+        Ok(())
     }
 
     fn tokenize(&self, text: &str) -> PyResult<Vec<String>> {
@@ -352,44 +817,149 @@ impl PyRobertaTokenizer {
     }
 
     fn tokenize_list(&self, text_list: Vec<&str>) -> PyResult<Vec<Vec<String>>> {
-        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::tokenize_list(&self, text_list)
+        <Self as PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab>>::tokenize_list(&self, text_list)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::encode(&self, text, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
     }
 
-    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::encode(&self, text, max_len, truncation_strategy, stride)
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
     }
 
-    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride)
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list_numpy(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab>>::encode_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list_numpy(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab>>::encode_pair_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<String> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::decode(&self, token_ids, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        <Self as PyMultiThreadTokenizer<RobertaTokenizer, RobertaVocab>>::decode_list(&self, token_ids_list, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    fn convert_tokens_to_ids(&self, tokens: Vec<String>) -> PyResult<Vec<i64>> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::convert_tokens_to_ids(&self, tokens)
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> PyResult<String> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::convert_tokens_to_string(&self, tokens)
+    }
+
+    fn token_to_id(&self, token: &str) -> PyResult<i64> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::token_to_id(&self, token)
+    }
+
+    fn id_to_token(&self, id: i64) -> PyResult<String> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::id_to_token(&self, id)
+    }
+
+    #[getter]
+    fn vocab_size(&self) -> PyResult<usize> {
+        <Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::vocab_size(&self)
+    }
+
+    #[getter]
+    fn unk_token(&self) -> PyResult<String> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).unk_token.clone())
+    }
+
+    #[getter]
+    fn pad_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).pad_token.clone())
+    }
+
+    #[getter]
+    fn bos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).bos_token.clone())
+    }
+
+    #[getter]
+    fn eos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).eos_token.clone())
+    }
+
+    #[getter]
+    fn sep_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).sep_token.clone())
+    }
+
+    #[getter]
+    fn cls_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).cls_token.clone())
+    }
+
+    #[getter]
+    fn mask_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).mask_token.clone())
+    }
+
+    #[getter]
+    fn additional_special_tokens(&self) -> PyResult<Vec<String>> {
+        Ok(<Self as PyTokenizer<RobertaTokenizer, RobertaVocab>>::special_token_map(&self).additional_special_tokens.clone())
     }
 }
 
 #[pyclass(module = "rust_tokenizers")]
 struct PyOpenAiGptTokenizer {
-    tokenizer: OpenAiGptTokenizer,
+    tokenizer: RwLock<Arc<OpenAiGptTokenizer>>,
 }
 
 impl PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab> for PyOpenAiGptTokenizer {
-    fn tokenizer(&self) -> &OpenAiGptTokenizer {
-        &self.tokenizer
+    fn tokenizer(&self) -> Arc<OpenAiGptTokenizer> {
+        self.tokenizer.read().unwrap().clone()
     }
 }
 
+impl PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab> for PyOpenAiGptTokenizer {}
+
 #[pymethods]
 impl PyOpenAiGptTokenizer {
     #[new]
-    fn new(obj: &PyRawObject, vocab_path: String, merges_path: String, do_lower_case: bool) {
-        obj.init(PyOpenAiGptTokenizer {
-            tokenizer: OpenAiGptTokenizer::from_file(vocab_path.as_str(), merges_path.as_str(), do_lower_case),
-        });
+    fn new(obj: &PyRawObject, vocab: &PyAny, merges: &PyAny, do_lower_case: bool) -> PyResult<()> {
+        let vocab = read_vocab::<OpenAiGptVocab>(vocab)?;
+        let merges = read_merges(merges)?;
+        let tokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(Arc::new(vocab), Arc::new(merges), do_lower_case);
+        obj.init(PyOpenAiGptTokenizer { tokenizer: RwLock::new(Arc::new(tokenizer)) });
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> PyResult<BpeState> {
+        Ok((placeholder_vocab::<OpenAiGptVocab>(), vec!(), false))
+    }
+
+    fn __getstate__(&self) -> PyResult<BpeState> {
+        let tokenizer = self.tokenizer();
+        Ok((Tokenizer::vocab(tokenizer.as_ref()).values().clone(), merges_to_list(tokenizer.merges()), tokenizer.lower_case()))
+    }
+
+    fn __setstate__(&self, state: BpeState) -> PyResult<()> {
+        let (vocab, merges, do_lower_case) = state;
+        let tokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(values_to_vocab(vocab)?, Arc::new(merges_from_list(merges)), do_lower_case);
+        *self.tokenizer.write().unwrap() = Arc::new(tokenizer);
+        Ok(())
     }
 
     fn tokenize(&self, text: &str) -> PyResult<Vec<String>> {
@@ -397,23 +967,108 @@ impl PyOpenAiGptTokenizer {
     }
 
     fn tokenize_list(&self, text_list: Vec<&str>) -> PyResult<Vec<Vec<String>>> {
-        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::tokenize_list(&self, text_list)
+        <Self as PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::tokenize_list(&self, text_list)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode(&self, text, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyTokenizedInput> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"do_not_pad\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<Vec<PyTokenizedInput>> {
+        <Self as PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_list_numpy(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(padding_strategy = "\"longest\"", padding_side = "\"right\"", pad_to_multiple_of = "8", head = "0")]
+    fn encode_pair_list_numpy(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize, padding_strategy: &str, padding_side: &str, pad_to_multiple_of: usize, head: usize) -> PyResult<PyObject> {
+        <Self as PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_pair_list_numpy(&self, text_list, max_len, truncation_strategy, stride, padding_strategy, padding_side, pad_to_multiple_of, head)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode(&self, token_ids: Vec<i64>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<String> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::decode(&self, token_ids, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    #[args(skip_special_tokens = "false", clean_up_tokenization_spaces = "true")]
+    fn decode_list(&self, token_ids_list: Vec<Vec<i64>>, skip_special_tokens: bool, clean_up_tokenization_spaces: bool) -> PyResult<Vec<String>> {
+        <Self as PyMultiThreadTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::decode_list(&self, token_ids_list, skip_special_tokens, clean_up_tokenization_spaces)
+    }
+
+    fn convert_tokens_to_ids(&self, tokens: Vec<String>) -> PyResult<Vec<i64>> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::convert_tokens_to_ids(&self, tokens)
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> PyResult<String> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::convert_tokens_to_string(&self, tokens)
+    }
+
+    fn token_to_id(&self, token: &str) -> PyResult<i64> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::token_to_id(&self, token)
+    }
+
+    fn id_to_token(&self, id: i64) -> PyResult<String> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::id_to_token(&self, id)
+    }
+
+    #[getter]
+    fn vocab_size(&self) -> PyResult<usize> {
+        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::vocab_size(&self)
+    }
+
+    #[getter]
+    fn unk_token(&self) -> PyResult<String> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).unk_token.clone())
+    }
+
+    #[getter]
+    fn pad_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).pad_token.clone())
+    }
+
+    #[getter]
+    fn bos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).bos_token.clone())
+    }
+
+    #[getter]
+    fn eos_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).eos_token.clone())
     }
 
-    fn encode(&self, text: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode(&self, text, max_len, truncation_strategy, stride)
+    #[getter]
+    fn sep_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).sep_token.clone())
     }
 
-    fn encode_pair(&self, text_a: &str, text_b: &str, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<PyTokenizedInput> {
-        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_pair(&self, text_a, text_b, max_len, truncation_strategy, stride)
+    #[getter]
+    fn cls_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).cls_token.clone())
     }
 
-    fn encode_list(&self, text_list: Vec<&str>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[getter]
+    fn mask_token(&self) -> PyResult<Option<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).mask_token.clone())
     }
 
-    fn encode_pair_list(&self, text_list: Vec<(&str, &str)>, max_len: usize, truncation_strategy: &str, stride: usize) -> PyResult<Vec<PyTokenizedInput>> {
-        <Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::encode_pair_list(&self, text_list, max_len, truncation_strategy, stride)
+    #[getter]
+    fn additional_special_tokens(&self) -> PyResult<Vec<String>> {
+        Ok(<Self as PyTokenizer<OpenAiGptTokenizer, OpenAiGptVocab>>::special_token_map(&self).additional_special_tokens.clone())
     }
 }
 
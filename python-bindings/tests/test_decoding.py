@@ -0,0 +1,96 @@
+# Copyright 2018 The HuggingFace Inc. team.
+# Copyright 2019 Guillaume Becquin
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#     http://www.apache.org/licenses/LICENSE-2.0
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import tempfile
+from pathlib import Path
+from transformers.file_utils import get_from_cache
+from transformers.tokenization_bert import BertTokenizer
+from transformers.tokenization_gpt2 import GPT2Tokenizer
+from rust_tokenizers import PyBertTokenizer, PyGpt2Tokenizer
+
+
+class TestDecoding:
+    def setup_class(self):
+        self.test_dir = Path(tempfile.mkdtemp())
+        self.sentence_list = ['For instance, on the planet Earth, man had always assumed that he was more intelligent '
+                              'than dolphins because he had achieved so much.',
+                              'But conversely, the dolphins had always believed that they were far more intelligent '
+                              'than man—for precisely the same reasons.']
+
+    def test_decoding_bert(self):
+        # Given
+        self.base_tokenizer = BertTokenizer.from_pretrained('bert-base-uncased', do_lower_case=True,
+                                                            cache_dir=self.test_dir)
+        self.rust_tokenizer = PyBertTokenizer(
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['bert-base-uncased']),
+            do_lower_case=True)
+        features = self.rust_tokenizer.encode_list(self.sentence_list, max_len=128, truncation_strategy='longest_first',
+                                                   stride=0)
+
+        # When
+        output_rust = self.rust_tokenizer.decode_list([f.token_ids for f in features], skip_special_tokens=True)
+
+        # Then
+        for rust, feature in zip(output_rust, features):
+            assert (rust == self.base_tokenizer.decode(feature.token_ids, skip_special_tokens=True))
+            assert (self.rust_tokenizer.decode(feature.token_ids) == self.base_tokenizer.decode(feature.token_ids))
+        tokens = self.rust_tokenizer.tokenize(self.sentence_list[0])
+        assert (self.rust_tokenizer.convert_tokens_to_ids(tokens) == self.base_tokenizer.convert_tokens_to_ids(tokens))
+        assert (self.rust_tokenizer.convert_tokens_to_string(tokens) ==
+                self.base_tokenizer.convert_tokens_to_string(tokens))
+        assert (self.rust_tokenizer.token_to_id('[CLS]') == self.base_tokenizer.cls_token_id)
+        assert (self.rust_tokenizer.id_to_token(self.base_tokenizer.sep_token_id) == '[SEP]')
+        assert (self.rust_tokenizer.vocab_size == self.base_tokenizer.vocab_size)
+        assert (self.rust_tokenizer.unk_token == self.base_tokenizer.unk_token)
+        assert (self.rust_tokenizer.pad_token == self.base_tokenizer.pad_token)
+        assert (self.rust_tokenizer.cls_token == self.base_tokenizer.cls_token)
+        assert (self.rust_tokenizer.sep_token == self.base_tokenizer.sep_token)
+        assert (self.rust_tokenizer.mask_token == self.base_tokenizer.mask_token)
+
+    def test_decoding_gpt2(self):
+        # Given
+        self.base_tokenizer = GPT2Tokenizer.from_pretrained('gpt2', do_lower_case=False, cache_dir=self.test_dir)
+        self.rust_tokenizer = PyGpt2Tokenizer(
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['gpt2']),
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['merges_file']['gpt2']),
+            do_lower_case=False)
+        features = self.rust_tokenizer.encode_list(self.sentence_list, max_len=128, truncation_strategy='longest_first',
+                                                   stride=0)
+
+        # When
+        output_rust = self.rust_tokenizer.decode_list([f.token_ids for f in features], clean_up_tokenization_spaces=False)
+
+        # Then
+        for rust, sentence in zip(output_rust, self.sentence_list):
+            assert (rust == sentence)
+        assert (self.rust_tokenizer.token_to_id('<|endoftext|>') == self.base_tokenizer.eos_token_id)
+        assert (self.rust_tokenizer.vocab_size == self.base_tokenizer.vocab_size)
+        assert (self.rust_tokenizer.unk_token == self.base_tokenizer.unk_token)
+        assert (self.rust_tokenizer.bos_token == self.base_tokenizer.bos_token)
+        assert (self.rust_tokenizer.eos_token == self.base_tokenizer.eos_token)
+        assert (self.rust_tokenizer.cls_token is None)
+
+    def test_decoding_gpt2_partial_character(self):
+        # Given
+        self.base_tokenizer = GPT2Tokenizer.from_pretrained('gpt2', do_lower_case=False, cache_dir=self.test_dir)
+        self.rust_tokenizer = PyGpt2Tokenizer(
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['gpt2']),
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['merges_file']['gpt2']),
+            do_lower_case=False)
+        token_ids = [self.rust_tokenizer.token_to_id('Hello'), self.rust_tokenizer.token_to_id('Ã')]
+
+        # When
+        output_rust = self.rust_tokenizer.decode(token_ids)
+
+        # Then
+        assert (output_rust == 'Hello�')
+        assert (self.rust_tokenizer.convert_tokens_to_string(['Ã', '©']) == 'é')
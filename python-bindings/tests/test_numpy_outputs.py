@@ -0,0 +1,72 @@
+# Copyright 2018 The HuggingFace Inc. team.
+# Copyright 2019 Guillaume Becquin
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#     http://www.apache.org/licenses/LICENSE-2.0
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import tempfile
+from pathlib import Path
+import numpy as np
+import pytest
+from transformers.file_utils import get_from_cache
+from transformers.tokenization_bert import BertTokenizer
+from rust_tokenizers import PyBertTokenizer
+
+
+class TestNumpyOutputs:
+    def setup_class(self):
+        self.test_dir = Path(tempfile.mkdtemp())
+        self.sentence_list = ['For instance, on the planet Earth, man had always assumed that he was more intelligent '
+                              'than dolphins because he had achieved so much.',
+                              'But conversely, the dolphins had always believed that they were far more intelligent '
+                              'than man—for precisely the same reasons.',
+                              'Hello world!']
+        self.base_tokenizer = BertTokenizer.from_pretrained('bert-base-uncased', do_lower_case=True,
+                                                            cache_dir=self.test_dir)
+        self.rust_tokenizer = PyBertTokenizer(
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['bert-base-uncased']),
+            do_lower_case=True)
+
+    def test_encode_list_numpy(self):
+        # Given
+        features = self.rust_tokenizer.encode_list(self.sentence_list, max_len=128, truncation_strategy='longest_first',
+                                                   stride=0, padding_strategy='longest')
+
+        # When
+        output = self.rust_tokenizer.encode_list_numpy(self.sentence_list, max_len=128,
+                                                       truncation_strategy='longest_first', stride=0)
+
+        # Then
+        assert (output['token_ids'].shape == (len(self.sentence_list), len(features[0].token_ids)))
+        assert (output['token_ids'].dtype == np.int64)
+        assert (np.array_equal(output['token_ids'], np.array([f.token_ids for f in features])))
+        assert (np.array_equal(output['attention_mask'], np.array([f.attention_mask for f in features])))
+        assert (np.array_equal(output['segment_ids'], np.array([f.segment_ids for f in features])))
+        assert (np.array_equal(output['special_tokens_mask'], np.array([f.special_tokens_mask for f in features])))
+
+    def test_encode_pair_list_numpy(self):
+        # Given
+        pairs = list(zip(self.sentence_list, reversed(self.sentence_list)))
+        features = self.rust_tokenizer.encode_pair_list(pairs, max_len=64, truncation_strategy='longest_first',
+                                                        stride=0, padding_strategy='max_length')
+
+        # When
+        output = self.rust_tokenizer.encode_pair_list_numpy(pairs, max_len=64, truncation_strategy='longest_first',
+                                                            stride=0, padding_strategy='max_length')
+
+        # Then
+        assert (output['token_ids'].shape == (len(pairs), 64))
+        assert (np.array_equal(output['token_ids'], np.array([f.token_ids for f in features])))
+        assert (np.array_equal(output['segment_ids'], np.array([f.segment_ids for f in features])))
+
+    def test_encode_list_numpy_requires_padding(self):
+        # When & Then
+        with pytest.raises(ValueError):
+            self.rust_tokenizer.encode_list_numpy(self.sentence_list, max_len=128, truncation_strategy='longest_first',
+                                                  stride=0, padding_strategy='do_not_pad')
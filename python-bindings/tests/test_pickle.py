@@ -0,0 +1,73 @@
+# Copyright 2018 The HuggingFace Inc. team.
+# Copyright 2019 Guillaume Becquin
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#     http://www.apache.org/licenses/LICENSE-2.0
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import tempfile
+from pathlib import Path
+import json
+import pickle
+from transformers.file_utils import get_from_cache
+from transformers.tokenization_bert import BertTokenizer
+from transformers.tokenization_gpt2 import GPT2Tokenizer
+from rust_tokenizers import PyBertTokenizer, PyGpt2Tokenizer
+
+
+class TestPickle:
+    def setup_class(self):
+        self.test_dir = Path(tempfile.mkdtemp())
+        self.sentence_list = ['For instance, on the planet Earth, man had always assumed that he was more intelligent '
+                              'than dolphins because he had achieved so much.',
+                              'But conversely, the dolphins had always believed that they were far more intelligent '
+                              'than man—for precisely the same reasons.']
+
+    def encode(self, tokenizer):
+        return [f.token_ids for f in tokenizer.encode_list(self.sentence_list, max_len=128,
+                                                           truncation_strategy='longest_first', stride=0)]
+
+    def test_bert_in_memory_and_pickle(self):
+        # Given
+        self.base_tokenizer = BertTokenizer.from_pretrained('bert-base-uncased', do_lower_case=True,
+                                                            cache_dir=self.test_dir)
+        vocab_path = get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['bert-base-uncased'])
+        with open(vocab_path, encoding='utf-8') as f:
+            vocab = {token.strip(): index for index, token in enumerate(f)}
+        path_tokenizer = PyBertTokenizer(vocab_path, do_lower_case=True)
+
+        # When
+        memory_tokenizer = PyBertTokenizer(vocab, do_lower_case=True)
+        unpickled_tokenizer = pickle.loads(pickle.dumps(path_tokenizer))
+
+        # Then
+        expected = self.encode(path_tokenizer)
+        assert (self.encode(memory_tokenizer) == expected)
+        assert (self.encode(unpickled_tokenizer) == expected)
+        assert (unpickled_tokenizer.vocab_size == path_tokenizer.vocab_size)
+
+    def test_gpt2_in_memory_and_pickle(self):
+        # Given
+        self.base_tokenizer = GPT2Tokenizer.from_pretrained('gpt2', do_lower_case=False, cache_dir=self.test_dir)
+        vocab_path = get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['gpt2'])
+        merges_path = get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['merges_file']['gpt2'])
+        with open(vocab_path, encoding='utf-8') as f:
+            vocab = json.load(f)
+        with open(merges_path, encoding='utf-8') as f:
+            merges = [tuple(line.split()) for line in f.read().split('\n')[1:] if len(line.split()) == 2]
+        path_tokenizer = PyGpt2Tokenizer(vocab_path, merges_path, do_lower_case=False)
+
+        # When
+        memory_tokenizer = PyGpt2Tokenizer(vocab, merges, do_lower_case=False)
+        unpickled_tokenizer = pickle.loads(pickle.dumps(path_tokenizer))
+
+        # Then
+        expected = self.encode(path_tokenizer)
+        assert (self.encode(memory_tokenizer) == expected)
+        assert (self.encode(unpickled_tokenizer) == expected)
+        assert (unpickled_tokenizer.__getstate__() == path_tokenizer.__getstate__())
@@ -0,0 +1,50 @@
+# Copyright 2018 The HuggingFace Inc. team.
+# Copyright 2019 Guillaume Becquin
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#     http://www.apache.org/licenses/LICENSE-2.0
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import tempfile
+from pathlib import Path
+from concurrent.futures import ThreadPoolExecutor
+from transformers.file_utils import get_from_cache
+from transformers.tokenization_gpt2 import GPT2Tokenizer
+from rust_tokenizers import PyGpt2Tokenizer
+
+
+class TestThreading:
+    def setup_class(self):
+        self.test_dir = Path(tempfile.mkdtemp())
+        self.sentence_list = ['For instance, on the planet Earth, man had always assumed that he was more intelligent '
+                              'than dolphins because he had achieved so much.',
+                              'But conversely, the dolphins had always believed that they were far more intelligent '
+                              'than man—for precisely the same reasons.'] * 200
+
+    def test_concurrent_batches_gpt2(self):
+        # Given
+        self.base_tokenizer = GPT2Tokenizer.from_pretrained('gpt2', do_lower_case=False, cache_dir=self.test_dir)
+        self.rust_tokenizer = PyGpt2Tokenizer(
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['gpt2']),
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['merges_file']['gpt2']),
+            do_lower_case=False)
+        expected = [f.token_ids for f in self.rust_tokenizer.encode_list(self.sentence_list, max_len=128,
+                                                                         truncation_strategy='longest_first',
+                                                                         stride=0)]
+
+        # When
+        def encode_batch(_):
+            return self.rust_tokenizer.encode_list(self.sentence_list, max_len=128,
+                                                   truncation_strategy='longest_first', stride=0)
+
+        with ThreadPoolExecutor(max_workers=4) as executor:
+            outputs = list(executor.map(encode_batch, range(8)))
+
+        # Then
+        for features in outputs:
+            assert ([f.token_ids for f in features] == expected)
@@ -0,0 +1,47 @@
+# Copyright 2018 The HuggingFace Inc. team.
+# Copyright 2019 Guillaume Becquin
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#     http://www.apache.org/licenses/LICENSE-2.0
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+import tempfile
+from pathlib import Path
+import pytest
+from transformers.file_utils import get_from_cache
+from transformers.tokenization_bert import BertTokenizer
+from rust_tokenizers import PyBertTokenizer
+
+
+class TestTruncation:
+    def setup_class(self):
+        self.test_dir = Path(tempfile.mkdtemp())
+        self.sentence = 'For instance, on the planet Earth, man had always assumed that he was more intelligent ' \
+                        'than dolphins because he had achieved so much.'
+        self.base_tokenizer = BertTokenizer.from_pretrained('bert-base-uncased', do_lower_case=True,
+                                                            cache_dir=self.test_dir)
+        self.rust_tokenizer = PyBertTokenizer(
+            get_from_cache(self.base_tokenizer.pretrained_vocab_files_map['vocab_file']['bert-base-uncased']),
+            do_lower_case=True)
+
+    def test_head_tail_truncation(self):
+        # Given
+        full_ids = self.rust_tokenizer.encode(self.sentence, max_len=128, truncation_strategy='longest_first',
+                                              stride=0).token_ids
+
+        # When
+        features = self.rust_tokenizer.encode_list([self.sentence], max_len=12, truncation_strategy='head_tail',
+                                                   stride=0, head=4)
+
+        # Then
+        assert (features[0].token_ids == full_ids[:5] + full_ids[-7:])
+
+    def test_invalid_truncation_strategy(self):
+        # When & Then
+        with pytest.raises(ValueError):
+            self.rust_tokenizer.encode(self.sentence, max_len=12, truncation_strategy='middle', stride=0)